@@ -0,0 +1,27 @@
+// Messages posted to the window by the embedding page, waiting to be read
+// by rusty-code. Objects are sent as JSON, like:
+// iframe.contentWindow.postMessage({code: "fn main() {}", language: "rust", theme: "base16-ocean.dark"}, "*")
+var post_messages = [];
+
+window.addEventListener("message", function (event) {
+    if (typeof event.data === "string") {
+        post_messages.push(event.data);
+    } else {
+        post_messages.push(JSON.stringify(event.data));
+    }
+});
+
+post_message_register_js_plugin = function (importObject) {
+    importObject.env.rusty_code_next_message = function () {
+        if (post_messages.length == 0) {
+            return -1;
+        }
+        return js_object(post_messages.shift());
+    }
+}
+
+miniquad_add_plugin({
+    register_plugin: post_message_register_js_plugin,
+    name: "post_message",
+    version: "0.1.0"
+});
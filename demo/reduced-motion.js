@@ -0,0 +1,13 @@
+// Tells rusty-code whether the visitor has asked for less motion in the
+// settings of their system, to turn off animations like with --reduced-motion.
+reduced_motion_register_js_plugin = function (importObject) {
+    importObject.env.rusty_code_prefers_reduced_motion = function () {
+        return window.matchMedia("(prefers-reduced-motion: reduce)").matches;
+    }
+}
+
+miniquad_add_plugin({
+    register_plugin: reduced_motion_register_js_plugin,
+    name: "reduced_motion",
+    version: "0.1.0"
+});
@@ -0,0 +1,26 @@
+use clap::ValueEnum;
+
+/// Number of characters typed per second by the typewriter animation.
+const TYPEWRITER_CHARS_PER_SECOND: f32 = 40.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Animation {
+    /// Type out the code one character at a time
+    Typewriter,
+}
+
+impl Animation {
+    /// Number of characters that are visible `elapsed` seconds into the animation.
+    pub fn visible_chars(&self, elapsed: f32) -> usize {
+        match self {
+            Animation::Typewriter => (elapsed.max(0.) * TYPEWRITER_CHARS_PER_SECOND) as usize,
+        }
+    }
+
+    /// Length in seconds of the animation for code with `char_count` characters.
+    pub fn duration(&self, char_count: usize) -> f32 {
+        match self {
+            Animation::Typewriter => char_count as f32 / TYPEWRITER_CHARS_PER_SECOND,
+        }
+    }
+}
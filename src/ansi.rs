@@ -0,0 +1,154 @@
+use {
+    crate::{
+        code::Code,
+        code_theme::{hex_color, CodeTheme},
+        codebox::FALLBACK_CODE_THEME,
+        line_ranges::LineRanges,
+    },
+    macroquad::prelude::*,
+    nanoserde::DeJson,
+    std::{fmt::Write, fs, path::Path},
+    syntect::{
+        easy::HighlightLines,
+        highlighting::{self, FontStyle, ThemeSet},
+        parsing::SyntaxSet,
+        util::LinesWithEndings,
+    },
+};
+
+const RESET: &str = "\x1b[0m";
+
+/// The name of the syntax highlighting theme in a rusty-slider theme file.
+#[derive(DeJson)]
+struct ThemeFile {
+    #[nserde(default)]
+    code_theme: String,
+}
+
+/// Load the code theme and the syntax highlighting theme with `std::fs`
+/// instead of Macroquad, so that they can be used before any window is
+/// opened.
+pub fn load_themes(theme_path: &Path) -> (CodeTheme, highlighting::Theme) {
+    let name = theme_path.to_string_lossy();
+    let (code_theme, syntax_theme_name) = match CodeTheme::bundled(&name) {
+        Some(code_theme) => (code_theme, name.into_owned()),
+        None => {
+            let content = fs::read_to_string(theme_path).unwrap_or_default();
+            let syntax_theme_name = ThemeFile::deserialize_json(&content)
+                .map(|theme| theme.code_theme)
+                .unwrap_or_default();
+            (CodeTheme::parse(theme_path, content), syntax_theme_name)
+        }
+    };
+    let syntax_theme = code_theme.syntax_theme().unwrap_or_else(|| {
+        let mut theme_set = ThemeSet::load_defaults();
+        theme_set
+            .themes
+            .remove(&syntax_theme_name)
+            .unwrap_or_else(|| theme_set.themes.remove(FALLBACK_CODE_THEME).unwrap())
+    });
+    (code_theme, syntax_theme)
+}
+
+/// Highlight the code with syntect and render it with 24-bit ANSI color
+/// escape codes, for printing to a terminal. The terminal's own background
+/// is kept, except for highlighted lines.
+pub fn render_ansi(
+    code: &Code,
+    language: Option<String>,
+    syntax_set: &SyntaxSet,
+    syntax_theme: &highlighting::Theme,
+    code_theme: &CodeTheme,
+    line_numbers: bool,
+    highlight_lines: &LineRanges,
+) -> String {
+    let syntax = language
+        .and_then(|lang| syntax_set.find_syntax_by_token(&lang))
+        .or_else(|| {
+            code.extension()
+                .and_then(|extension| syntax_set.find_syntax_by_extension(&extension))
+        })
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, syntax_theme);
+    let background = hex_color(&code_theme.code_background_color);
+    let highlight = blend(hex_color(&code_theme.highlight_line_color), background);
+    let line_count = code.sourcecode.lines().count();
+    let digits = line_count.to_string().len();
+    let mut output = String::new();
+    for (index, line) in LinesWithEndings::from(&code.sourcecode).enumerate() {
+        // Lines outside the slice are still highlighted, since the syntax
+        // state carries over from one line to the next
+        let ranges = highlighter
+            .highlight_line(line, syntax_set)
+            .unwrap_or_default();
+        let number = index + 1;
+        if code.lines.is_some_and(|slice| !slice.contains(number)) {
+            continue;
+        }
+        if line_numbers {
+            let _ = write!(
+                output,
+                "{}{:>width$}  {}",
+                foreground(hex_color(&code_theme.gutter_text_color)),
+                number,
+                RESET,
+                width = digits
+            );
+        }
+        if highlight_lines.contains(number) {
+            output.push_str(&ansi_background(highlight));
+        }
+        for (style, text) in ranges {
+            let highlighting::Color { r, g, b, a } = style.foreground;
+            let text = text.trim_end_matches(['\r', '\n']);
+            let _ = write!(
+                output,
+                "{}{}{}",
+                font_style(style.font_style),
+                foreground(Color::from_rgba(r, g, b, a)),
+                text
+            );
+        }
+        output.push_str(RESET);
+        output.push('\n');
+    }
+    output
+}
+
+fn foreground(color: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = color.into();
+    format!("\x1b[38;2;{};{};{}m", r, g, b)
+}
+
+/// Turn bold, italic and underline on for the style, and off otherwise,
+/// keeping the background.
+fn font_style(style: FontStyle) -> String {
+    let mut codes = vec!["22", "23", "24"];
+    if style.contains(FontStyle::BOLD) {
+        codes.push("1");
+    }
+    if style.contains(FontStyle::ITALIC) {
+        codes.push("3");
+    }
+    if style.contains(FontStyle::UNDERLINE) {
+        codes.push("4");
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn ansi_background(color: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = color.into();
+    format!("\x1b[48;2;{};{};{}m", r, g, b)
+}
+
+/// Terminal colors are opaque, so translucent colors are blended with the
+/// background of the code box.
+fn blend(color: Color, background: Color) -> Color {
+    let mix = |value: f32, background: f32| value * color.a + background * (1. - color.a);
+    Color::new(
+        mix(color.r, background.r),
+        mix(color.g, background.g),
+        mix(color.b, background.b),
+        1.,
+    )
+}
@@ -0,0 +1,1936 @@
+//! The rusty-code app, which shows the code in a window and reacts to the
+//! keyboard, the mouse and remote commands, or exports it and exits.
+//!
+//! The `rusty-code` binary runs it with the options from the command line.
+//! Other binaries may run it with their own overlays:
+//!
+//! ```no_run
+//! use rusty_code::{app, overlay::Renderer};
+//!
+//! let renderers: Vec<Box<dyn Renderer>> = Vec::new();
+//! app::run(renderers);
+//! ```
+
+#[cfg(feature = "scripting")]
+use crate::script;
+#[cfg(target_arch = "wasm32")]
+use crate::{post_message, reduced_motion};
+use rusty_slider::prelude::Theme;
+use std::path::{Path, PathBuf};
+use {
+    crate::{
+        animation::Animation,
+        audio::{BackgroundAudio, TypingSound, KEYSTROKE_SOUND},
+        auto_advance::{AutoAdvance, Interval},
+        background::Background,
+        code::{file_name, playground_gist_id, PLAYGROUND_FILENAME},
+        code_theme::{parse_hex_color, CodeTheme, BUNDLED_THEMES},
+        codebox::{CodeBox, CodeBoxBuilder, Highlighter},
+        colorblind::Colorblind,
+        compare::{self, GridLayout},
+        diagnostics::Diagnostics,
+        folding,
+        goto::Goto,
+        keys,
+        line_ranges::{LinePosition, LineRanges, LineSlice},
+        minimap,
+        notes::Notes,
+        overlay::{self, Frame, Overlay, Renderer},
+        progress::Progress,
+        renderer::{load_themes, DEFAULT_THEME},
+        scroll::{Scroll, SCROLL_MARGIN},
+        search::Search,
+        selection::Selection,
+        slides::{Slide, Slides},
+        tabs::TabBar,
+        timer::Timer,
+        toast::Toast,
+        transition::{SlideTransition, Transition},
+        watermark::Watermark,
+        Code, CodeError, CodeRenderer, CodeSource, Gist, Result,
+    },
+    clap::Parser,
+    macroquad::prelude::*,
+    quad_url::get_program_parameters,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::{
+        ansi,
+        codebox::{load_syntax_set, BlameLine, LineChange},
+        export, git,
+        godbolt::CompilerExplorer,
+        lsp, remote,
+        run::{self, Runner},
+        server, watcher,
+    },
+    std::collections::HashMap,
+};
+
+/// Name of the file shown on the slide, for its tab.
+fn slide_title(slide: &Slide) -> String {
+    slide
+        .code
+        .as_ref()
+        .map(|code| file_name(Path::new(&code.filename)))
+        .unwrap_or_else(|| slide.source.filename())
+}
+
+/// Load the code from the source and build a slide to display it.
+async fn load_slide(opt: &CliOptions, renderer: &CodeRenderer, source: CodeSource) -> Slide {
+    let (code, codebox) = match Code::load(source.clone()).await {
+        #[allow(unused_mut)]
+        Ok(mut code) => {
+            #[cfg(not(target_arch = "wasm32"))]
+            if opt.blame {
+                code.blame = blame(&code);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if opt.git_gutter {
+                code.git_changes = git_changes(&code);
+            }
+            let codebox = build_codebox(opt, renderer, &code);
+            (Some(code), Ok(codebox))
+        }
+        Err(e) => (None, Err(e)),
+    };
+    Slide {
+        source,
+        code,
+        codebox,
+    }
+}
+
+/// Build a slide to display code sent to the program while it runs, in the
+/// language given with it.
+fn code_slide(
+    opt: &CliOptions,
+    renderer: &CodeRenderer,
+    sourcecode: String,
+    language: Option<String>,
+) -> Slide {
+    let mut code = Code::from_sourcecode(sourcecode);
+    code.language = language;
+    let codebox = build_codebox(opt, renderer, &code);
+    Slide {
+        source: CodeSource::Code(code.sourcecode.clone()),
+        code: Some(code),
+        codebox: Ok(codebox),
+    }
+}
+
+/// Ask the language server for the semantic tokens of the slide, if its
+/// code is loaded from a file, and return whether they were asked for. The
+/// code is shown with only syntax highlighting until they arrive.
+#[cfg(not(target_arch = "wasm32"))]
+fn request_semantic_tokens(
+    client: Option<&lsp::SemanticTokensClient>,
+    opt: &CliOptions,
+    slide: &Slide,
+) -> bool {
+    let (Some(client), Some(code)) = (client, &slide.code) else {
+        return false;
+    };
+    if !Path::new(&code.filename).is_file() {
+        return false;
+    }
+    client.request(
+        code.filename.clone(),
+        code.sourcecode.clone(),
+        code.language(opt.language.clone()).unwrap_or_default(),
+    );
+    true
+}
+
+/// Color the code of the slides that the semantic tokens were asked for,
+/// unless the code has changed since. Errors are logged, and the code keeps
+/// only syntax highlighting.
+#[cfg(not(target_arch = "wasm32"))]
+fn apply_semantic_tokens(
+    opt: &CliOptions,
+    renderer: &CodeRenderer,
+    slides: &mut Slides,
+    response: lsp::TokensResponse,
+) {
+    let tokens = match response.tokens {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            error!(
+                "Couldn't get semantic tokens for {}: {}",
+                response.filename, e
+            );
+            return;
+        }
+    };
+    for slide in slides.iter_mut() {
+        let Some(code) = slide.code.as_mut().filter(|code| {
+            code.filename == response.filename && code.sourcecode == response.sourcecode
+        }) else {
+            continue;
+        };
+        code.semantic_tokens = tokens.clone();
+        slide.codebox = Ok(build_codebox(opt, renderer, code));
+    }
+}
+
+/// Blame the lines of code loaded from a file in a git repository.
+#[cfg(not(target_arch = "wasm32"))]
+fn blame(code: &Code) -> Vec<BlameLine> {
+    let path = Path::new(&code.filename);
+    if !path.is_file() {
+        return Vec::new();
+    }
+    git::blame(path).unwrap_or_else(|e| {
+        error!("Couldn't blame {}: {}", code.filename, e);
+        Vec::new()
+    })
+}
+
+/// Lines changed since the last commit of code loaded from a file in a git
+/// repository.
+#[cfg(not(target_arch = "wasm32"))]
+fn git_changes(code: &Code) -> HashMap<usize, LineChange> {
+    let path = Path::new(&code.filename);
+    if !path.is_file() {
+        return HashMap::new();
+    }
+    git::changes(path).unwrap_or_else(|e| {
+        error!("Couldn't diff {} against HEAD: {}", code.filename, e);
+        HashMap::new()
+    })
+}
+
+fn build_codebox(opt: &CliOptions, renderer: &CodeRenderer, code: &Code) -> CodeBox {
+    codebox_builder(opt, renderer, code)
+        .build_draw_box(code.language(opt.language.clone()), code.sourcecode.clone())
+}
+
+/// Builder of the code box with the options, to set more of them before
+/// building it.
+fn codebox_builder(opt: &CliOptions, renderer: &CodeRenderer, code: &Code) -> CodeBoxBuilder {
+    renderer
+        .builder()
+        .line_numbers(opt.line_numbers)
+        .highlight_lines(opt.highlight_lines.clone().unwrap_or_default())
+        .line_slice(opt.lines.or(code.lines))
+        .wrap(opt.wrap.then(|| opt.canvas_width() - opt.margin() * 2.))
+        .font_size(opt.font_size)
+        .padding(opt.padding)
+        .highlighter(opt.highlighter)
+        .extension(code.extension())
+        .tab_width(opt.tab_width)
+        .show_whitespace(opt.show_whitespace)
+        .highlight_trailing_whitespace(opt.highlight_trailing_whitespace)
+        .indent_guides(opt.indent_guides)
+        .rulers(opt.ruler.clone())
+        .rainbow_brackets(opt.rainbow_brackets)
+        .strip_comments(opt.strip_comments)
+        .semantic_tokens(code.semantic_tokens.clone())
+        .blame(code.blame.clone())
+        .git_changes(code.git_changes.clone())
+        .folds(code.folds.hidden())
+        .header(
+            opt.caption
+                .clone()
+                .or_else(|| opt.header.then(|| file_name(Path::new(&code.filename)))),
+        )
+        .window_controls(opt.window_controls)
+        .high_contrast(opt.high_contrast)
+        .colorblind(opt.colorblind)
+        .scale(opt.scale())
+}
+
+/// What to do with an image of the code box, rendered at the end of the
+/// frame.
+#[cfg(not(target_arch = "wasm32"))]
+enum Capture {
+    /// Save it to a file in the working directory
+    Screenshot,
+    /// Put it on the clipboard
+    Clipboard,
+}
+
+/// Scroll to the position in the code box and flash it, or warn if the line
+/// isn't displayed.
+#[cfg(any(not(target_arch = "wasm32"), feature = "scripting"))]
+fn jump_to(codebox: &CodeBox, position: LinePosition, scroll: &mut Scroll) -> Option<Goto> {
+    let goto = Goto::new(codebox, position);
+    match &goto {
+        Some(goto) => goto.scroll_to(codebox, scroll),
+        None => warn!("Line {} isn't displayed", position.line),
+    }
+    goto
+}
+
+/// Switch the renderer to the themes, logging an error and keeping the
+/// current themes if their fonts can't be loaded.
+async fn switch_themes(renderer: &mut CodeRenderer, theme: Theme, code_theme: CodeTheme) {
+    if let Err(e) = renderer.set_themes(theme, code_theme).await {
+        error!("Couldn't load the fonts of the theme: {}", e);
+    }
+}
+
+/// Rebuild the code boxes of all loaded slides, after the theme has changed.
+fn rebuild_codeboxes(opt: &CliOptions, renderer: &CodeRenderer, slides: &mut Slides) {
+    for slide in slides.iter_mut() {
+        if let Some(code) = &slide.code {
+            slide.codebox = Ok(build_codebox(opt, renderer, code));
+        }
+    }
+}
+
+fn draw_error_message(message: String, font_size: u16) {
+    let text_dim = measure_text(&message, None, font_size, 1.0);
+    let xpos = screen_width() / 2. - text_dim.width / 2.;
+    let ypos = screen_height() / 2. - text_dim.height / 2.;
+    draw_text_ex(
+        &message,
+        xpos,
+        ypos,
+        TextParams {
+            font_size,
+            ..TextParams::default()
+        },
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(clap::Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Run an HTTP server that renders code posted as JSON to `/render` as PNG images
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+    },
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "rusty-code",
+    about = "A small tool to display sourcecode files"
+)]
+pub(crate) struct CliOptions {
+    #[cfg(not(target_arch = "wasm32"))]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Code to display, overrides both `filename` and `gist`
+    #[arg(short, long)]
+    pub code: Option<String>,
+    /// Display the code in the clipboard, overrides both `filename` and `gist`
+    #[arg(long)]
+    pub clipboard: bool,
+    /// Paths or glob patterns of sourcecode files to display, use `-` to read from stdin, a directory shows a list of files to pick from [default: assets/helloworld.rs]
+    #[arg(short, long, num_args = 1..)]
+    pub filename: Vec<PathBuf>,
+    /// Gist id to display, if set, will override `filename` option
+    #[arg(short, long)]
+    pub gist: Option<String>,
+    /// Name of file to display from a Gist with multiple files, shows a list to pick from if not set
+    #[arg(long)]
+    pub gist_file: Option<String>,
+    /// Revision SHA of the Gist to display, defaults to the latest revision
+    #[arg(long)]
+    pub gist_revision: Option<String>,
+    /// Rust Playground share link or id to display, press Shift+R to run the code on the playground
+    #[arg(long, value_name = "share")]
+    pub playground: Option<String>,
+    /// GitHub token used to load private Gists
+    #[arg(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    pub github_token: Option<String>,
+    /// GitHub file URL to display, line fragments like `#L10-L40` select the lines to show
+    #[arg(long)]
+    pub github: Option<String>,
+    /// URL of raw sourcecode to display, if set, will override `filename` option
+    #[arg(short, long)]
+    pub url: Option<String>,
+    /// Language of the code, if empty defaults to a Vim or Emacs modeline in the file, or the file extension.
+    #[arg(short, long)]
+    pub language: Option<String>,
+    /// Path to theme.json file, a TextMate `.tmTheme` color scheme, a base16 YAML scheme or the name of a bundled theme
+    #[arg(short, long, default_value = DEFAULT_THEME)]
+    pub theme: PathBuf,
+    /// GLSL fragment shader to draw the background with, given the uniforms `canvasSize` and `time`
+    #[arg(long)]
+    pub background_shader: Option<PathBuf>,
+    /// Color of the background, e.g. `#1e1e2e`, instead of the gradient
+    #[arg(long, value_name = "color", value_parser = parse_hex_color)]
+    pub background_color: Option<Color>,
+    /// Don't draw the gradient, leaving the background transparent
+    #[arg(long)]
+    pub no_gradient: bool,
+    /// Draw only the code box on a transparent background without margins, to composite it into a web page or an OBS scene
+    #[arg(long)]
+    pub embed: bool,
+    /// Title of the window, `{filename}` and `{language}` are replaced with the displayed file and its language
+    #[arg(long, default_value = "Rusty Code")]
+    pub title: String,
+    /// Start in a window instead of fullscreen
+    #[arg(long)]
+    pub windowed: bool,
+    /// Width of the window in pixels, implies `--windowed`
+    #[arg(long)]
+    pub width: Option<i32>,
+    /// Height of the window in pixels, implies `--windowed`
+    #[arg(long)]
+    pub height: Option<i32>,
+    /// Font size of the code, overrides the code font size of the theme
+    #[arg(long)]
+    pub font_size: Option<f32>,
+    /// Space between the code and the edge of the code box, in pixels before scaling
+    #[arg(long)]
+    pub padding: Option<f32>,
+    /// Space between the code box and the edge of the screen or exported image, in pixels
+    #[arg(long)]
+    pub margin: Option<f32>,
+    /// Scale fonts and padding by this factor, defaults to the DPI scale of the display
+    #[arg(long)]
+    pub scale: Option<f32>,
+    /// Key that toggles between fullscreen and windowed mode
+    #[arg(long, default_value = "F11", value_parser = keys::parse_key_code)]
+    pub fullscreen_key: KeyCode,
+    /// List the names of the bundled themes and exit
+    #[arg(long)]
+    pub list_themes: bool,
+    /// Read the code to display from stdin, same as `--filename -`
+    #[arg(long)]
+    pub stdin: bool,
+    /// Run a shell command and display what it prints, like `--command "kubectl get pods -o yaml" --language yaml`
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long = "command", value_name = "command")]
+    pub shell_command: Option<String>,
+    /// Show the author and age of each line of a file in a git repository in the gutter
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long)]
+    pub blame: bool,
+    /// Mark lines of a file in a git repository that were added, modified or removed since HEAD
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long)]
+    pub git_gutter: bool,
+    /// Show line numbers in a gutter next to the code
+    #[arg(short = 'n', long)]
+    pub line_numbers: bool,
+    /// Save the rendered code to a PNG, SVG, PDF, HTML or animated GIF file and exit
+    #[arg(short, long, group = "export")]
+    pub output: Option<PathBuf>,
+    /// Record the animation to an MP4 or WebM video with ffmpeg and exit
+    #[cfg(feature = "record")]
+    #[arg(long, value_name = "video", group = "export")]
+    pub record: Option<PathBuf>,
+    /// Save each frame of the animation as numbered PNG files in the directory and exit
+    #[arg(long, value_name = "directory", group = "export")]
+    pub record_frames: Option<PathBuf>,
+    /// Put the rendered code on the clipboard as an image and exit
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, group = "export")]
+    pub copy_image: bool,
+    /// Number of frames to export, instead of the length of the animation
+    #[arg(long)]
+    pub frames: Option<usize>,
+    /// Export without showing the code on screen, a 1x1 pixel window is still opened since a graphics context is needed
+    #[arg(long, requires = "export")]
+    pub export_only: bool,
+    /// Print the highlighted code to stdout with ANSI color codes, without opening a window, and exit
+    #[arg(long)]
+    pub print_ansi: bool,
+    /// Only display a slice of the lines, e.g. `40:80`
+    #[arg(long)]
+    pub lines: Option<LineSlice>,
+    /// Open the code scrolled to this line, and optionally column, and flash it, e.g. `120` or `120:8`
+    #[arg(long, value_name = "line[:column]")]
+    pub goto: Option<LinePosition>,
+    /// Lines to highlight, e.g. `3,7-12`
+    #[arg(long)]
+    pub highlight_lines: Option<LineRanges>,
+    /// Show spaces as middle dots and tabs as arrows, in a dimmed color
+    #[arg(long)]
+    pub show_whitespace: bool,
+    /// Highlight whitespace at the end of lines with a red background
+    #[arg(long)]
+    pub highlight_trailing_whitespace: bool,
+    /// Draw vertical lines at each level of indentation, also enabled by `indent_guides` in the theme
+    #[arg(long)]
+    pub indent_guides: bool,
+    /// Color brackets, parentheses and braces by their nesting depth
+    #[arg(long)]
+    pub rainbow_brackets: bool,
+    /// Hide comments, and lines with only comments, toggled with H
+    #[arg(long)]
+    pub strip_comments: bool,
+    /// Draw vertical rulers at these columns, e.g. `80,100`
+    #[arg(long, value_delimiter = ',', value_name = "columns")]
+    pub ruler: Vec<usize>,
+    /// Number of columns between tab stops, overrides the code tab width of the theme
+    #[arg(long, value_name = "columns")]
+    pub tab_width: Option<usize>,
+    /// Directory with extra `.sublime-syntax` definitions to highlight the code with
+    #[arg(long, value_name = "directory")]
+    pub syntax_dir: Option<PathBuf>,
+    /// Rhai script that reacts to keypresses and frame ticks to change files, highlights and zoom, needs the `scripting` feature
+    #[cfg(feature = "scripting")]
+    #[arg(long, value_name = "file")]
+    pub script: Option<PathBuf>,
+    /// Language server to color the code from a file with semantic highlighting, e.g. `rust-analyzer`
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "command")]
+    pub lsp: Option<String>,
+    /// Port to listen for WebSocket remote control commands on: `next`, `prev`, `goto-line <line[:column]>` or `load <path>`
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "port")]
+    pub control_port: Option<u16>,
+    /// Address to listen for remote control commands on, use `0.0.0.0` to let phones on the network connect
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "address", default_value = "127.0.0.1")]
+    pub control_address: String,
+    /// Secret that remote control clients must connect with, as `?token=<token>` in the WebSocket URL
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "token")]
+    pub control_token: Option<String>,
+    /// Port on localhost to accept code on, a JSON object like `{"code": "...", "language": "rust"}` posted to `/code` replaces the displayed code
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "port")]
+    pub code_port: Option<u16>,
+    /// Backend to highlight the syntax with
+    #[arg(long, value_enum, default_value_t = Highlighter::Syntect)]
+    pub highlighter: Highlighter,
+    /// Animate the code when it is displayed
+    #[arg(short, long, value_enum)]
+    pub animation: Option<Animation>,
+    /// Play a keystroke sound while the typewriter animation types, the bundled sound or an audio file
+    #[arg(long, value_name = "file", num_args = 0..=1, default_missing_value = KEYSTROKE_SOUND)]
+    pub typing_sound: Option<String>,
+    /// Volume of the typing sound, from 0 to 1
+    #[arg(long, value_name = "volume", default_value_t = 0.5)]
+    pub typing_volume: f32,
+    /// Loop an audio file, like an OGG or WAV file, in the background
+    #[arg(long, value_name = "file")]
+    pub audio: Option<String>,
+    /// Volume of the background audio, from 0 to 1
+    #[arg(long, value_name = "volume", default_value_t = 1.)]
+    pub audio_volume: f32,
+    /// Frames per second of exported animations
+    #[arg(long, default_value_t = 30)]
+    pub fps: u16,
+    /// Number of lines to scroll for each step of the mouse wheel
+    #[arg(long, default_value_t = 3.)]
+    pub scroll_speed: f32,
+    /// Wrap lines that are wider than the window
+    #[arg(long)]
+    pub wrap: bool,
+    /// Reload the code when a file given by `filename` changes, and the theme when the theme file changes
+    #[arg(short, long)]
+    pub watch: bool,
+    /// Display two files side by side, scrolled together, to compare them
+    #[arg(long, num_args = 2, value_names = ["FILE_A", "FILE_B"])]
+    pub compare: Vec<PathBuf>,
+    /// Animate switching between files, overrides the transition of the theme
+    #[arg(long, value_enum)]
+    pub transition: Option<Transition>,
+    /// Turn off animations, transitions and the movement of the background shader
+    #[arg(long)]
+    pub reduced_motion: bool,
+    /// Built-in overlays to draw on top of the code, can be given more than once
+    #[arg(long, value_enum)]
+    pub overlay: Vec<Overlay>,
+    /// Show how far along the files the presentation is
+    #[arg(long, value_enum)]
+    pub progress: Option<Progress>,
+    /// Move to the next file after this interval, and start over after the last, e.g. `15s` or `2m`
+    #[arg(long, value_name = "interval")]
+    pub auto_advance: Option<Interval>,
+    /// Count down the length of the talk in a timer that turns yellow near the end, e.g. `20m`
+    #[arg(long, value_name = "interval")]
+    pub talk_length: Option<Interval>,
+    /// Don't show tabs with the names of the files when more than one file is loaded
+    #[arg(long)]
+    pub no_tabs: bool,
+    /// Markdown file with presenter notes, a section for each file, defaults to `notes.md` next to the first file, press P to show them
+    #[arg(long, value_name = "file")]
+    pub notes: Option<PathBuf>,
+    /// JSON file with a list of diagnostics like `{"file": "src/main.rs", "line": 3, "severity": "error", "message": "..."}` to underline, for the first file without `file`, hover a line to read them
+    #[arg(long, value_name = "file")]
+    pub diagnostics: Option<PathBuf>,
+    /// Run the code with this command, or a runner for the language, and show the output below it, press R to run it again
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "command", num_args = 0..=1, default_missing_value = "")]
+    pub run: Option<String>,
+    /// Compile the code on Compiler Explorer with this compiler id, or one for the language, and show the assembly next to it
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long, value_name = "compiler", num_args = 0..=1, default_missing_value = "", conflicts_with_all = ["compare", "layout"])]
+    pub godbolt: Option<String>,
+    /// Arrange the files in a grid of code boxes, e.g. `2x2`, press F to zoom in on the focused box
+    #[arg(long, value_name = "COLUMNSxROWS", conflicts_with = "compare")]
+    pub layout: Option<GridLayout>,
+    /// Show a header with the filename at the top of the code box
+    #[arg(long)]
+    pub header: bool,
+    /// Caption to show in a header at the top of the code box, instead of the filename
+    #[arg(long)]
+    pub caption: Option<String>,
+    /// Decorate the code box as a window with rounded corners and three colored window controls
+    #[arg(long)]
+    pub window_controls: bool,
+    /// Use a high-contrast version of the theme with bold code, for washed-out projectors
+    #[arg(long)]
+    pub high_contrast: bool,
+    /// Adjust the colors of the code so that they can be told apart with this color vision deficiency
+    #[arg(long, value_enum)]
+    pub colorblind: Option<Colorblind>,
+    /// Show a minimap of the whole file on the right edge of the screen, click it to jump
+    #[arg(long)]
+    pub minimap: bool,
+    /// Reveal the code one line at a time, or one group of highlighted lines at a time, with Space or Enter
+    #[arg(long)]
+    pub reveal: bool,
+}
+
+impl CliOptions {
+    /// Where to load code from, each source is displayed as its own slide.
+    fn code_sources(&self) -> Vec<CodeSource> {
+        if let Some(code) = &self.code {
+            return vec![CodeSource::Code(code.clone())];
+        }
+        if self.clipboard {
+            return vec![CodeSource::Clipboard];
+        }
+        if let Some(gist_id) = &self.gist {
+            return vec![CodeSource::Gist(Gist {
+                id: gist_id.clone(),
+                token: self.github_token.clone(),
+                filename: self.gist_file.clone(),
+                revision: self.gist_revision.clone(),
+            })];
+        }
+        // Playground share links are stored as Gists with a single file
+        if let Some(share) = &self.playground {
+            return vec![CodeSource::Gist(Gist {
+                id: playground_gist_id(share),
+                token: self.github_token.clone(),
+                filename: Some(PLAYGROUND_FILENAME.to_string()),
+                revision: None,
+            })];
+        }
+        if let Some(url) = &self.github {
+            return vec![CodeSource::Github(url.clone())];
+        }
+        if let Some(url) = &self.url {
+            return vec![CodeSource::Url(url.clone())];
+        }
+        if self.stdin {
+            return vec![CodeSource::Stdin];
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(command) = &self.shell_command {
+            return vec![CodeSource::Command(command.clone())];
+        }
+        if !self.compare.is_empty() {
+            return self.compare.iter().cloned().map(CodeSource::File).collect();
+        }
+        let filenames = expand_filenames(&self.filename);
+        if filenames.is_empty() {
+            return vec![CodeSource::File(PathBuf::from("assets/helloworld.rs"))];
+        }
+        filenames
+            .into_iter()
+            .map(|filename| match filename.as_os_str() == "-" {
+                true => CodeSource::Stdin,
+                false if filename.is_dir() => CodeSource::Directory(filename),
+                false => CodeSource::File(filename),
+            })
+            .collect()
+    }
+
+    /// Scale of fonts and padding, defaults to the DPI scale of the display.
+    fn scale(&self) -> f32 {
+        self.scale.unwrap_or_else(screen_dpi_scale)
+    }
+
+    /// Presenter notes to load, the `notes.md` sidecar next to the first
+    /// file is used if no file is given.
+    fn notes_path(&self) -> Option<PathBuf> {
+        if let Some(notes) = &self.notes {
+            return Some(notes.clone());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(CodeSource::File(path) | CodeSource::Directory(path)) =
+            self.code_sources().first()
+        {
+            let directory = match path.is_dir() {
+                true => path.as_path(),
+                false => path.parent().unwrap_or(Path::new("")),
+            };
+            let sidecar = directory.join(NOTES_FILENAME);
+            if sidecar.is_file() {
+                return Some(sidecar);
+            }
+        }
+        None
+    }
+
+    /// Space between the code box and the edge of the screen.
+    fn margin(&self) -> f32 {
+        self.margin
+            .unwrap_or(if self.embed { 0. } else { SCROLL_MARGIN })
+    }
+
+    /// File to save the rendered code to, instead of showing it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_path(&self) -> Option<&PathBuf> {
+        #[cfg(feature = "record")]
+        if let Some(record) = &self.record {
+            return Some(record);
+        }
+        self.output.as_ref()
+    }
+
+    /// Nothing is shown in the window when only exporting, printing to
+    /// the terminal, copying an image or rendering images for the HTTP
+    /// server.
+    fn offscreen(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.command.is_some() || self.copy_image {
+            return true;
+        }
+        self.export_only || self.print_ansi
+    }
+
+    /// The background of the code box, a color or a gradient shader.
+    async fn background(&self) -> Background {
+        match (&self.background_color, self.no_gradient) {
+            _ if self.embed => Background::Color(BLANK),
+            (Some(color), _) => Background::Color(*color),
+            (None, true) => Background::Color(BLANK),
+            (None, false) => Background::shader(self.background_shader.as_deref()).await,
+        }
+    }
+
+    /// Width available for the code box, which is the width of the exported
+    /// image when offscreen since the window is only a placeholder.
+    fn canvas_width(&self) -> f32 {
+        match self.offscreen() {
+            true => self.width.unwrap_or(Conf::default().window_width) as f32,
+            false => screen_width(),
+        }
+    }
+
+    /// Start in fullscreen unless a window, or the size of it, is requested.
+    fn fullscreen(&self) -> bool {
+        !(self.windowed || self.width.is_some() || self.height.is_some())
+    }
+
+    /// The window title, with the template variables replaced using the
+    /// first file to display.
+    fn window_title(&self) -> String {
+        let filename = self
+            .code_sources()
+            .first()
+            .map(CodeSource::filename)
+            .unwrap_or_default();
+        let language = self
+            .language
+            .clone()
+            .or_else(|| detect_lang::from_path(&filename).map(|lang| lang.id().to_string()))
+            .unwrap_or_default();
+        self.title
+            .replace("{filename}", &filename)
+            .replace("{language}", &language)
+    }
+}
+
+/// Expand glob patterns in the filenames, sorting the matched files. A
+/// pattern without matches is kept, so that it is reported as a file that
+/// can't be loaded instead of showing the example code.
+#[cfg(not(target_arch = "wasm32"))]
+fn expand_filenames(filenames: &[PathBuf]) -> Vec<PathBuf> {
+    filenames
+        .iter()
+        .flat_map(|filename| {
+            let pattern = filename.to_string_lossy();
+            if !pattern.contains(['*', '?', '[']) {
+                return vec![filename.clone()];
+            }
+            let mut paths: Vec<PathBuf> = glob::glob(&pattern)
+                .map(|paths| paths.filter_map(|path| path.ok()).collect())
+                .unwrap_or_default();
+            if paths.is_empty() {
+                warn!("No files match {}", pattern);
+                return vec![filename.clone()];
+            }
+            paths.sort();
+            paths
+        })
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn expand_filenames(filenames: &[PathBuf]) -> Vec<PathBuf> {
+    filenames.to_vec()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export_options(opt: &CliOptions, watermark: Option<Watermark>) -> export::ExportOptions {
+    export::ExportOptions {
+        animation: opt.animation,
+        fps: opt.fps,
+        frames: opt.frames,
+        watermark,
+        margin: opt.margin.or(opt.embed.then_some(0.)),
+    }
+}
+
+/// Render the code posted to the HTTP server, with the options given on the
+/// command line as defaults, until the program is stopped.
+#[cfg(not(target_arch = "wasm32"))]
+async fn serve(opt: CliOptions, address: &str) {
+    let server = match server::PostServer::<server::RenderPayload>::listen(address, "/render") {
+        Ok(server) => server,
+        Err(e) => {
+            error!("Couldn't listen on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    let (default_theme, default_code_theme) = load_themes(&opt.theme).await;
+    let mut renderer =
+        match CodeRenderer::new(default_theme.clone(), default_code_theme.clone()).await {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                error!("Couldn't load the fonts of the theme: {}", e);
+                std::process::exit(1);
+            }
+        };
+    if let Some(syntax_dir) = &opt.syntax_dir {
+        renderer.load_syntax_dir(syntax_dir);
+    }
+    let background = opt.background().await;
+    loop {
+        for request in server.requests() {
+            let themes = match request.payload.theme.clone() {
+                Some(name) => CodeTheme::bundled(&name).map(|code_theme| {
+                    let mut theme = default_theme.clone();
+                    theme.code_theme = name;
+                    (theme, code_theme)
+                }),
+                None => Some((default_theme.clone(), default_code_theme.clone())),
+            };
+            let Some((theme, code_theme)) = themes else {
+                request.respond_error(400, "Unknown theme, use a theme from --list-themes");
+                continue;
+            };
+            // The bundled themes use the fonts of the default theme, so the
+            // fonts are only loaded once
+            if let Err(e) = renderer.set_themes(theme, code_theme).await {
+                request.respond_error(500, &e.to_string());
+                continue;
+            }
+            let mut code = Code::from_sourcecode(request.payload.code.clone());
+            code.language = request.payload.language.clone();
+            let codebox = build_codebox(&opt, &renderer, &code);
+            if codebox.width_with_padding() > server::MAX_IMAGE_SIZE
+                || codebox.height_with_padding() > server::MAX_IMAGE_SIZE
+            {
+                request.respond_error(
+                    413,
+                    &format!(
+                        "The code is too large to render, the image would be larger than {} pixels",
+                        server::MAX_IMAGE_SIZE
+                    ),
+                );
+                continue;
+            }
+            let watermark = Watermark::load(renderer.code_theme(), opt.scale()).await;
+            let options = export_options(&opt, watermark);
+            match export::render_png(&codebox, &background, &options).await {
+                Ok(png) => request.respond_png(png),
+                Err(e) => request.respond_error(500, &format!("Couldn't encode image: {}", e)),
+            }
+        }
+        next_frame().await
+    }
+}
+
+/// Presenter notes loaded from next to the code, if there are any.
+#[cfg(not(target_arch = "wasm32"))]
+const NOTES_FILENAME: &str = "notes.md";
+
+/// Change of the code font size for each step of zooming in or out.
+const FONT_SIZE_STEP: f32 = 2.;
+const MIN_FONT_SIZE: f32 = 6.;
+
+/// Options from the command line, or on the web from the query parameters of
+/// the URL, like `?line-numbers&lines=10:20&theme=base16-ocean.dark`.
+fn parse_options() -> CliOptions {
+    let arguments = get_program_parameters();
+    #[cfg(target_arch = "wasm32")]
+    {
+        let arguments = query_arguments(arguments);
+        // The page can't be exited, so invalid parameters are ignored
+        return CliOptions::try_parse_from(&arguments).unwrap_or_else(|e| {
+            error!("Invalid URL parameters: {}", e);
+            CliOptions::parse_from(arguments.iter().take(1))
+        });
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    CliOptions::parse_from(arguments)
+}
+
+/// Turn the query parameters, given as `--name=value`, into command line
+/// arguments. Names may use underscores or the short option, and flags may
+/// be given a value like `wrap=true` or `wrap=false`.
+#[cfg(any(test, target_arch = "wasm32"))]
+fn query_arguments(parameters: Vec<String>) -> Vec<String> {
+    use clap::CommandFactory;
+
+    let command = CliOptions::command();
+    let mut parameters = parameters.into_iter();
+    // The first parameter is the path of the page, like the program name
+    let mut arguments: Vec<String> = parameters.next().into_iter().collect();
+    for parameter in parameters {
+        let Some(option) = parameter.strip_prefix("--") else {
+            arguments.push(parameter);
+            continue;
+        };
+        let (name, value) = option.split_once('=').unwrap_or((option, ""));
+        let name = name.replace('_', "-");
+        let argument = command.get_arguments().find(|argument| {
+            argument.get_long() == Some(name.as_str())
+                || argument.get_short().map(String::from) == Some(name.clone())
+        });
+        let Some(argument) = argument else {
+            warn!("Unknown URL parameter: {}", name);
+            continue;
+        };
+        let long = argument.get_long().unwrap_or_default();
+        match (argument.get_action().takes_values(), value) {
+            (true, value) => arguments.push(format!("--{}={}", long, value)),
+            (false, "" | "true" | "1" | "yes" | "on") => arguments.push(format!("--{}", long)),
+            (false, _) => (),
+        }
+    }
+    arguments
+}
+
+/// Window for the options. Macroquad needs a window for its graphics context,
+/// even when nothing is shown in it, so the window is kept as small as
+/// possible when exporting.
+fn window_conf(opt: &CliOptions) -> Conf {
+    let default = Conf::default();
+    if opt.offscreen() {
+        return Conf {
+            window_title: opt.window_title(),
+            window_width: 1,
+            window_height: 1,
+            window_resizable: false,
+            ..default
+        };
+    }
+    Conf {
+        window_title: opt.window_title(),
+        window_width: opt.width.unwrap_or(default.window_width),
+        window_height: opt.height.unwrap_or(default.window_height),
+        fullscreen: opt.fullscreen(),
+        high_dpi: true,
+        ..default
+    }
+}
+
+/// Print the highlighted code of each file to stdout, straight from syntect
+/// without opening a window, and exit with an error if a file can't be
+/// loaded.
+#[cfg(not(target_arch = "wasm32"))]
+fn print_ansi(opt: &CliOptions) {
+    let (code_theme, syntax_theme) = ansi::load_themes(&opt.theme);
+    let syntax_set = load_syntax_set(opt.syntax_dir.as_deref());
+    let highlight_lines = opt.highlight_lines.clone().unwrap_or_default();
+    for source in opt.code_sources() {
+        let mut code = match Code::load_blocking(source) {
+            Ok(code) => code,
+            Err(e) => {
+                error!("Encountered an error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        code.lines = opt.lines.or(code.lines);
+        let language = code.language(opt.language.clone());
+        print!(
+            "{}",
+            ansi::render_ansi(
+                &code,
+                language,
+                &syntax_set,
+                &syntax_theme,
+                &code_theme,
+                opt.line_numbers,
+                &highlight_lines,
+            )
+        );
+    }
+}
+
+/// Run the app with the options from the command line, in a window until it
+/// is closed, or export the code and exit. The renderers draw their overlays
+/// each frame, on top of the built-in overlays from the options.
+pub fn run(renderers: Vec<Box<dyn Renderer>>) {
+    let opt = parse_options();
+    // Printing to the terminal doesn't need a window
+    if opt.list_themes {
+        for name in BUNDLED_THEMES {
+            println!("{}", name);
+        }
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if opt.print_ansi {
+        print_ansi(&opt);
+        return;
+    }
+    macroquad::Window::from_config(window_conf(&opt), main_loop(opt, renderers));
+}
+
+/// Show the code with the options until the window is closed, or export it
+/// and exit.
+async fn main_loop(mut opt: CliOptions, renderers: Vec<Box<dyn Renderer>>) {
+    #[cfg(target_arch = "wasm32")]
+    if reduced_motion::prefers_reduced_motion() {
+        opt.reduced_motion = true;
+    }
+    // Nothing moves by itself, but scrolling and switching files still work
+    if opt.reduced_motion {
+        opt.animation = None;
+        opt.transition = Some(Transition::None);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(Command::Serve { address }) = opt.command.take() {
+        serve(opt, &address).await;
+        return;
+    }
+    let (theme, code_theme) = load_themes(&opt.theme).await;
+    // The fonts are loaded once, and kept while the themes use the same fonts
+    let mut renderer = match CodeRenderer::new(theme, code_theme).await {
+        Ok(renderer) => renderer,
+        Err(e) => {
+            error!("Couldn't load the fonts of the theme: {}", e);
+            #[cfg(not(target_arch = "wasm32"))]
+            std::process::exit(1);
+            #[cfg(target_arch = "wasm32")]
+            return;
+        }
+    };
+    if let Some(syntax_dir) = &opt.syntax_dir {
+        renderer.load_syntax_dir(syntax_dir);
+    }
+
+    let mut slides = Vec::new();
+    for source in opt.code_sources() {
+        let slide = load_slide(&opt, &renderer, source).await;
+        if let Err(e) = &slide.codebox {
+            error!("Encountered an error: {}", e);
+            #[cfg(not(target_arch = "wasm32"))]
+            if !opt.watch {
+                std::process::exit(1);
+            }
+        }
+        slides.push(slide);
+    }
+    let mut slides = Slides::new(slides);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let lsp_client = opt.lsp.clone().map(lsp::SemanticTokensClient::new);
+    #[cfg(not(target_arch = "wasm32"))]
+    let requested = slides
+        .iter()
+        .filter(|slide| request_semantic_tokens(lsp_client.as_ref(), &opt, slide))
+        .count();
+    // Exports are only drawn once, so they wait for the tokens
+    #[cfg(not(target_arch = "wasm32"))]
+    if let (Some(client), true) = (&lsp_client, opt.offscreen()) {
+        for _ in 0..requested {
+            let Some(response) = client.wait() else {
+                break;
+            };
+            apply_semantic_tokens(&opt, &renderer, &mut slides, response);
+        }
+    }
+
+    // Nothing is shown offscreen, so a file that couldn't be loaded fails
+    // the export instead of being shown as an error in the window
+    #[cfg(not(target_arch = "wasm32"))]
+    if let (true, Err(e)) = (opt.offscreen(), &slides.current().codebox) {
+        error!("Couldn't export the code: {}", e);
+        std::process::exit(1);
+    }
+
+    let background = opt.background().await;
+    let mut watermark = Watermark::load(renderer.code_theme(), opt.scale()).await;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let (Some(directory), Ok(codebox)) = (&opt.record_frames, &slides.current().codebox) {
+        let options = export_options(&opt, watermark);
+        if let Err(e) = export::export_frames(codebox, &background, directory, &options).await {
+            error!("Couldn't export frames to {}: {}", directory.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let (true, Ok(codebox)) = (opt.copy_image, &slides.current().codebox) {
+        let options = export_options(&opt, watermark);
+        let copied = match arboard::Clipboard::new() {
+            Ok(mut clipboard) => {
+                export::copy_image(codebox, &background, &options, &mut clipboard, true).await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = copied {
+            error!("Couldn't copy the image to the clipboard: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let (Some(output), Ok(codebox)) = (opt.export_path(), &slides.current().codebox) {
+        let options = export_options(&opt, watermark);
+        if let Err(e) = export::export(codebox, &background, output, &options).await {
+            error!("Couldn't export the code to {}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut file_watchers: Vec<(usize, watcher::FileWatcher)> = slides
+        .iter()
+        .enumerate()
+        .filter(|_| opt.watch)
+        .filter_map(|(index, slide)| match &slide.source {
+            CodeSource::File(path) => Some((index, watcher::FileWatcher::new(path.clone()))),
+            _ => None,
+        })
+        .collect();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut theme_watcher = opt
+        .watch
+        .then(|| watcher::FileWatcher::new(opt.theme.clone()));
+
+    let mut start_time = get_time();
+    let mut scroll = Scroll::new(opt.scroll_speed, opt.margin());
+    let mut goto = match (&slides.current().codebox, opt.goto) {
+        (Ok(codebox), Some(position)) => Goto::new(codebox, position).or_else(|| {
+            warn!("Line {} isn't displayed", position.line);
+            None
+        }),
+        _ => None,
+    };
+    if let (Some(goto), Ok(codebox)) = (&goto, &slides.current().codebox) {
+        goto.scroll_to(codebox, &mut scroll);
+    }
+    let mut grid_scrolls: Vec<Scroll> = slides
+        .iter()
+        .map(|_| Scroll::new(opt.scroll_speed, opt.margin()))
+        .collect();
+    let mut grid_zoomed = false;
+    let mut auto_advance = opt.auto_advance.map(AutoAdvance::new);
+    let mut timer = Timer::new(opt.talk_length);
+    let mut typing_sound = match (&opt.typing_sound, opt.animation) {
+        (Some(path), Some(Animation::Typewriter)) => {
+            TypingSound::load(path, opt.typing_volume).await
+        }
+        _ => None,
+    };
+    let mut background_audio = match &opt.audio {
+        Some(path) => BackgroundAudio::play(path, opt.audio_volume).await,
+        None => None,
+    };
+    let mut muted = false;
+    #[cfg(not(target_arch = "wasm32"))]
+    let remote_control = opt.control_port.and_then(|port| {
+        match remote::RemoteControl::listen(&opt.control_address, port, opt.control_token.clone()) {
+            Ok(remote_control) => Some(remote_control),
+            Err(e) => {
+                error!("Couldn't listen for remote control on port {}: {}", port, e);
+                None
+            }
+        }
+    });
+    #[cfg(not(target_arch = "wasm32"))]
+    let code_server = opt.code_port.and_then(|port| {
+        let address = format!("127.0.0.1:{}", port);
+        match server::PostServer::<server::CodePayload>::listen(&address, "/code") {
+            Ok(code_server) => Some(code_server),
+            Err(e) => {
+                error!("Couldn't listen for code on {}: {}", address, e);
+                None
+            }
+        }
+    });
+    let mut renderers: Vec<Box<dyn Renderer>> = opt
+        .overlay
+        .iter()
+        .map(|overlay| overlay.renderer())
+        .chain(renderers)
+        .collect();
+    #[cfg(feature = "scripting")]
+    let mut script = match &opt.script {
+        Some(path) => script::Script::load(&path.to_string_lossy())
+            .await
+            .map_err(|e| error!("{}", e))
+            .ok(),
+        None => None,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut capture: Option<Capture> = None;
+    // Copied images are served from the clipboard on Linux, so it is kept
+    // until the program exits
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut clipboard: Option<arboard::Clipboard> = None;
+    let mut toast: Option<Toast> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut runner = opt.run.clone().map(Runner::new);
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut playground = Runner::playground();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut run_output: Option<CodeBox> = None;
+    // The code is run when it is first shown, and then with R
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut run_requested = true;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut compiler_explorer = opt.godbolt.clone().map(CompilerExplorer::new);
+    // The code and its assembly, shown side by side once it is compiled
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut assembly_panes: Option<[Result<CodeBox>; 2]> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut compile_requested = true;
+    let diagnostics = match &opt.diagnostics {
+        Some(path) => {
+            // Diagnostics without a file are for the first file given
+            let default_file = opt.code_sources().first().map(|source| match source {
+                CodeSource::File(path) => path.to_string_lossy().into_owned(),
+                source => source.filename(),
+            });
+            Diagnostics::load(&path.to_string_lossy(), default_file).await
+        }
+        None => None,
+    };
+    let mut notes = match opt.notes_path() {
+        Some(path) => Notes::load(&path.to_string_lossy()).await,
+        None => None,
+    };
+    let mut transition: Option<SlideTransition> = None;
+    // Where the code box was drawn in the last frame, for the snapshot of
+    // the previous file in a transition
+    let mut last_position = (0., 0.);
+    let mut reveal_step = 0;
+    let mut fullscreen = opt.fullscreen();
+    let mut screen_size = (screen_width(), screen_height());
+    let initial_font_size = opt.font_size;
+    let mut search = Search::default();
+    let mut selection = Selection::default();
+    loop {
+        // Keys typed into the search input aren't used as commands
+        let typing = search.handle_keys();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !typing && (is_key_pressed(KeyCode::Q) | is_key_pressed(KeyCode::Escape)) {
+            break;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for (index, file_watcher) in file_watchers.iter_mut() {
+            if !file_watcher.has_changed() {
+                continue;
+            }
+            if let Some(slide) = slides.get_mut(*index) {
+                *slide = load_slide(&opt, &renderer, slide.source.clone()).await;
+                if let Err(e) = &slide.codebox {
+                    error!("Encountered an error: {}", e);
+                }
+                request_semantic_tokens(lsp_client.as_ref(), &opt, slide);
+                start_time = get_time();
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for response in lsp_client
+            .as_ref()
+            .map(lsp::SemanticTokensClient::responses)
+            .unwrap_or_default()
+        {
+            apply_semantic_tokens(&opt, &renderer, &mut slides, response);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(theme_watcher) = &mut theme_watcher {
+            if theme_watcher.has_changed() {
+                let (theme, code_theme) = load_themes(&opt.theme).await;
+                switch_themes(&mut renderer, theme, code_theme).await;
+                watermark = Watermark::load(renderer.code_theme(), opt.scale()).await;
+                rebuild_codeboxes(&opt, &renderer, &mut slides);
+            }
+        }
+
+        // Wrapped lines depend on the width of the window
+        if screen_size != (screen_width(), screen_height()) {
+            screen_size = (screen_width(), screen_height());
+            if opt.wrap {
+                rebuild_codeboxes(&opt, &renderer, &mut slides);
+            }
+        }
+
+        let show_tabs = !opt.no_tabs
+            && slides.iter().count() > 1
+            && opt.compare.is_empty()
+            && (opt.layout.is_none() || grid_zoomed);
+        let tab_bar = show_tabs.then(|| {
+            TabBar::new(
+                slides.iter().map(slide_title).collect(),
+                renderer.code_theme(),
+                opt.scale(),
+            )
+        });
+
+        let previous_slide = slides.current_index();
+
+        // Slides switched or replaced remotely or by a script, which resets
+        // the view like switching with the keyboard
+        #[allow(unused_mut)]
+        let mut remote_changed = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        for command in remote_control
+            .as_ref()
+            .map(remote::RemoteControl::commands)
+            .unwrap_or_default()
+        {
+            match command {
+                remote::Command::Next => remote_changed |= slides.next_slide(),
+                remote::Command::Previous => remote_changed |= slides.previous_slide(),
+                remote::Command::GotoLine(position) => {
+                    if let Ok(codebox) = &slides.current().codebox {
+                        goto = jump_to(codebox, position, &mut scroll);
+                    }
+                }
+                remote::Command::Load(path) => {
+                    let slide = slides.current_mut();
+                    *slide = load_slide(&opt, &renderer, CodeSource::File(path)).await;
+                    if let Err(e) = &slide.codebox {
+                        error!("Encountered an error: {}", e);
+                    }
+                    request_semantic_tokens(lsp_client.as_ref(), &opt, slide);
+                    remote_changed = true;
+                }
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        for request in code_server
+            .as_ref()
+            .map(server::PostServer::requests)
+            .unwrap_or_default()
+        {
+            let slide = code_slide(
+                &opt,
+                &renderer,
+                request.payload.code.clone(),
+                request.payload.language.clone(),
+            );
+            match &slide.codebox {
+                Ok(_) => request.respond_ok(),
+                Err(e) => request.respond_error(500, &e.to_string()),
+            }
+            *slides.current_mut() = slide;
+            remote_changed = true;
+        }
+        #[cfg(target_arch = "wasm32")]
+        for message in post_message::messages() {
+            if let Some(name) = &message.theme {
+                let (theme, code_theme) = load_themes(Path::new(name)).await;
+                switch_themes(&mut renderer, theme, code_theme).await;
+                watermark = Watermark::load(renderer.code_theme(), opt.scale()).await;
+                rebuild_codeboxes(&opt, &renderer, &mut slides);
+            }
+            let slide = slides.current_mut();
+            if let Some(sourcecode) = message.code {
+                *slide = code_slide(&opt, &renderer, sourcecode, message.language);
+                remote_changed = true;
+            } else if let (Some(language), Some(code)) = (message.language, &mut slide.code) {
+                code.language = Some(language);
+                slide.codebox = Ok(build_codebox(&opt, &renderer, code));
+            }
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(script) = &mut script {
+            let mut actions = script.on_frame(get_time(), slides.current_index());
+            if let (false, Some(key)) = (typing, get_last_key_pressed()) {
+                actions.extend(script.on_key(key));
+            }
+            for action in actions {
+                match action {
+                    script::Action::Next => remote_changed |= slides.next_slide(),
+                    script::Action::Previous => remote_changed |= slides.previous_slide(),
+                    script::Action::Show(index) => remote_changed |= slides.set_current(index),
+                    // Scripts may set the same value on every frame, so the
+                    // code is only rebuilt when it changes
+                    script::Action::Highlight(lines) => {
+                        if opt.highlight_lines.as_ref() != Some(&lines) {
+                            opt.highlight_lines = Some(lines);
+                            rebuild_codeboxes(&opt, &renderer, &mut slides);
+                        }
+                    }
+                    script::Action::FontSize(size) => {
+                        let font_size = Some(size.max(MIN_FONT_SIZE));
+                        if opt.font_size != font_size {
+                            opt.font_size = font_size;
+                            rebuild_codeboxes(&opt, &renderer, &mut slides);
+                        }
+                    }
+                    script::Action::GotoLine(position) => {
+                        if let Ok(codebox) = &slides.current().codebox {
+                            goto = jump_to(codebox, position, &mut scroll);
+                        }
+                    }
+                }
+            }
+        }
+
+        if !typing {
+            if is_key_pressed(opt.fullscreen_key) {
+                fullscreen = !fullscreen;
+                set_fullscreen(fullscreen);
+            }
+
+            let font_size = opt
+                .font_size
+                .unwrap_or(renderer.theme().font_code_size as f32);
+            let zoomed_font_size =
+                if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+                    Some(Some(font_size + FONT_SIZE_STEP))
+                } else if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+                    Some(Some((font_size - FONT_SIZE_STEP).max(MIN_FONT_SIZE)))
+                } else if is_key_pressed(KeyCode::Key0) || is_key_pressed(KeyCode::Kp0) {
+                    Some(initial_font_size)
+                } else {
+                    None
+                };
+            if let Some(zoomed_font_size) = zoomed_font_size {
+                opt.font_size = zoomed_font_size;
+                rebuild_codeboxes(&opt, &renderer, &mut slides);
+            }
+
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            #[cfg(not(target_arch = "wasm32"))]
+            if is_key_pressed(KeyCode::C) && shift {
+                capture = Some(Capture::Clipboard);
+            }
+
+            if is_key_pressed(KeyCode::C) && !shift {
+                if let Ok(codebox) = &slides.current().codebox {
+                    let text = selection.text(codebox).unwrap_or_else(|| codebox.text());
+                    macroquad::miniquad::window::clipboard_set(&text);
+                    info!("Copied code to clipboard");
+                }
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if is_key_pressed(KeyCode::S) {
+                capture = Some(Capture::Screenshot);
+            }
+
+            if is_key_pressed(KeyCode::K) {
+                match shift {
+                    true => timer.restart(),
+                    false => timer.visible = !timer.visible,
+                }
+            }
+
+            if is_key_pressed(KeyCode::M) {
+                muted = !muted;
+                if let Some(background_audio) = &mut background_audio {
+                    background_audio.set_muted(muted);
+                }
+                if let Some(typing_sound) = &mut typing_sound {
+                    typing_sound.muted = muted;
+                }
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if is_key_pressed(KeyCode::R) && !shift {
+                run_requested = true;
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let (true, Some(code)) =
+                (is_key_pressed(KeyCode::R) && shift, &slides.current().code)
+            {
+                playground.start(code, None);
+                toast = Some(Toast::new("Running the code on the Rust Playground"));
+            }
+
+            if is_key_pressed(KeyCode::P) {
+                if let Some(notes) = &mut notes {
+                    notes.visible = !notes.visible;
+                }
+            }
+
+            if is_key_pressed(KeyCode::H) {
+                opt.strip_comments = !opt.strip_comments;
+                rebuild_codeboxes(&opt, &renderer, &mut slides);
+            }
+
+            if is_key_pressed(KeyCode::Z) {
+                let Slide { code, codebox, .. } = slides.current_mut();
+                if let (Some(code), Ok(current)) = (code, &*codebox) {
+                    let toggled =
+                        match is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                            true => {
+                                code.folds.toggle_all();
+                                true
+                            }
+                            false => folding::line_at_mouse(current, &mut scroll)
+                                .map(|line| code.folds.toggle(line))
+                                .unwrap_or(false),
+                        };
+                    if toggled {
+                        *codebox = Ok(build_codebox(&opt, &renderer, code));
+                        goto = None;
+                    }
+                }
+            }
+
+            if is_key_pressed(KeyCode::T) {
+                let next = BUNDLED_THEMES
+                    .iter()
+                    .position(|name| {
+                        *name == renderer.theme().code_theme
+                            && renderer.code_theme().tm_theme.is_empty()
+                    })
+                    .map(|index| (index + 1) % BUNDLED_THEMES.len())
+                    .unwrap_or(0);
+                info!("Switching to theme: {}", BUNDLED_THEMES[next]);
+                let mut theme = renderer.theme().clone();
+                theme.code_theme = BUNDLED_THEMES[next].to_string();
+                let code_theme = CodeTheme::bundled(BUNDLED_THEMES[next]).unwrap_or_default();
+                switch_themes(&mut renderer, theme, code_theme).await;
+                rebuild_codeboxes(&opt, &renderer, &mut slides);
+            }
+
+            let reveal_steps = match (&slides.current().codebox, opt.reveal) {
+                (Ok(codebox), true) => codebox.reveal_steps(),
+                _ => Vec::new(),
+            };
+            if opt.layout.is_some() && is_key_pressed(KeyCode::F) {
+                grid_zoomed = !grid_zoomed;
+                scroll = Scroll::new(opt.scroll_speed, opt.margin());
+            }
+            let selected_tab = tab_bar
+                .as_ref()
+                .and_then(|tab_bar| tab_bar.handle_input(slides.current_index()));
+            let slide_changed = if remote_changed {
+                true
+            } else if !opt.compare.is_empty() {
+                false
+            } else if let Some(index) = selected_tab {
+                slides.set_current(index)
+            } else if auto_advance.as_ref().map(AutoAdvance::is_due) == Some(true) {
+                if let Some(auto_advance) = &mut auto_advance {
+                    auto_advance.restart();
+                }
+                slides.next_slide() || slides.set_current(0)
+            } else if let (Some(index), Some(_)) = (keys::pressed_number(), opt.layout) {
+                slides.set_current(index)
+            } else if reveal_step + 1 < reveal_steps.len()
+                && (is_key_pressed(KeyCode::Space)
+                    || is_key_pressed(KeyCode::Enter)
+                    || is_key_pressed(KeyCode::Right))
+            {
+                reveal_step += 1;
+                false
+            } else if reveal_step > 0
+                && (is_key_pressed(KeyCode::Backspace) || is_key_pressed(KeyCode::Left))
+            {
+                reveal_step -= 1;
+                false
+            } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Space) {
+                slides.next_slide()
+            } else if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::Backspace) {
+                slides.previous_slide()
+            } else {
+                false
+            };
+            if is_key_pressed(KeyCode::O) {
+                let slide = slides.current_mut();
+                if let CodeSource::Directory(_) = slide.source {
+                    // Keep showing the current file if no new file is selected
+                    let new_slide = load_slide(&opt, &renderer, slide.source.clone()).await;
+                    if !matches!(new_slide.codebox, Err(CodeError::NoFileSelected)) {
+                        *slide = new_slide;
+                        #[cfg(not(target_arch = "wasm32"))]
+                        request_semantic_tokens(lsp_client.as_ref(), &opt, slide);
+                        scroll = Scroll::new(opt.scroll_speed, opt.margin());
+                        goto = None;
+                        selection = Selection::default();
+                        start_time = get_time();
+                        reveal_step = 0;
+                    }
+                }
+            }
+            if slide_changed {
+                if let Some(auto_advance) = &mut auto_advance {
+                    auto_advance.restart();
+                }
+                let kind = opt
+                    .transition
+                    .unwrap_or_else(|| Transition::parse(&renderer.code_theme().transition));
+                transition = (kind != Transition::None && (opt.layout.is_none() || grid_zoomed))
+                    .then(|| {
+                        let direction = match slides.current_index() > previous_slide {
+                            true => 1.,
+                            false => -1.,
+                        };
+                        let started = SlideTransition::new(
+                            kind,
+                            renderer.code_theme().transition_duration,
+                            direction,
+                        );
+                        set_camera(&started.from_camera(scroll.zoom));
+                        clear_background(BLANK);
+                        if let Some(Ok(codebox)) = slides
+                            .iter()
+                            .nth(previous_slide)
+                            .map(|slide| &slide.codebox)
+                        {
+                            codebox.draw(last_position.0, last_position.1);
+                        }
+                        set_default_camera();
+                        started
+                    });
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    run_output = None;
+                    run_requested = true;
+                    assembly_panes = None;
+                    compile_requested = true;
+                }
+                scroll = Scroll::new(opt.scroll_speed, opt.margin());
+                goto = None;
+                selection = Selection::default();
+                start_time = get_time();
+                reveal_step = 0;
+            }
+        }
+
+        let time = match opt.reduced_motion {
+            true => 0.,
+            false => get_time() as f32,
+        };
+        background.draw(screen_width(), screen_height(), time);
+        overlay::pre_frame(&mut renderers, &Frame::new(&slides, opt.scale()));
+
+        if let (Some(layout), false) = (opt.layout, grid_zoomed) {
+            let codeboxes: Vec<&Result<CodeBox>> =
+                slides.iter().map(|slide| &slide.codebox).collect();
+            let clicked = compare::draw_grid(
+                &codeboxes,
+                layout,
+                &mut grid_scrolls,
+                slides.current_index(),
+                (renderer.theme().font_size_text as f32 * opt.scale()) as u16,
+            );
+            if let Some(index) = clicked {
+                slides.set_current(index);
+            }
+            if let Some(watermark) = &watermark {
+                watermark.draw(screen_width(), screen_height());
+            }
+            overlay::post_frame(&mut renderers, &Frame::new(&slides, opt.scale()));
+            next_frame().await;
+            continue;
+        }
+
+        if !opt.compare.is_empty() {
+            let codeboxes: Vec<&Result<CodeBox>> =
+                slides.iter().map(|slide| &slide.codebox).collect();
+            compare::draw_side_by_side(
+                &codeboxes,
+                &mut scroll,
+                (renderer.theme().font_size_text as f32 * opt.scale()) as u16,
+            );
+            if let Some(watermark) = &watermark {
+                watermark.draw(screen_width(), screen_height());
+            }
+            overlay::post_frame(&mut renderers, &Frame::new(&slides, opt.scale()));
+            next_frame().await;
+            continue;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(compiler_explorer) = &mut compiler_explorer {
+            if let (true, Some(code)) = (compile_requested, &slides.current().code) {
+                compiler_explorer.start(code, code.language(opt.language.clone()).as_deref());
+            }
+            compile_requested = false;
+            if let (Some(output), Some(code)) = (compiler_explorer.output(), &slides.current().code)
+            {
+                let (source_backgrounds, assembly_backgrounds, text) = match output {
+                    Ok(assembly) => (
+                        assembly.source_backgrounds(),
+                        assembly.assembly_backgrounds(),
+                        assembly.text,
+                    ),
+                    Err(e) => (
+                        HashMap::new(),
+                        HashMap::new(),
+                        format!("Couldn't compile the code on Compiler Explorer: {}", e),
+                    ),
+                };
+                let mut assembly = Code::new("output.s".to_string(), text);
+                assembly.language = Some("asm".to_string());
+                let source = codebox_builder(&opt, &renderer, code)
+                    .line_backgrounds(source_backgrounds)
+                    .build_draw_box(code.language(opt.language.clone()), code.sourcecode.clone());
+                let assembly = codebox_builder(&opt, &renderer, &assembly)
+                    .line_slice(None)
+                    .highlight_lines(LineRanges::default())
+                    .header(opt.header.then(|| assembly.filename.clone()))
+                    .line_backgrounds(assembly_backgrounds)
+                    .build_draw_box(assembly.language.clone(), assembly.sourcecode.clone());
+                assembly_panes = Some([Ok(source), Ok(assembly)]);
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(runner) = &mut runner {
+                if let (true, Some(code)) = (run_requested, &slides.current().code) {
+                    runner.start(code, code.language(opt.language.clone()).as_deref());
+                }
+            }
+            run_requested = false;
+            let output = runner
+                .as_mut()
+                .and_then(Runner::output)
+                .or_else(|| playground.output());
+            if let Some(output) = output {
+                let mut code = Code::new("output.txt".to_string(), output.trim_end().to_string());
+                code.language = Some("txt".to_string());
+                run_output = Some(build_codebox(&opt, &renderer, &code));
+            }
+        }
+
+        // The overlays below are drawn on top of the assembly panes too
+        #[cfg(not(target_arch = "wasm32"))]
+        let side_by_side = assembly_panes.as_ref();
+        #[cfg(target_arch = "wasm32")]
+        let side_by_side = None::<&[Result<CodeBox>; 2]>;
+        search.find_matches(slides.current().codebox.as_ref().ok());
+        match (side_by_side, &slides.current().codebox) {
+            #[cfg(not(target_arch = "wasm32"))]
+            (Some(panes), _) => {
+                let codeboxes: Vec<&Result<CodeBox>> = panes.iter().collect();
+                let (xpos, ypos) = compare::draw_side_by_side(
+                    &codeboxes,
+                    &mut scroll,
+                    (renderer.theme().font_size_text as f32 * opt.scale()) as u16,
+                );
+                if let (Some(run_output), Ok(source)) = (&run_output, &panes[0]) {
+                    run::draw_output(run_output, source, xpos, ypos, opt.scale());
+                }
+            }
+            (_, Ok(codebox)) => {
+                scroll.set_top(tab_bar.as_ref().map(TabBar::height).unwrap_or(0.));
+                scroll.handle_keys(codebox.line_height());
+                scroll.handle_mouse_wheel(codebox.line_height());
+                if !typing {
+                    search.handle_navigation(codebox, &mut scroll);
+                }
+                // The output of the code is drawn below it, and scrolls with it
+                #[cfg(not(target_arch = "wasm32"))]
+                let (width, height) = (
+                    codebox.width_with_padding().max(
+                        run_output
+                            .as_ref()
+                            .map(CodeBox::width_with_padding)
+                            .unwrap_or(0.),
+                    ),
+                    codebox.height_with_padding()
+                        + run::output_height(run_output.as_ref(), opt.scale()),
+                );
+                #[cfg(target_arch = "wasm32")]
+                let (width, height) = (codebox.width_with_padding(), codebox.height_with_padding());
+                let (xpos, ypos) = scroll.position(width, height);
+                if !selection.handle_mouse(codebox, &scroll, xpos, ypos)
+                    && !(opt.minimap && minimap::handle_mouse(codebox, &mut scroll))
+                {
+                    scroll.handle_mouse_drag();
+                }
+                let (xpos, ypos) = scroll.position(width, height);
+                let filename = slides
+                    .current()
+                    .code
+                    .as_ref()
+                    .map(|code| code.filename.clone())
+                    .unwrap_or_else(|| slides.current().source.filename());
+                let elapsed = (get_time() - start_time) as f32;
+                let revealed_chars = match opt.reveal {
+                    true => codebox.reveal_steps().get(reveal_step).copied(),
+                    false => None,
+                };
+                let visible_chars = opt
+                    .animation
+                    .map(|animation| animation.visible_chars(elapsed))
+                    .unwrap_or(usize::MAX)
+                    .min(revealed_chars.unwrap_or(usize::MAX));
+                if let Some(typing_sound) = &mut typing_sound {
+                    typing_sound.update(visible_chars.min(codebox.char_count()));
+                }
+                last_position = (xpos, ypos);
+                match &transition {
+                    Some(transition) => {
+                        set_camera(&transition.to_camera(scroll.zoom));
+                        clear_background(BLANK);
+                    }
+                    None => set_camera(&scroll.camera()),
+                }
+                codebox.draw_visible(xpos, ypos, visible_chars);
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(run_output) = &run_output {
+                    run::draw_output(run_output, codebox, xpos, ypos, opt.scale());
+                }
+                if let Some(diagnostics) = &diagnostics {
+                    diagnostics.draw(&filename, codebox, xpos, ypos, renderer.code_theme());
+                }
+                search.draw_matches(codebox, xpos, ypos);
+                if let Some(goto) = goto.as_ref().filter(|goto| goto.is_flashing()) {
+                    goto.draw(codebox, xpos, ypos);
+                }
+                selection.draw(codebox, xpos, ypos);
+                set_default_camera();
+                if let Some(transition) = &transition {
+                    transition.draw();
+                }
+                if let Some(diagnostics) = &diagnostics {
+                    diagnostics.draw_popover(&filename, codebox, ypos, &scroll.camera());
+                }
+                if opt.minimap {
+                    minimap::draw(codebox, &scroll, ypos);
+                }
+            }
+            (_, Err(e)) => {
+                draw_error_message(
+                    e.to_string(),
+                    (renderer.theme().font_size_text as f32 * opt.scale()) as u16,
+                );
+            }
+        };
+        if transition.as_ref().map(SlideTransition::is_done) == Some(true) {
+            transition = None;
+        }
+        if let Some(tab_bar) = &tab_bar {
+            tab_bar.draw(slides.current_index());
+        }
+        if let Some(progress) = opt.progress {
+            let count = slides.iter().count();
+            progress.draw(
+                slides.current_index(),
+                count,
+                renderer.code_theme(),
+                opt.scale(),
+            );
+        }
+        if let Some(auto_advance) = &auto_advance {
+            auto_advance.draw(opt.scale());
+        }
+        timer.draw(opt.scale());
+        if let Some(notes) = &notes {
+            let titles: Vec<String> = slides.iter().map(slide_title).collect();
+            notes.draw(slides.current_index(), &titles, opt.scale());
+        }
+        if let Some(watermark) = &watermark {
+            watermark.draw(screen_width(), screen_height());
+        }
+        overlay::post_frame(&mut renderers, &Frame::new(&slides, opt.scale()));
+        search.draw_input();
+
+        // The image is rendered after the frame is drawn, and ends it
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(capture), Ok(codebox)) = (capture.take(), &slides.current().codebox) {
+            let watermark = Watermark::load(renderer.code_theme(), opt.scale()).await;
+            let options = export_options(&opt, watermark);
+            let message = match capture {
+                Capture::Screenshot => {
+                    let path = export::screenshot_path();
+                    match export::export_png(codebox, &background, &path, &options).await {
+                        Ok(()) => format!("Saved {}", path.display()),
+                        Err(e) => format!("Couldn't save {}: {}", path.display(), e),
+                    }
+                }
+                Capture::Clipboard => {
+                    let opened = match &mut clipboard {
+                        Some(clipboard) => Ok(clipboard),
+                        None => arboard::Clipboard::new().map(|opened| clipboard.insert(opened)),
+                    };
+                    let copied = match opened {
+                        Ok(clipboard) => {
+                            export::copy_image(codebox, &background, &options, clipboard, false)
+                                .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    match copied {
+                        Ok(()) => "Copied image to clipboard".to_string(),
+                        Err(e) => format!("Couldn't copy image: {}", e),
+                    }
+                }
+            };
+            toast = Some(Toast::new(message));
+            continue;
+        }
+        if let Some(shown) = &toast {
+            shown.draw(opt.scale());
+            if shown.is_done() {
+                toast = None;
+            }
+        }
+
+        next_frame().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(parameters: &[&str]) -> Vec<String> {
+        query_arguments(
+            parameters
+                .iter()
+                .map(|parameter| parameter.to_string())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn turns_query_parameters_into_arguments() {
+        assert_eq!(
+            arguments(&["/", "--theme=dark", "--l=rust", "--line_numbers", "code"]),
+            [
+                "/",
+                "--theme=dark",
+                "--language=rust",
+                "--line-numbers",
+                "code"
+            ]
+        );
+    }
+
+    #[test]
+    fn gives_flags_with_true_values() {
+        assert_eq!(
+            arguments(&["/", "--wrap=true", "--n=false", "--minimap=0"]),
+            ["/", "--wrap"]
+        );
+    }
+
+    #[test]
+    fn skips_unknown_parameters() {
+        assert_eq!(arguments(&["/", "--unknown=1"]), ["/"]);
+    }
+}
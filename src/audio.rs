@@ -0,0 +1,89 @@
+use macroquad::{
+    audio::{load_sound, play_sound, set_sound_volume, PlaySoundParams, Sound},
+    prelude::*,
+};
+
+/// Keystroke sound that is bundled with the program.
+pub const KEYSTROKE_SOUND: &str = "assets/keystroke.wav";
+/// Shortest time in seconds between two keystroke sounds, since the
+/// typewriter types faster than sounds can be told apart.
+const KEYSTROKE_INTERVAL: f64 = 0.06;
+
+/// Plays a keystroke sound while the typewriter animation types the code.
+pub struct TypingSound {
+    sound: Sound,
+    volume: f32,
+    typed_chars: usize,
+    last_played: f64,
+    pub muted: bool,
+}
+
+impl TypingSound {
+    /// Load the sound, which is played at the volume from 0 to 1.
+    pub async fn load(path: &str, volume: f32) -> Option<Self> {
+        match load_sound(path).await {
+            Ok(sound) => Some(Self {
+                sound,
+                volume: volume.clamp(0., 1.),
+                typed_chars: 0,
+                last_played: 0.,
+                muted: false,
+            }),
+            Err(e) => {
+                warn!("Couldn't load typing sound {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Play a keystroke if more characters are visible than in the last
+    /// frame. Fewer visible characters means that the animation restarted.
+    pub fn update(&mut self, visible_chars: usize) {
+        let typed = visible_chars > self.typed_chars;
+        self.typed_chars = visible_chars;
+        if typed && !self.muted && get_time() - self.last_played >= KEYSTROKE_INTERVAL {
+            self.last_played = get_time();
+            play_sound(
+                &self.sound,
+                PlaySoundParams {
+                    looped: false,
+                    volume: self.volume,
+                },
+            );
+        }
+    }
+}
+
+/// An audio track that loops in the background during the presentation,
+/// like for a demo kiosk.
+pub struct BackgroundAudio {
+    sound: Sound,
+    volume: f32,
+}
+
+impl BackgroundAudio {
+    /// Load the track and start playing it at the volume from 0 to 1.
+    pub async fn play(path: &str, volume: f32) -> Option<Self> {
+        let sound = load_sound(path)
+            .await
+            .map_err(|e| warn!("Couldn't load audio track {}: {}", path, e))
+            .ok()?;
+        let volume = volume.clamp(0., 1.);
+        play_sound(
+            &sound,
+            PlaySoundParams {
+                looped: true,
+                volume,
+            },
+        );
+        Some(Self { sound, volume })
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        let volume = match muted {
+            true => 0.,
+            false => self.volume,
+        };
+        set_sound_volume(&self.sound, volume);
+    }
+}
@@ -0,0 +1,82 @@
+use {macroquad::prelude::*, std::str::FromStr};
+
+/// Height of the progress bar at the bottom of the screen, before scaling.
+const PROGRESS_HEIGHT: f32 = 4.;
+const PROGRESS_COLOR: Color = Color::new(1., 1., 1., 0.5);
+
+/// A length of time, parsed from a string like `15s`, `500ms`, `2m` or `1h`.
+/// A number without a unit is in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub seconds: f64,
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number = number
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid interval: {}", s))?;
+        let factor = match unit.trim() {
+            "ms" => 0.001,
+            "" | "s" => 1.,
+            "m" | "min" => 60.,
+            "h" => 3600.,
+            unit => return Err(format!("Unknown unit {}, use ms, s, m or h", unit)),
+        };
+        match number > 0. {
+            true => Ok(Interval {
+                seconds: number * factor,
+            }),
+            false => Err(format!("The interval must be longer than zero: {}", s)),
+        }
+    }
+}
+
+/// Moves to the next file when the interval has passed, showing the time
+/// left as a bar at the bottom of the screen.
+pub struct AutoAdvance {
+    interval: Interval,
+    start_time: f64,
+}
+
+impl AutoAdvance {
+    pub fn new(interval: Interval) -> Self {
+        Self {
+            interval,
+            start_time: get_time(),
+        }
+    }
+
+    /// Start counting the interval from now, after moving to another file.
+    pub fn restart(&mut self) {
+        self.start_time = get_time();
+    }
+
+    /// Returns true when it is time to move to the next file.
+    pub fn is_due(&self) -> bool {
+        self.progress() >= 1.
+    }
+
+    /// Fraction of the interval that has passed.
+    fn progress(&self) -> f32 {
+        ((get_time() - self.start_time) / self.interval.seconds) as f32
+    }
+
+    pub fn draw(&self, scale: f32) {
+        let height = PROGRESS_HEIGHT * scale;
+        draw_rectangle(
+            0.,
+            screen_height() - height,
+            screen_width() * self.progress().min(1.),
+            height,
+            PROGRESS_COLOR,
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use {crate::CodeError, macroquad::prelude::*, std::path::Path};
+
+/// What to draw behind the code box.
+pub enum Background {
+    /// A material with a fragment shader, the gradient by default.
+    Shader(Material),
+    /// A solid color, which may be transparent.
+    Color(Color),
+}
+
+impl Background {
+    /// Load the background shader from the file if one is given, falling
+    /// back to the default gradient if it can't be loaded.
+    pub async fn shader(shader_path: Option<&Path>) -> Self {
+        if let Some(shader_path) = shader_path {
+            let material = load_string(&shader_path.to_string_lossy())
+                .await
+                .and_then(|shader| background_material(&shader));
+            match material {
+                Ok(material) => return Background::Shader(material),
+                Err(e) => error!(
+                    "Couldn't load background shader {}: {}",
+                    shader_path.display(),
+                    CodeError::from(e)
+                ),
+            }
+        }
+        Background::Shader(
+            background_material(GRADIENT_FRAGMENT_SHADER).expect("Couldn't load material"),
+        )
+    }
+
+    /// Fill a canvas of the given size with the background, `time` is the
+    /// number of seconds passed to the shader.
+    pub fn draw(&self, width: f32, height: f32, time: f32) {
+        match self {
+            Background::Shader(material) => {
+                clear_background(WHITE);
+                gl_use_material(material);
+                material.set_uniform("canvasSize", (width, height));
+                material.set_uniform("time", time);
+                draw_rectangle(0., 0., width, height, WHITE);
+                gl_use_default_material();
+            }
+            Background::Color(color) => clear_background(*color),
+        }
+    }
+}
+
+fn background_material(fragment_shader: &str) -> Result<Material, macroquad::Error> {
+    load_material(
+        ShaderSource {
+            glsl_vertex: Some(GRADIENT_VERTEX_SHADER),
+            glsl_fragment: Some(fragment_shader),
+            metal_shader: None,
+        },
+        MaterialParams {
+            uniforms: vec![
+                ("canvasSize".to_owned(), UniformType::Float2),
+                ("time".to_owned(), UniformType::Float1),
+            ],
+            ..Default::default()
+        },
+    )
+}
+
+const GRADIENT_FRAGMENT_SHADER: &str = r#"#version 100
+precision lowp float;
+uniform vec2 canvasSize;
+uniform sampler2D Texture;
+
+void main() {
+    vec2 coord = gl_FragCoord.xy/canvasSize.xy;
+    gl_FragColor = vec4(coord.x, coord.y, 1.-coord.x, 1);
+}
+"#;
+
+const GRADIENT_VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+uniform mat4 Model;
+uniform mat4 Projection;
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+}
+";
@@ -0,0 +1,119 @@
+/// Syntax scopes colored by each of the base16 palette colors.
+const SCOPE_COLORS: &[(&str, usize)] = &[
+    ("comment, punctuation.definition.comment", 0x03),
+    (
+        "variable, punctuation.definition.variable, entity.name.tag, markup.deleted",
+        0x08,
+    ),
+    (
+        "constant, support.constant, variable.parameter, entity.other.attribute-name",
+        0x09,
+    ),
+    (
+        "entity.name.type, entity.name.class, support.type, support.class",
+        0x0a,
+    ),
+    (
+        "string, constant.other.symbol, entity.other.inherited-class, markup.inserted",
+        0x0b,
+    ),
+    (
+        "constant.character.escape, string.regexp, support.other.regex",
+        0x0c,
+    ),
+    (
+        "entity.name.function, meta.function-call, support.function, entity.name.section",
+        0x0d,
+    ),
+    ("keyword, storage.type, storage.modifier", 0x0e),
+    ("keyword.operator, punctuation", 0x05),
+    ("invalid.deprecated, meta.embedded", 0x0f),
+];
+
+/// A base16 color scheme, parsed from a YAML file with the colors `base00`
+/// to `base0F` given as hex strings.
+#[derive(Debug)]
+pub struct Base16 {
+    colors: [String; 16],
+}
+
+impl Base16 {
+    /// Parse the palette from a base16 YAML scheme. Only the `baseXX` keys
+    /// are read, so both the classic flat format and schemes with the colors
+    /// nested under `palette` are supported.
+    pub fn parse(yaml: &str) -> Option<Self> {
+        let mut colors: [Option<String>; 16] = Default::default();
+        for line in yaml.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let Some(index) = key
+                .trim()
+                .strip_prefix("base")
+                .and_then(|index| usize::from_str_radix(index, 16).ok())
+                .filter(|index| *index < 16)
+            else {
+                continue;
+            };
+            let hex = value
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .trim_matches(['"', '\''])
+                .trim_start_matches('#');
+            if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                colors[index] = Some(format!("#{}", hex.to_lowercase()));
+            }
+        }
+        let colors: Vec<String> = colors.into_iter().collect::<Option<_>>()?;
+        Some(Self {
+            colors: colors.try_into().ok()?,
+        })
+    }
+
+    pub fn color(&self, index: usize) -> &str {
+        &self.colors[index]
+    }
+
+    /// Build a TextMate color scheme from the palette, following the usual
+    /// base16 styling guidelines.
+    pub fn to_tm_theme(&self) -> String {
+        let mut scopes = String::new();
+        for (scope, index) in SCOPE_COLORS {
+            scopes.push_str(&format!(
+                "<dict><key>scope</key><string>{}</string><key>settings</key><dict><key>foreground</key><string>{}</string></dict></dict>\n",
+                scope,
+                self.color(*index)
+            ));
+        }
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+<key>settings</key>
+<array>
+<dict><key>settings</key><dict>
+<key>background</key><string>{}</string>
+<key>foreground</key><string>{}</string>
+<key>caret</key><string>{}</string>
+<key>lineHighlight</key><string>{}</string>
+<key>selection</key><string>{}</string>
+<key>gutter</key><string>{}</string>
+<key>gutterForeground</key><string>{}</string>
+</dict></dict>
+{}</array>
+</dict>
+</plist>
+"#,
+            self.color(0x00),
+            self.color(0x05),
+            self.color(0x05),
+            self.color(0x02),
+            self.color(0x02),
+            self.color(0x01),
+            self.color(0x03),
+            scopes
+        )
+    }
+}
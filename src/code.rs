@@ -0,0 +1,538 @@
+use {
+    crate::{
+        codebox::{BlameLine, LineChange, SemanticToken},
+        detect,
+        folding::Folds,
+        github::GithubPermalink,
+        line_ranges::LineSlice,
+        picker,
+    },
+    jsonpath_rust::JsonPathFinder,
+    macroquad::prelude::*,
+    quad_net::http_request::{HttpError, RequestBuilder},
+    std::{
+        collections::HashMap,
+        error, fmt,
+        io::Read,
+        path::{Path, PathBuf},
+    },
+};
+
+/// A Gist to load code from.
+#[derive(Clone, Debug)]
+pub struct Gist {
+    pub id: String,
+    /// GitHub token used to access private Gists.
+    pub token: Option<String>,
+    /// Name of the file to display from a Gist with multiple files.
+    pub filename: Option<String>,
+    /// Revision of the Gist to load, defaults to the latest revision.
+    pub revision: Option<String>,
+}
+
+/// Where to load the code to display from.
+#[derive(Clone, Debug)]
+pub enum CodeSource {
+    Code(String),
+    Clipboard,
+    Gist(Gist),
+    Github(String),
+    Url(String),
+    Stdin,
+    /// A shell command, whose output is displayed.
+    #[cfg(not(target_arch = "wasm32"))]
+    Command(String),
+    File(PathBuf),
+    Directory(PathBuf),
+}
+
+impl CodeSource {
+    /// Name of the file the code is loaded from, without loading it.
+    pub fn filename(&self) -> String {
+        match self {
+            CodeSource::Gist(gist) => gist.filename.clone().unwrap_or_else(|| gist.id.clone()),
+            CodeSource::Github(url) => GithubPermalink::parse(url)
+                .map(|permalink| permalink.filename)
+                .unwrap_or_else(|| filename_from_url(url)),
+            CodeSource::Url(url) => filename_from_url(url),
+            CodeSource::File(path) | CodeSource::Directory(path) => file_name(path),
+            CodeSource::Code(_) | CodeSource::Clipboard | CodeSource::Stdin => {
+                "noname.txt".to_string()
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            CodeSource::Command(_) => "noname.txt".to_string(),
+        }
+    }
+
+    /// Web page where the code can be viewed, for code loaded from the web.
+    pub fn url(&self) -> Option<String> {
+        match self {
+            CodeSource::Gist(gist) => Some(match &gist.revision {
+                Some(revision) => format!("https://gist.github.com/{}/{}", gist.id, revision),
+                None => format!("https://gist.github.com/{}", gist.id),
+            }),
+            CodeSource::Github(url) | CodeSource::Url(url) => Some(url.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Code to display, and what is known about it.
+pub struct Code {
+    pub filename: String,
+    pub sourcecode: String,
+    /// Lines to display, if the source only refers to part of the file.
+    pub lines: Option<LineSlice>,
+    /// Semantic highlighting from a language server, fetched once when the
+    /// code is loaded.
+    pub semantic_tokens: Vec<SemanticToken>,
+    /// Author and commit time of each line, if the file is in a git repository.
+    pub blame: Vec<BlameLine>,
+    /// Lines changed since the last commit, if the file is in a git repository.
+    pub git_changes: HashMap<usize, LineChange>,
+    /// Blocks of the code that can be folded, and which are folded.
+    pub folds: Folds,
+    /// Language given together with the code, instead of the language option.
+    pub language: Option<String>,
+}
+
+impl Code {
+    pub fn new(filename: String, sourcecode: String) -> Self {
+        let folds = Folds::new(&sourcecode);
+        Self {
+            filename,
+            sourcecode,
+            lines: None,
+            semantic_tokens: Vec::new(),
+            blame: Vec::new(),
+            git_changes: HashMap::new(),
+            folds,
+            language: None,
+        }
+    }
+
+    pub fn from_sourcecode(sourcecode: String) -> Self {
+        Self::new("noname.txt".to_string(), sourcecode)
+    }
+
+    /// Extension of the filename, to find the syntax of the code by.
+    pub fn extension(&self) -> Option<String> {
+        Path::new(&self.filename)
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned())
+    }
+
+    /// Language of the code, from the language given with the code, the
+    /// override, a modeline, the file extension or the content.
+    pub fn language(&self, language_override: Option<String>) -> Option<String> {
+        // A modeline is an explicit choice in the file, so it wins over the
+        // extension. Plain text files, and code without a filename, may
+        // still be recognized by their contents.
+        self.language
+            .clone()
+            .or(language_override)
+            .or_else(|| detect::from_modeline(&self.sourcecode))
+            .or_else(|| {
+                detect_lang::from_path(&self.filename)
+                    .map(|lang| lang.id().to_string())
+                    .filter(|lang| lang != "text")
+            })
+            .or_else(|| detect::from_content(&self.sourcecode))
+    }
+
+    /// Load the code from the source.
+    pub async fn load(source: CodeSource) -> Result<Code> {
+        match source {
+            CodeSource::Code(content) => Ok(Code::from_sourcecode(content)),
+            CodeSource::Clipboard => macroquad::miniquad::window::clipboard_get()
+                .map(Code::from_sourcecode)
+                .ok_or(CodeError::Clipboard),
+            CodeSource::Gist(gist) => get_gist_file(gist).await,
+            CodeSource::Github(url) => get_github_file(url).await,
+            CodeSource::Url(url) => get_url_file(url).await,
+            CodeSource::Stdin => Self::read_stdin(),
+            #[cfg(not(target_arch = "wasm32"))]
+            CodeSource::Command(command) => Self::run_command(&command),
+            CodeSource::File(path) => Self::load_file(&path).await,
+            CodeSource::Directory(path) => {
+                let file = browse_directory(&path).await?;
+                Self::load_file(&file).await
+            }
+        }
+    }
+
+    /// Load the code from the source without Macroquad, which needs a
+    /// window, blocking until it is loaded. There is no picker, so a
+    /// directory, or a Gist with more than one file and none named, can't
+    /// be loaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_blocking(source: CodeSource) -> Result<Code> {
+        match source {
+            CodeSource::Code(content) => Ok(Code::from_sourcecode(content)),
+            CodeSource::Clipboard => arboard::Clipboard::new()
+                .and_then(|mut clipboard| clipboard.get_text())
+                .map(Code::from_sourcecode)
+                .map_err(|_| CodeError::Clipboard),
+            CodeSource::Gist(gist) => {
+                let (url, headers) = gist_request(&gist);
+                let headers: Vec<(&str, &str)> = headers
+                    .iter()
+                    .map(|(name, value)| (*name, value.as_str()))
+                    .collect();
+                let json = http_get_blocking(&url, &headers)
+                    .map_err(|e| CodeError::GistLoad(gist.id.clone(), e))?;
+                let files = parse_gist_response(json)?;
+                match gist.filename {
+                    None if files.len() > 1 => Err(CodeError::NoFileSelected),
+                    filename => find_gist_file(files, filename),
+                }
+            }
+            CodeSource::Github(url) => {
+                let permalink = GithubPermalink::parse(&url).ok_or(CodeError::GithubUrl(url))?;
+                let mut code = http_get_blocking(&permalink.raw_url, &[])
+                    .map(|code| Code::new(permalink.filename, code))
+                    .map_err(|e| CodeError::UrlLoad(permalink.raw_url, e))?;
+                code.lines = permalink.lines;
+                Ok(code)
+            }
+            CodeSource::Url(url) => http_get_blocking(&url, &[])
+                .map(|code| Code::new(filename_from_url(&url), code))
+                .map_err(|e| CodeError::UrlLoad(url, e)),
+            CodeSource::Stdin => Self::read_stdin(),
+            CodeSource::Command(command) => Self::run_command(&command),
+            CodeSource::File(path) => {
+                let file = path.to_string_lossy().into_owned();
+                std::fs::read_to_string(&path)
+                    .map(|code| Code::new(file.clone(), code))
+                    .map_err(|e| CodeError::File(file, macroquad::miniquad::fs::Error::IOError(e)))
+            }
+            CodeSource::Directory(_) => Err(CodeError::NoFileSelected),
+        }
+    }
+
+    async fn load_file(path: &Path) -> Result<Code> {
+        let file = path.to_string_lossy().into_owned();
+        load_string(&file)
+            .await
+            .map(|code| Code::new(file, code))
+            .map_err(|e| e.into())
+    }
+
+    fn read_stdin() -> Result<Code> {
+        let mut sourcecode = String::new();
+        std::io::stdin()
+            .read_to_string(&mut sourcecode)
+            .map_err(CodeError::Stdin)?;
+        Ok(Code::from_sourcecode(sourcecode))
+    }
+
+    /// Run the command in a shell and use what it prints to stdout as the
+    /// code.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_command(command: &str) -> Result<Code> {
+        let output = match cfg!(windows) {
+            true => std::process::Command::new("cmd")
+                .args(["/C", command])
+                .output(),
+            false => std::process::Command::new("sh")
+                .args(["-c", command])
+                .output(),
+        }
+        .map_err(|e| CodeError::Command(command.to_string(), e.to_string()))?;
+        if !output.status.success() {
+            return Err(CodeError::Command(
+                command.to_string(),
+                String::from_utf8_lossy(&output.stderr)
+                    .trim_end()
+                    .to_string(),
+            ));
+        }
+        Ok(Code::from_sourcecode(
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+        ))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, CodeError>;
+
+#[derive(Debug)]
+pub enum CodeError {
+    File(String, macroquad::miniquad::fs::Error),
+    GistLoad(String, HttpError),
+    UrlLoad(String, HttpError),
+    GithubUrl(String),
+    Stdin(std::io::Error),
+    Command(String, String),
+    Clipboard,
+    Directory(String, std::io::Error),
+    Font(String),
+    GistParse(String),
+    GistFileMissing(String),
+    NoFileSelected,
+    Macroquad(macroquad::Error),
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodeError::File(filename, _e) => write!(f, "Couldn't load file: {}", filename),
+            CodeError::GistLoad(gist_id, _e) => {
+                write!(f, "Couldn't load Gist with ID: {}", gist_id)
+            }
+            CodeError::UrlLoad(url, _e) => write!(f, "Couldn't load URL: {}", url),
+            CodeError::GithubUrl(url) => write!(f, "Couldn't parse GitHub URL: {}", url),
+            CodeError::Stdin(e) => write!(f, "Couldn't read from stdin: {}", e),
+            CodeError::Command(command, message) => {
+                write!(f, "Couldn't run command {}: {}", command, message)
+            }
+            CodeError::Clipboard => write!(f, "Couldn't read code from clipboard"),
+            CodeError::Directory(path, e) => write!(f, "Couldn't read directory {}: {}", path, e),
+            CodeError::Font(error) => write!(f, "Couldn't load font: {:?}", error),
+            CodeError::GistParse(message) => write!(f, "Couldn't parse JSON: {}", message),
+            CodeError::GistFileMissing(filename) => {
+                write!(f, "Couldn't find file in Gist: {}", filename)
+            }
+            CodeError::NoFileSelected => write!(f, "No file selected"),
+            CodeError::Macroquad(err) => write!(f, "Macroquad error: {:?}", err),
+        }
+    }
+}
+
+impl error::Error for CodeError {}
+
+impl From<macroquad::Error> for CodeError {
+    fn from(err: macroquad::Error) -> CodeError {
+        match err {
+            macroquad::Error::FontError(msg) => CodeError::Font(msg.to_string()),
+            macroquad::Error::FileError { kind, path } => CodeError::File(path.clone(), kind),
+            macroquad::Error::ShaderError(_) => CodeError::Macroquad(err),
+            macroquad::Error::ImageError(_) => CodeError::Macroquad(err),
+            macroquad::Error::UnknownError(_) => CodeError::Macroquad(err),
+        }
+    }
+}
+
+pub async fn http_get(
+    url: &str,
+    headers: &[(&str, &str)],
+) -> std::result::Result<String, HttpError> {
+    let mut request_builder = RequestBuilder::new(url);
+    for (name, value) in headers {
+        request_builder = request_builder.header(name, value);
+    }
+    let mut request = request_builder.send();
+    loop {
+        if let Some(result) = request.try_recv() {
+            return result;
+        };
+        next_frame().await;
+    }
+}
+
+/// Download the text at the URL with ureq, blocking until it is done.
+#[cfg(not(target_arch = "wasm32"))]
+fn http_get_blocking(
+    url: &str,
+    headers: &[(&str, &str)],
+) -> std::result::Result<String, HttpError> {
+    let mut request = ureq::get(url);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    request
+        .call()
+        .map_err(HttpError::UreqError)?
+        .into_string()
+        .map_err(|_| HttpError::IOError)
+}
+
+/// URL and headers of the GitHub API request for the Gist.
+fn gist_request(gist: &Gist) -> (String, Vec<(&'static str, String)>) {
+    let url = match &gist.revision {
+        Some(revision) => format!("https://api.github.com/gists/{}/{}", gist.id, revision),
+        None => format!("https://api.github.com/gists/{}", gist.id),
+    };
+    let mut headers = vec![("Accept", "application/vnd.github.v3+json".to_string())];
+    if let Some(token) = &gist.token {
+        headers.push(("Authorization", format!("Bearer {}", token)));
+    }
+    (url, headers)
+}
+
+/// Load the JSON description of the Gist from the GitHub API.
+pub async fn load_gist(gist: Gist) -> Result<String> {
+    let (url, headers) = gist_request(&gist);
+    let headers: Vec<(&str, &str)> = headers
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+    http_get(&url, &headers)
+        .await
+        .map_err(|e| CodeError::GistLoad(gist.id, e))
+}
+
+/// Parse the filename and content of each file in the Gist.
+pub fn parse_gist_response(json: String) -> Result<Vec<Code>> {
+    let finder = JsonPathFinder::from_str(&json, "$.files.*['filename', 'content']")
+        .map_err(CodeError::GistParse)?;
+    let values = finder
+        .find_slice()
+        .into_iter()
+        .map(|value| {
+            value
+                .to_data()
+                .as_str()
+                .map(|value| value.to_string())
+                .ok_or_else(|| CodeError::GistParse("Couldn't parse file".to_string()))
+        })
+        .collect::<Result<Vec<String>>>()?;
+    if values.len() < 2 {
+        return Err(CodeError::GistParse("File missing".to_string()));
+    }
+    Ok(values
+        .chunks_exact(2)
+        .map(|file| {
+            debug!("gist filename:\n{},\ngist_content:\n{}", file[0], file[1]);
+            Code::new(file[0].clone(), file[1].clone())
+        })
+        .collect())
+}
+
+/// Load a file from the Gist, letting the user pick one if the Gist has
+/// more than one file and none is named.
+pub async fn get_gist_file(gist: Gist) -> Result<Code> {
+    let filename = gist.filename.clone();
+    let json = load_gist(gist).await?;
+    let mut files = parse_gist_response(json)?;
+    match filename {
+        None if files.len() > 1 => {
+            let filenames: Vec<String> = files.iter().map(|file| file.filename.clone()).collect();
+            let index = picker::pick("Select a file from the Gist", &filenames)
+                .await
+                .ok_or(CodeError::NoFileSelected)?;
+            Ok(files.swap_remove(index))
+        }
+        filename => find_gist_file(files, filename),
+    }
+}
+
+/// The named file from the Gist, or its first file.
+fn find_gist_file(mut files: Vec<Code>, filename: Option<String>) -> Result<Code> {
+    match filename {
+        Some(filename) => files
+            .into_iter()
+            .find(|file| file.filename == filename)
+            .ok_or(CodeError::GistFileMissing(filename)),
+        None => Ok(files.swap_remove(0)),
+    }
+}
+
+pub async fn get_url_file(url: String) -> Result<Code> {
+    let filename = filename_from_url(&url);
+    http_get(&url, &[])
+        .await
+        .map(|code| Code::new(filename, code))
+        .map_err(|e| CodeError::UrlLoad(url, e))
+}
+
+pub async fn get_github_file(url: String) -> Result<Code> {
+    let permalink = GithubPermalink::parse(&url).ok_or(CodeError::GithubUrl(url))?;
+    let mut code = http_get(&permalink.raw_url, &[])
+        .await
+        .map(|code| Code::new(permalink.filename, code))
+        .map_err(|e| CodeError::UrlLoad(permalink.raw_url, e))?;
+    code.lines = permalink.lines;
+    Ok(code)
+}
+
+/// Let the user pick a file by browsing the directory and its subdirectories.
+pub async fn browse_directory(root: &Path) -> Result<PathBuf> {
+    let mut directory = root.to_path_buf();
+    loop {
+        let mut entries: Vec<(String, PathBuf)> = std::fs::read_dir(&directory)
+            .map_err(|e| CodeError::Directory(directory.to_string_lossy().into_owned(), e))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| !is_hidden(path))
+            .map(|path| match path.is_dir() {
+                true => (format!("{}/", file_name(&path)), path),
+                false => (file_name(&path), path),
+            })
+            .collect();
+        entries.sort_by_key(|(name, path)| (!path.is_dir(), name.to_lowercase()));
+        if directory != root {
+            if let Some(parent) = directory.parent() {
+                entries.insert(0, ("../".to_string(), parent.to_path_buf()));
+            }
+        }
+
+        let names: Vec<String> = entries.iter().map(|(name, _)| name.clone()).collect();
+        let title = format!("Select a file in {}", directory.display());
+        let index = picker::pick(&title, &names)
+            .await
+            .ok_or(CodeError::NoFileSelected)?;
+        let (_, path) = entries.swap_remove(index);
+        if !path.is_dir() {
+            return Ok(path);
+        }
+        directory = path;
+    }
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn is_hidden(path: &Path) -> bool {
+    file_name(path).starts_with('.')
+}
+
+/// Name of the file in the Gists of Rust Playground share links.
+pub const PLAYGROUND_FILENAME: &str = "playground.rs";
+
+/// Id of the Gist that a Rust Playground share link, like
+/// `https://play.rust-lang.org/?version=stable&gist=<id>`, stores the code in.
+/// A bare id is returned as it is.
+pub fn playground_gist_id(share: &str) -> String {
+    share
+        .split(['?', '&'])
+        .find_map(|parameter| parameter.strip_prefix("gist="))
+        .unwrap_or(share)
+        .to_string()
+}
+
+/// The last segment of the path in the URL, used to detect the language.
+fn filename_from_url(url: &str) -> String {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.trim_end_matches('/').rsplit('/').next())
+        .filter(|filename| !filename.is_empty())
+        .unwrap_or("noname.txt")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_gist_of_playground_links() {
+        assert_eq!(
+            playground_gist_id(
+                "https://play.rust-lang.org/?version=stable&mode=debug&edition=2021&gist=4c5d0b4b"
+            ),
+            "4c5d0b4b"
+        );
+        assert_eq!(
+            playground_gist_id("https://play.rust-lang.org/?gist=4c5d0b4b&version=nightly"),
+            "4c5d0b4b"
+        );
+    }
+
+    #[test]
+    fn keeps_bare_gist_ids() {
+        assert_eq!(playground_gist_id("4c5d0b4b"), "4c5d0b4b");
+    }
+}
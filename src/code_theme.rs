@@ -0,0 +1,350 @@
+use {
+    crate::{
+        base16::Base16,
+        contrast::{extreme_for, with_contrast, HIGH_CONTRAST_RATIO},
+    },
+    macroquad::prelude::*,
+    nanoserde::DeJson,
+    std::{
+        collections::HashMap,
+        io::Cursor,
+        path::{Path, PathBuf},
+    },
+    syntect::highlighting::{self, ThemeSet},
+};
+
+/// Names of the color schemes bundled with the syntax highlighter, which can
+/// be used instead of a theme file and cycled through at runtime.
+pub const BUNDLED_THEMES: &[&str] = &[
+    "Solarized (dark)",
+    "Solarized (light)",
+    "base16-ocean.dark",
+    "base16-eighties.dark",
+    "base16-mocha.dark",
+    "base16-ocean.light",
+    "InspiredGitHub",
+];
+
+/// Settings for the code box that aren't covered by the rusty-slider theme.
+/// Read from the same theme.json file, missing keys use default values.
+/// The colors can also be derived from a TextMate `.tmTheme` color scheme or
+/// a base16 YAML scheme.
+#[derive(Clone, Debug, DeJson)]
+#[nserde(default)]
+pub struct CodeTheme {
+    pub code_background_color: String,
+    pub gutter_background_color: String,
+    pub gutter_text_color: String,
+    pub wrap_marker_color: String,
+    /// Color of the dots and arrows shown for whitespace.
+    pub whitespace_color: String,
+    /// Background color of whitespace at the end of lines.
+    pub trailing_whitespace_color: String,
+    pub highlight_line_color: String,
+    pub diff_added_color: String,
+    pub diff_removed_color: String,
+    pub diff_hunk_color: String,
+    /// Colors of the markers for lines changed since the last git commit.
+    pub git_added_color: String,
+    pub git_modified_color: String,
+    pub git_removed_color: String,
+    /// Colors of the squiggly underlines of diagnostics by severity.
+    pub diagnostic_error_color: String,
+    pub diagnostic_warning_color: String,
+    pub diagnostic_info_color: String,
+    pub header_background_color: String,
+    pub header_text_color: String,
+    /// Path to a font to draw emoji in the code with, like Noto Emoji. No
+    /// font is bundled, so emoji are drawn with the code font unless it is
+    /// set, or if the font can't be loaded.
+    pub font_emoji: String,
+    /// Paths to fonts to draw the characters that the code font doesn't
+    /// have with, like CJK or box-drawing characters, tried in order.
+    pub font_fallbacks: Vec<String>,
+    /// Path to a Nerd Font to draw an icon of the language before the
+    /// filename in the header with.
+    pub font_icons: String,
+    /// Glyphs of the icon font to use for languages, like `{"rust": "\ue7a8"}`,
+    /// instead of the bundled devicons.
+    pub file_icons: HashMap<String, String>,
+    /// Draw vertical lines at each level of indentation.
+    pub indent_guides: bool,
+    pub indent_guide_color: String,
+    pub ruler_color: String,
+    /// Colors of brackets at each nesting depth, when rainbow brackets are shown.
+    pub rainbow_bracket_colors: Vec<String>,
+    /// Substitute symbols like → and ≠ for operators like `->` and `!=`,
+    /// outside of strings. The operators are replaced symbol by symbol, the
+    /// font's own ligatures aren't used.
+    pub code_symbols: bool,
+    /// Radius of the rounded corners of the code box, zero for square corners.
+    pub corner_radius: f32,
+    pub shadow_color: String,
+    /// Width of the soft edge of the drop shadow behind the code box, zero
+    /// for a hard edge.
+    pub shadow_blur: f32,
+    /// Offset of the drop shadow from the code box. There is no shadow when
+    /// the blur and both offsets are zero.
+    pub shadow_offset_x: f32,
+    pub shadow_offset_y: f32,
+    /// Text to draw in a corner of the screen, like an attribution.
+    pub watermark_text: String,
+    /// Path to a small image, like a logo, to draw next to the watermark text.
+    pub watermark_image: String,
+    /// Corner to draw the watermark in: top-left, top-right, bottom-left or bottom-right.
+    pub watermark_position: String,
+    pub watermark_color: String,
+    pub watermark_font_size: f32,
+    /// Color of the progress bar or counter of the walkthrough.
+    pub progress_color: String,
+    /// Animation when switching files: none, fade, slide or zoom.
+    pub transition: String,
+    /// Length of the transition in seconds.
+    pub transition_duration: f32,
+    /// Contents of a TextMate `.tmTheme` color scheme to highlight the code
+    /// with, instead of the named `code_theme` of the rusty-slider theme.
+    pub tm_theme: String,
+}
+
+impl Default for CodeTheme {
+    fn default() -> Self {
+        Self {
+            code_background_color: "#002b36".to_string(),
+            gutter_background_color: "#073642".to_string(),
+            gutter_text_color: "#586e75".to_string(),
+            wrap_marker_color: "#586e75".to_string(),
+            whitespace_color: "#586e7580".to_string(),
+            trailing_whitespace_color: "#dc322f80".to_string(),
+            highlight_line_color: "#0e4a5c".to_string(),
+            diff_added_color: "#1e4620".to_string(),
+            diff_removed_color: "#5c1f1f".to_string(),
+            diff_hunk_color: "#1f3a5c".to_string(),
+            git_added_color: "#859900".to_string(),
+            git_modified_color: "#268bd2".to_string(),
+            git_removed_color: "#dc322f".to_string(),
+            diagnostic_error_color: "#dc322f".to_string(),
+            diagnostic_warning_color: "#b58900".to_string(),
+            diagnostic_info_color: "#268bd2".to_string(),
+            header_background_color: "#073642".to_string(),
+            header_text_color: "#93a1a1".to_string(),
+            font_emoji: String::new(),
+            font_fallbacks: Vec::new(),
+            font_icons: String::new(),
+            file_icons: HashMap::new(),
+            indent_guides: false,
+            indent_guide_color: "#586e7560".to_string(),
+            ruler_color: "#586e7580".to_string(),
+            rainbow_bracket_colors: vec![
+                "#ffd700".to_string(),
+                "#da70d6".to_string(),
+                "#179fff".to_string(),
+            ],
+            code_symbols: false,
+            corner_radius: 0.,
+            shadow_color: "#00000080".to_string(),
+            shadow_blur: 0.,
+            shadow_offset_x: 0.,
+            shadow_offset_y: 0.,
+            watermark_text: String::new(),
+            watermark_image: String::new(),
+            watermark_position: "bottom-right".to_string(),
+            watermark_color: "#ffffff80".to_string(),
+            watermark_font_size: 20.,
+            progress_color: "#268bd2".to_string(),
+            transition: "none".to_string(),
+            transition_duration: 0.4,
+            tm_theme: String::new(),
+        }
+    }
+}
+
+impl CodeTheme {
+    pub async fn load(theme_path: PathBuf) -> Self {
+        match load_string(&theme_path.to_string_lossy()).await {
+            Ok(content) => Self::parse(&theme_path, content),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Parse the content of the theme file, as a TextMate or base16 color
+    /// scheme or a JSON theme depending on the extension.
+    pub fn parse(theme_path: &Path, content: String) -> Self {
+        let extension = theme_path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "tmtheme" => Self::from_tm_theme(content),
+            "yaml" | "yml" => Self::from_base16(content),
+            _ => Self::deserialize_json(&content).unwrap_or_else(|e| {
+                warn!("Couldn't parse code theme: {:?}", e);
+                Self::default()
+            }),
+        }
+    }
+
+    /// Use the colors of one of the bundled color schemes for the code box.
+    pub fn bundled(name: &str) -> Option<Self> {
+        if !BUNDLED_THEMES.contains(&name) {
+            return None;
+        }
+        ThemeSet::load_defaults()
+            .themes
+            .get(name)
+            .map(Self::from_syntax_theme)
+    }
+
+    /// Use the colors of a TextMate color scheme for the code box.
+    fn from_tm_theme(content: String) -> Self {
+        match parse_tm_theme(&content) {
+            Some(syntax_theme) => Self {
+                tm_theme: content,
+                ..Self::from_syntax_theme(&syntax_theme)
+            },
+            None => Self::default(),
+        }
+    }
+
+    fn from_syntax_theme(syntax_theme: &highlighting::Theme) -> Self {
+        let defaults = Self::default();
+        let settings = &syntax_theme.settings;
+        let color = |color: Option<highlighting::Color>, fallback: &str| {
+            color
+                .map(syntect_color_to_hex)
+                .unwrap_or_else(|| fallback.to_string())
+        };
+        let gutter_text_color = color(
+            settings.gutter_foreground.or(settings.foreground),
+            &defaults.gutter_text_color,
+        );
+        Self {
+            code_background_color: color(settings.background, &defaults.code_background_color),
+            gutter_background_color: color(
+                settings.gutter.or(settings.background),
+                &defaults.gutter_background_color,
+            ),
+            wrap_marker_color: gutter_text_color.clone(),
+            whitespace_color: color(settings.invisibles, &defaults.whitespace_color),
+            indent_guide_color: color(settings.guide, &defaults.indent_guide_color),
+            ruler_color: color(settings.guide, &defaults.ruler_color),
+            gutter_text_color,
+            highlight_line_color: color(
+                settings.line_highlight.or(settings.selection),
+                &defaults.highlight_line_color,
+            ),
+            diff_added_color: "#2ea04340".to_string(),
+            diff_removed_color: "#f8514940".to_string(),
+            diff_hunk_color: "#388bfd40".to_string(),
+            git_added_color: "#2ea043".to_string(),
+            git_modified_color: "#388bfd".to_string(),
+            git_removed_color: "#f85149".to_string(),
+            header_background_color: color(
+                settings.gutter.or(settings.background),
+                &defaults.header_background_color,
+            ),
+            header_text_color: color(settings.foreground, &defaults.header_text_color),
+            progress_color: color(settings.accent.or(settings.caret), &defaults.progress_color),
+            ..defaults
+        }
+    }
+
+    /// Use the colors of a base16 palette for the code box.
+    fn from_base16(yaml: String) -> Self {
+        let Some(scheme) = Base16::parse(&yaml) else {
+            warn!("Couldn't parse base16 scheme, all colors base00 to base0F are needed");
+            return Self::default();
+        };
+        Self {
+            diff_added_color: format!("{}40", scheme.color(0x0b)),
+            diff_removed_color: format!("{}40", scheme.color(0x08)),
+            diff_hunk_color: format!("{}40", scheme.color(0x0d)),
+            git_added_color: scheme.color(0x0b).to_string(),
+            git_modified_color: scheme.color(0x0d).to_string(),
+            git_removed_color: scheme.color(0x08).to_string(),
+            progress_color: scheme.color(0x0d).to_string(),
+            ..Self::from_tm_theme(scheme.to_tm_theme())
+        }
+    }
+
+    /// The same theme with a black or white background, whichever is closest
+    /// to the background of the theme, and text colors with enough contrast
+    /// against it for WCAG level AAA.
+    pub fn high_contrast(&self) -> Self {
+        let background = match extreme_for(hex_color(&self.code_background_color)) == WHITE {
+            true => BLACK,
+            false => WHITE,
+        };
+        let text = |color: &str| {
+            color_to_hex(with_contrast(
+                hex_color(color),
+                background,
+                HIGH_CONTRAST_RATIO,
+            ))
+        };
+        Self {
+            code_background_color: color_to_hex(background),
+            gutter_background_color: color_to_hex(background),
+            header_background_color: color_to_hex(background),
+            gutter_text_color: text(&self.gutter_text_color),
+            header_text_color: text(&self.header_text_color),
+            wrap_marker_color: text(&self.wrap_marker_color),
+            rainbow_bracket_colors: self
+                .rainbow_bracket_colors
+                .iter()
+                .map(|color| text(color))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The syntax highlighting theme to use instead of the named code theme.
+    pub fn syntax_theme(&self) -> Option<highlighting::Theme> {
+        match self.tm_theme.is_empty() {
+            true => None,
+            false => parse_tm_theme(&self.tm_theme),
+        }
+    }
+}
+
+fn parse_tm_theme(content: &str) -> Option<highlighting::Theme> {
+    ThemeSet::load_from_reader(&mut Cursor::new(content.as_bytes()))
+        .map_err(|e| warn!("Couldn't parse tmTheme: {:?}", e))
+        .ok()
+}
+
+fn syntect_color_to_hex(color: highlighting::Color) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        color.r, color.g, color.b, color.a
+    )
+}
+
+/// Convert a hex color string like `#rrggbb` or `#rrggbbaa` into a Color.
+pub fn hex_color(hex: &str) -> Color {
+    let hex = hex.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).unwrap_or(0);
+    match hex.len() {
+        8 => Color::from_rgba(
+            (value >> 24) as u8,
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ),
+        _ => Color::from_rgba((value >> 16) as u8, (value >> 8) as u8, value as u8, 255),
+    }
+}
+
+/// Parse a hex color like `#rrggbb` or `#rrggbbaa` given on the command line.
+pub fn parse_hex_color(hex: &str) -> Result<Color, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    match (digits.len(), digits.chars().all(|c| c.is_ascii_hexdigit())) {
+        (6 | 8, true) => Ok(hex_color(digits)),
+        _ => Err(format!("Invalid color {}, use #rrggbb or #rrggbbaa", hex)),
+    }
+}
+
+/// Convert a Color into a hex color string like `#rrggbb`.
+pub fn color_to_hex(color: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = color.into();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
@@ -0,0 +1,2237 @@
+use {
+    crate::{
+        code_theme::{hex_color, CodeTheme},
+        colorblind::Colorblind,
+        contrast::{composite, with_contrast, HIGH_CONTRAST_RATIO},
+        fonts::FallbackFonts,
+        icons::{self, FileIcon},
+        line_ranges::{LineRanges, LineSlice},
+        shaping::shape_arabic,
+    },
+    clap::ValueEnum,
+    macroquad::prelude::*,
+    rusty_slider::prelude::Theme,
+    std::{
+        borrow::Cow, cmp::Reverse, collections::HashMap, ops::RangeInclusive, path::Path, sync::Arc,
+    },
+    syntect::{
+        easy::ScopeRangeIterator,
+        highlighting::{self, FontStyle, ThemeSet},
+        parsing::{ParseState, Scope, ScopeStack, SyntaxSet},
+        util::LinesWithEndings,
+    },
+    unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level},
+    unicode_width::UnicodeWidthChar,
+};
+
+const BOX_PADDING: f32 = 20.;
+pub(crate) const FALLBACK_CODE_THEME: &str = "Solarized (dark)";
+/// Height of the header, in number of lines.
+pub(crate) const HEADER_LINES: f32 = 1.6;
+/// Radius of the corners of the box when it is decorated as a window.
+const WINDOW_CORNER_RADIUS: f32 = 10.;
+/// Number of line segments used to draw each rounded corner.
+const CORNER_SEGMENTS: usize = 8;
+/// Character sequences of operators, with the symbol drawn in their place.
+/// This isn't font shaping, the symbol is drawn centered over the columns of
+/// the sequence, like the ligatures of programming fonts. There's no `<-`,
+/// since it's a comparison with a negative number in `x<-1`.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("->", "→"),
+    ("=>", "⇒"),
+    ("!=", "≠"),
+    ("<=", "≤"),
+    (">=", "≥"),
+];
+/// Number of characters of the author's name shown in the blame column.
+const BLAME_AUTHOR_COLUMNS: usize = 14;
+/// Number of characters of the age, long enough for `10 minutes`.
+const BLAME_AGE_COLUMNS: usize = 10;
+/// Width of the blame column: the author, the age and some space around them.
+const BLAME_COLUMNS: usize = BLAME_AUTHOR_COLUMNS + 1 + BLAME_AGE_COLUMNS + 2;
+/// Opacity of the blame annotations of the oldest lines, relative to the newest.
+const BLAME_MIN_ALPHA: f32 = 0.35;
+/// Shown in place of the lines of a folded block.
+const FOLD_MARKER: &str = "…";
+/// Drawn over spaces and tabs when whitespace is shown.
+const SPACE_MARKER: &str = "·";
+const TAB_MARKER: &str = "→";
+/// Width of the markers for lines changed since the last git commit.
+const GIT_GUTTER_WIDTH: f32 = 3.;
+/// How far to the right the code is drawn a second time to make it bold, in
+/// parts of the font size.
+const BOLD_OFFSET: f32 = 0.04;
+/// How far below the baseline underlined code is underlined, in parts of the
+/// font size.
+const UNDERLINE_OFFSET: f32 = 0.1;
+/// Number of layers used to fade out the edge of the drop shadow.
+const SHADOW_STEPS: usize = 12;
+/// Colors of the close, minimize and maximize buttons of the window controls.
+const WINDOW_CONTROL_COLORS: [Color; 3] = [
+    Color::new(1., 0.373, 0.337, 1.),
+    Color::new(1., 0.741, 0.18, 1.),
+    Color::new(0.153, 0.788, 0.247, 1.),
+];
+
+/// Marker drawn at the start of lines continued by soft wrapping.
+pub const WRAP_MARKER: &str = "↪";
+/// Number of columns continued lines are indented by to make room for the marker.
+pub const WRAP_INDENT: usize = 2;
+
+/// Backend used to highlight the syntax of the code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Highlighter {
+    /// TextMate grammars with syntect
+    #[default]
+    Syntect,
+    /// Tree-sitter grammars, for Rust, Python and JavaScript
+    #[cfg(feature = "tree-sitter")]
+    TreeSitter,
+}
+
+/// What kind of text a span of code is, for features that treat parts of
+/// the code differently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpanKind {
+    #[default]
+    Code,
+    Comment,
+    String,
+    /// The first column of a tab expanded to spaces, where the tab marker is
+    /// drawn
+    Tab,
+    /// The other columns of a tab expanded to spaces
+    TabPadding,
+}
+
+impl SpanKind {
+    /// The kind of code with the TextMate scope, like `comment.line.double-slash`.
+    pub fn from_scope(scope: &str) -> Self {
+        match scope.split('.').next() {
+            Some("comment") => SpanKind::Comment,
+            Some("string") => SpanKind::String,
+            _ => SpanKind::Code,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Span {
+    pub text: String,
+    pub color: Color,
+    pub kind: SpanKind,
+    /// Bold, italic or underlined, as the syntax highlighting theme styles it.
+    pub style: FontStyle,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, color: Color) -> Self {
+        Self {
+            text: text.into(),
+            color,
+            kind: SpanKind::Code,
+            style: FontStyle::empty(),
+        }
+    }
+
+    /// The characters of the span, with how each of them is drawn.
+    fn chars(&self) -> impl DoubleEndedIterator<Item = (char, Format)> + '_ {
+        let format = self.format();
+        self.text.chars().map(move |c| (c, format))
+    }
+
+    /// How the text of the span is drawn.
+    fn format(&self) -> Format {
+        Format {
+            color: self.color,
+            kind: self.kind,
+            style: self.style,
+        }
+    }
+}
+
+/// How a character of code is drawn, apart from the character itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Format {
+    color: Color,
+    kind: SpanKind,
+    style: FontStyle,
+}
+
+/// A range of characters on a line that a language server has classified,
+/// with the TextMate scope used to look up its color in the theme.
+#[derive(Clone, Debug)]
+pub struct SemanticToken {
+    /// Index of the line in the code, starting at 0.
+    pub line: usize,
+    /// Index of the first character of the token on the line.
+    pub start: usize,
+    /// Number of characters in the token.
+    pub length: usize,
+    pub scope: String,
+}
+
+/// Author and commit time of a line, from `git blame`.
+#[derive(Clone, Debug)]
+pub struct BlameLine {
+    pub author: String,
+    /// Unix timestamp of the commit that last changed the line.
+    pub time: u64,
+}
+
+/// How a line has changed since the last git commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    /// Lines were removed below this line.
+    Removed,
+}
+
+#[derive(Clone)]
+pub struct CodeLine {
+    pub number: usize,
+    /// True if this line is the continuation of a soft wrapped line.
+    pub continuation: bool,
+    /// Background color of the line, used for added and removed lines in diffs.
+    pub background: Option<Color>,
+    pub spans: Vec<Span>,
+}
+
+impl CodeLine {
+    /// Number of characters of code on the line.
+    pub fn char_count(&self) -> usize {
+        self.spans
+            .iter()
+            .map(|span| span.text.chars().count())
+            .sum()
+    }
+
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Number of cells the code on the line takes up, wide characters like
+    /// CJK take up two.
+    pub fn columns(&self) -> usize {
+        self.spans.iter().map(|span| text_columns(&span.text)).sum()
+    }
+
+    /// Number of cells taken up by the first `count` characters of the line.
+    pub fn columns_before(&self, count: usize) -> usize {
+        self.spans
+            .iter()
+            .flat_map(|span| span.text.chars())
+            .take(count)
+            .map(char_columns)
+            .sum()
+    }
+
+    /// The character boundary closest to `column` cells from the start of
+    /// the line.
+    pub fn char_index_at(&self, column: f32) -> usize {
+        let mut cells = 0.;
+        for (index, c) in self
+            .spans
+            .iter()
+            .flat_map(|span| span.text.chars())
+            .enumerate()
+        {
+            let width = char_columns(c) as f32;
+            if column < cells + width / 2. {
+                return index;
+            }
+            cells += width;
+        }
+        self.char_count()
+    }
+
+    /// Number of columns needed to draw the line.
+    pub fn width(&self) -> usize {
+        match self.continuation {
+            true => self.columns() + WRAP_INDENT,
+            false => self.columns(),
+        }
+    }
+}
+
+pub struct Gutter {
+    pub width: f32,
+    pub digits: usize,
+    pub background_color: Color,
+    pub text_color: Color,
+}
+
+/// Gutter column with the author and age of each line, to the right of the
+/// line numbers.
+pub struct Blame {
+    pub width: f32,
+    pub background_color: Color,
+    /// Annotation of each line of the code, faded by the age of the line.
+    pub annotations: Vec<Span>,
+}
+
+impl Blame {
+    /// Annotation of the line, unless it continues a wrapped line.
+    pub fn annotation(&self, line: &CodeLine) -> Option<&Span> {
+        match line.continuation {
+            true => None,
+            false => self.annotations.get(line.number - 1),
+        }
+    }
+}
+
+/// Colored markers at the left edge of lines changed since the last git commit.
+pub struct GitGutter {
+    /// Changes by line number, removed lines at the start of the file are on line 0.
+    pub changes: HashMap<usize, LineChange>,
+    pub width: f32,
+    pub added_color: Color,
+    pub modified_color: Color,
+    pub removed_color: Color,
+}
+
+impl GitGutter {
+    /// Bar to draw next to the line, for added and modified lines.
+    pub fn bar_color(&self, line: &CodeLine) -> Option<Color> {
+        match self.changes.get(&line.number) {
+            Some(LineChange::Added) => Some(self.added_color),
+            Some(LineChange::Modified) => Some(self.modified_color),
+            _ => None,
+        }
+    }
+
+    /// Show that lines were removed below the row at `index`, or above it
+    /// for the first line of the file.
+    pub fn removed_marker(&self, lines: &[CodeLine], index: usize) -> Option<MarkerEdge> {
+        let line = &lines[index];
+        let last_row = lines
+            .get(index + 1)
+            .map(|next| !next.continuation)
+            .unwrap_or(true);
+        if last_row && self.changes.get(&line.number) == Some(&LineChange::Removed) {
+            return Some(MarkerEdge::Bottom);
+        }
+        (line.number == 1 && !line.continuation && self.changes.contains_key(&0))
+            .then_some(MarkerEdge::Top)
+    }
+}
+
+/// Edge of a line that a marker for removed lines is drawn on.
+pub enum MarkerEdge {
+    Top,
+    Bottom,
+}
+
+/// Strip at the top of the code box showing the filename or a caption.
+pub struct Header {
+    pub title: String,
+    /// Icon of the language drawn before the title.
+    pub icon: Option<FileIcon>,
+    pub height: f32,
+    /// Draw window controls in the header, like the three colored dots of a macOS window.
+    pub window_controls: bool,
+    pub background_color: Color,
+    pub text_color: Color,
+}
+
+/// Soft shadow drawn behind the code box.
+pub struct Shadow {
+    pub color: Color,
+    /// Width of the fading edge of the shadow.
+    pub blur: f32,
+    pub offset: Vec2,
+}
+
+/// Vertical lines at each level of indentation.
+struct IndentGuides {
+    color: Color,
+    /// Number of columns per level of indentation.
+    width: usize,
+    /// Number of columns each line is indented by.
+    indents: Vec<usize>,
+}
+
+/// Syntax highlighted sourcecode that can be drawn on screen.
+pub struct CodeBox {
+    lines: Vec<CodeLine>,
+    /// Lines with right-to-left text by index, in the order they are drawn.
+    visual_lines: HashMap<usize, Vec<Span>>,
+    font: Font,
+    font_bold: Font,
+    font_italic: Font,
+    /// Font to draw emoji with, since code fonts don't have them.
+    emoji_font: Option<Font>,
+    fallback_fonts: Option<FallbackFonts>,
+    /// Path of the code font, for exports that embed it.
+    font_path: String,
+    font_family: String,
+    font_size: u16,
+    line_height: f32,
+    ascent: f32,
+    char_width: f32,
+    columns: usize,
+    padding: f32,
+    corner_radius: f32,
+    /// Draw symbols for the operators in `SYMBOLS`.
+    symbols: bool,
+    background_color: Color,
+    wrap_marker_color: Color,
+    highlighted_lines: LineRanges,
+    highlight_color: Color,
+    /// Color of the markers for whitespace, if whitespace is shown.
+    whitespace_color: Option<Color>,
+    /// Background color of whitespace at the end of lines, if it is highlighted.
+    trailing_whitespace_color: Option<Color>,
+    indent_guides: Option<IndentGuides>,
+    /// Columns to draw vertical rulers at.
+    rulers: Vec<usize>,
+    ruler_color: Color,
+    gutter: Option<Gutter>,
+    blame: Option<Blame>,
+    git_gutter: Option<GitGutter>,
+    header: Option<Header>,
+    shadow: Option<Shadow>,
+    /// Distance between the two times bold code is drawn, zero for regular
+    /// code.
+    bold_offset: f32,
+    high_contrast: bool,
+}
+
+impl CodeBox {
+    pub fn width_with_padding(&self) -> f32 {
+        self.padding * 2. + self.gutter_width() + self.columns as f32 * self.char_width
+    }
+
+    pub fn height_with_padding(&self) -> f32 {
+        self.header_height() + self.padding * 2. + self.lines.len() as f32 * self.line_height
+    }
+
+    pub fn lines(&self) -> &[CodeLine] {
+        &self.lines
+    }
+
+    pub fn gutter(&self) -> Option<&Gutter> {
+        self.gutter.as_ref()
+    }
+
+    pub fn blame(&self) -> Option<&Blame> {
+        self.blame.as_ref()
+    }
+
+    pub fn git_gutter(&self) -> Option<&GitGutter> {
+        self.git_gutter.as_ref()
+    }
+
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    pub fn font_path(&self) -> &str {
+        &self.font_path
+    }
+
+    pub fn font_family(&self) -> &str {
+        &self.font_family
+    }
+
+    pub fn font_size(&self) -> u16 {
+        self.font_size
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    pub fn char_width(&self) -> f32 {
+        self.char_width
+    }
+
+    pub fn background_color(&self) -> Color {
+        self.background_color
+    }
+
+    /// Whether the colors were remapped for high contrast.
+    pub fn high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    pub fn wrap_marker_color(&self) -> Color {
+        self.wrap_marker_color
+    }
+
+    /// Background color of the line, if it is highlighted or part of a diff.
+    pub fn line_background(&self, line: &CodeLine) -> Option<Color> {
+        match self.highlighted_lines.contains(line.number) {
+            true => Some(self.highlight_color),
+            false => line.background,
+        }
+    }
+
+    pub fn padding(&self) -> f32 {
+        self.padding
+    }
+
+    /// How far the drop shadow reaches outside the box on any side, zero
+    /// without a shadow.
+    pub fn shadow_extent(&self) -> f32 {
+        self.shadow
+            .as_ref()
+            .map(|shadow| shadow.blur + shadow.offset.x.abs().max(shadow.offset.y.abs()))
+            .unwrap_or(0.)
+    }
+
+    pub fn shadow(&self) -> Option<&Shadow> {
+        self.shadow.as_ref()
+    }
+
+    /// Radius of each corner of the box as top left, top right, bottom right
+    /// and bottom left, for the part of the box starting at `top` and `left`
+    /// and ending at the bottom or right edge if `bottom` or `right` is set.
+    pub fn corners(&self, top: bool, right: bool, bottom: bool, left: bool) -> [f32; 4] {
+        let radius = |rounded: bool| match rounded {
+            true => self.corner_radius,
+            false => 0.,
+        };
+        [
+            radius(top && left),
+            radius(top && right),
+            radius(bottom && right),
+            radius(bottom && left),
+        ]
+    }
+
+    /// Left edge of the header title, centered between window controls.
+    pub fn header_title_x(&self, xpos: f32) -> f32 {
+        match &self.header {
+            Some(header) if header.window_controls => {
+                let title_width =
+                    text_columns(&header.title) as f32 * self.char_width + self.header_icon_width();
+                xpos + (self.width_with_padding() - title_width) / 2.
+            }
+            _ => xpos + self.padding,
+        }
+    }
+
+    /// Width of the icon before the title in the header, and the space after
+    /// it.
+    pub fn header_icon_width(&self) -> f32 {
+        match self.header.as_ref().and_then(|header| header.icon.as_ref()) {
+            Some(_) => 2. * self.char_width,
+            None => 0.,
+        }
+    }
+
+    /// Center, radius and color of each window control in the header.
+    pub fn window_controls(&self, xpos: f32, ypos: f32) -> Vec<(Vec2, f32, Color)> {
+        let Some(header) = self.header.as_ref().filter(|header| header.window_controls) else {
+            return Vec::new();
+        };
+        let radius = header.height * 0.18;
+        WINDOW_CONTROL_COLORS
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                let x = xpos + self.padding + radius + index as f32 * radius * 3.2;
+                (vec2(x, ypos + header.height / 2.), radius, *color)
+            })
+            .collect()
+    }
+
+    /// Offset from the top of the box to the baseline of the line at `index`.
+    pub fn baseline_offset(&self, index: usize) -> f32 {
+        self.header_height() + self.padding + index as f32 * self.line_height + self.ascent
+    }
+
+    /// Offset from the top of the box to the baseline of the header title.
+    pub fn header_baseline_offset(&self) -> f32 {
+        (self.header_height() - self.line_height) / 2. + self.ascent
+    }
+
+    /// The displayed code, with soft wrapped lines joined again.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 && !line.continuation {
+                text.push('\n');
+            }
+            text.push_str(&line.text());
+        }
+        text
+    }
+
+    /// Number of characters in the code, counting line breaks as one character.
+    pub fn char_count(&self) -> usize {
+        self.lines.iter().map(|line| line.char_count() + 1).sum()
+    }
+
+    /// Number of visible characters after each step of a stepped reveal.
+    /// Each step shows the next line, or the code up to the end of the next
+    /// group of highlighted lines if any lines are highlighted.
+    pub fn reveal_steps(&self) -> Vec<usize> {
+        let mut steps = Vec::new();
+        let mut visible_chars = 0;
+        for (index, line) in self.lines.iter().enumerate() {
+            visible_chars += line.char_count() + 1;
+            let next = self.lines.get(index + 1);
+            if next.map(|next| next.continuation).unwrap_or(false) {
+                continue;
+            }
+            let end_of_step = match self.highlighted_lines.is_empty() {
+                true => true,
+                false => {
+                    next.is_none()
+                        || (self.highlighted_lines.contains(line.number)
+                            && !next
+                                .map(|next| self.highlighted_lines.contains(next.number))
+                                .unwrap_or(false))
+                }
+            };
+            if end_of_step {
+                steps.push(visible_chars);
+            }
+        }
+        steps
+    }
+
+    pub fn draw(&self, xpos: f32, ypos: f32) {
+        self.draw_visible(xpos, ypos, usize::MAX);
+    }
+
+    /// Draw the code box with only the first `visible_chars` characters of
+    /// the code shown.
+    pub fn draw_visible(&self, xpos: f32, ypos: f32, visible_chars: usize) {
+        if let Some(shadow) = &self.shadow {
+            self.draw_shadow(shadow, xpos, ypos);
+        }
+        draw_rounded_rectangle(
+            Rect::new(
+                xpos,
+                ypos,
+                self.width_with_padding(),
+                self.height_with_padding(),
+            ),
+            self.corners(true, true, true, true),
+            self.background_color,
+        );
+        if let Some(header) = &self.header {
+            self.draw_header(header, xpos, ypos);
+        }
+        self.draw_gutter(xpos, ypos);
+        if let Some(git_gutter) = &self.git_gutter {
+            self.draw_git_gutter(git_gutter, xpos, ypos);
+        }
+        self.draw_line_backgrounds(xpos, ypos);
+        if let Some(indent_guides) = &self.indent_guides {
+            self.draw_indent_guides(indent_guides, xpos, ypos);
+        }
+        self.draw_rulers(xpos, ypos);
+        let text_x = xpos + self.padding + self.gutter_width();
+        let mut remaining = visible_chars;
+        for (index, line) in self.lines.iter().enumerate() {
+            let baseline = self.baseline(ypos, index);
+            let mut x = text_x;
+            if line.continuation {
+                draw_text_ex(
+                    WRAP_MARKER,
+                    x,
+                    baseline,
+                    self.text_params(self.wrap_marker_color),
+                );
+                x += WRAP_INDENT as f32 * self.char_width;
+            }
+            let spans = self.visual_lines.get(&index).unwrap_or(&line.spans);
+            for span in spans.iter() {
+                if remaining == 0 {
+                    return;
+                }
+                let text = prefix(&span.text, remaining);
+                self.draw_code_text(text, x, baseline, span.format());
+                if let Some(whitespace_color) = self.whitespace_color {
+                    self.draw_whitespace_markers(text, span.kind, x, baseline, whitespace_color);
+                }
+                x += text_columns(text) as f32 * self.char_width;
+                remaining -= text.chars().count();
+            }
+            remaining = remaining.saturating_sub(1);
+        }
+    }
+
+    /// Draw a dot over each space and an arrow over each tab in the text.
+    fn draw_whitespace_markers(
+        &self,
+        text: &str,
+        kind: SpanKind,
+        x: f32,
+        baseline: f32,
+        color: Color,
+    ) {
+        let marker = match kind {
+            SpanKind::Tab => TAB_MARKER,
+            SpanKind::TabPadding => return,
+            _ => SPACE_MARKER,
+        };
+        let mut x = x;
+        for c in text.chars() {
+            if c == ' ' {
+                draw_text_ex(marker, x, baseline, self.text_params(color));
+            }
+            x += char_columns(c) as f32 * self.char_width;
+        }
+    }
+
+    fn draw_line_backgrounds(&self, xpos: f32, ypos: f32) {
+        let x = xpos + self.padding + self.gutter_width();
+        let width = self.width_with_padding() - self.padding - self.gutter_width();
+        for (index, line) in self.lines.iter().enumerate() {
+            if let Some(color) = self.line_background(line) {
+                draw_rectangle(
+                    x,
+                    self.line_top(ypos, index),
+                    width,
+                    self.line_height,
+                    color,
+                );
+            }
+            let trailing = self.trailing_whitespace(index);
+            if let (Some(color), true) = (self.trailing_whitespace_color, trailing > 0) {
+                let start = self.column_x(xpos, line, line.char_count() - trailing);
+                draw_rectangle(
+                    start,
+                    self.line_top(ypos, index),
+                    trailing as f32 * self.char_width,
+                    self.line_height,
+                    color,
+                );
+            }
+        }
+    }
+
+    fn draw_rulers(&self, xpos: f32, ypos: f32) {
+        let top = ypos + self.header_height();
+        let bottom = ypos + self.height_with_padding();
+        for column in self.rulers.iter() {
+            let x = (xpos + self.padding + self.gutter_width() + *column as f32 * self.char_width)
+                .round()
+                + 0.5;
+            draw_line(x, top, x, bottom, 1., self.ruler_color);
+        }
+    }
+
+    fn draw_indent_guides(&self, indent_guides: &IndentGuides, xpos: f32, ypos: f32) {
+        let x = xpos + self.padding + self.gutter_width();
+        for (index, indent) in indent_guides.indents.iter().enumerate() {
+            let top = self.line_top(ypos, index);
+            for column in (0..*indent).step_by(indent_guides.width) {
+                let guide_x = (x + column as f32 * self.char_width).round() + 0.5;
+                draw_line(
+                    guide_x,
+                    top,
+                    guide_x,
+                    top + self.line_height,
+                    1.,
+                    indent_guides.color,
+                );
+            }
+        }
+    }
+
+    /// Number of whitespace characters at the end of the line with the index,
+    /// zero if the line continues on the next row.
+    fn trailing_whitespace(&self, index: usize) -> usize {
+        let continues = self
+            .lines
+            .get(index + 1)
+            .map(|next| next.continuation)
+            .unwrap_or(false);
+        if continues {
+            return 0;
+        }
+        self.lines[index]
+            .spans
+            .iter()
+            .rev()
+            .flat_map(|span| span.text.chars().rev())
+            .take_while(|c| c.is_whitespace())
+            .count()
+    }
+
+    /// Draw layers of growing and increasingly transparent rounded rectangles
+    /// behind the box, which add up to a shadow with a soft edge.
+    fn draw_shadow(&self, shadow: &Shadow, xpos: f32, ypos: f32) {
+        // The layers overlap under the box, where they add up to the opacity
+        // of the shadow color
+        let color = Color {
+            a: 1. - (1. - shadow.color.a).powf(1. / SHADOW_STEPS as f32),
+            ..shadow.color
+        };
+        for step in 0..SHADOW_STEPS {
+            let spread = shadow.blur * (1. - step as f32 / SHADOW_STEPS as f32);
+            draw_rounded_rectangle(
+                Rect::new(
+                    xpos + shadow.offset.x - spread,
+                    ypos + shadow.offset.y - spread,
+                    self.width_with_padding() + spread * 2.,
+                    self.height_with_padding() + spread * 2.,
+                ),
+                [self.corner_radius + spread; 4],
+                color,
+            );
+        }
+    }
+
+    fn draw_header(&self, header: &Header, xpos: f32, ypos: f32) {
+        draw_rounded_rectangle(
+            Rect::new(xpos, ypos, self.width_with_padding(), header.height),
+            self.corners(true, true, false, true),
+            header.background_color,
+        );
+        for (center, radius, color) in self.window_controls(xpos, ypos) {
+            draw_circle(center.x, center.y, radius, color);
+        }
+        let title_x = self.header_title_x(xpos);
+        let baseline = ypos + self.header_baseline_offset();
+        if let Some(icon) = &header.icon {
+            let params = TextParams {
+                font: Some(&icon.font),
+                ..self.text_params(header.text_color)
+            };
+            draw_text_ex(&icon.glyph.to_string(), title_x, baseline, params);
+        }
+        draw_text_ex(
+            &header.title,
+            title_x + self.header_icon_width(),
+            baseline,
+            self.text_params(header.text_color),
+        );
+    }
+
+    fn draw_gutter(&self, xpos: f32, ypos: f32) {
+        let Some(background_color) = self.gutter_background_color() else {
+            return;
+        };
+        draw_rounded_rectangle(
+            Rect::new(
+                xpos,
+                ypos + self.header_height(),
+                self.padding + self.gutter_width(),
+                self.height_with_padding() - self.header_height(),
+            ),
+            self.corners(self.header.is_none(), false, true, true),
+            background_color,
+        );
+        let number_width = self.gutter.as_ref().map(|gutter| gutter.width);
+        for (index, line) in self.lines.iter().enumerate() {
+            let baseline = self.baseline(ypos, index);
+            if let (Some(gutter), false) = (&self.gutter, line.continuation) {
+                let number = format!("{:>width$}", line.number, width = gutter.digits);
+                draw_text_ex(
+                    &number,
+                    xpos + self.padding,
+                    baseline,
+                    self.text_params(gutter.text_color),
+                );
+            }
+            if let Some(annotation) = self.blame.as_ref().and_then(|blame| blame.annotation(line)) {
+                draw_text_ex(
+                    &annotation.text,
+                    xpos + self.padding + number_width.unwrap_or(0.),
+                    baseline,
+                    self.text_params(annotation.color),
+                );
+            }
+        }
+    }
+
+    /// Draw a bar in the left padding of added and modified lines, and a
+    /// triangle where lines were removed.
+    fn draw_git_gutter(&self, git_gutter: &GitGutter, xpos: f32, ypos: f32) {
+        let x = self.git_gutter_x(xpos, git_gutter);
+        for (index, line) in self.lines.iter().enumerate() {
+            let top = self.line_top(ypos, index);
+            if let Some(color) = git_gutter.bar_color(line) {
+                draw_rectangle(x, top, git_gutter.width, self.line_height, color);
+            }
+            if let Some(edge) = git_gutter.removed_marker(&self.lines, index) {
+                let y = match edge {
+                    MarkerEdge::Top => top,
+                    MarkerEdge::Bottom => top + self.line_height,
+                };
+                let size = git_gutter.width * 2.;
+                draw_triangle(
+                    vec2(x, y - size),
+                    vec2(x, y + size),
+                    vec2(x + size, y),
+                    git_gutter.removed_color,
+                );
+            }
+        }
+    }
+
+    /// Left edge of the git change markers, centered in the left padding.
+    pub fn git_gutter_x(&self, xpos: f32, git_gutter: &GitGutter) -> f32 {
+        xpos + (self.padding - git_gutter.width) / 2.
+    }
+
+    /// Background of the gutter, if there is a gutter with line numbers or
+    /// blame annotations.
+    pub fn gutter_background_color(&self) -> Option<Color> {
+        self.gutter
+            .as_ref()
+            .map(|gutter| gutter.background_color)
+            .or_else(|| self.blame.as_ref().map(|blame| blame.background_color))
+    }
+
+    /// Width of the line numbers and blame annotations to the left of the code.
+    pub fn gutter_width(&self) -> f32 {
+        self.gutter
+            .as_ref()
+            .map(|gutter| gutter.width)
+            .unwrap_or(0.)
+            + self.blame.as_ref().map(|blame| blame.width).unwrap_or(0.)
+    }
+
+    pub fn header_height(&self) -> f32 {
+        self.header
+            .as_ref()
+            .map(|header| header.height)
+            .unwrap_or(0.)
+    }
+
+    fn baseline(&self, ypos: f32, index: usize) -> f32 {
+        ypos + self.baseline_offset(index)
+    }
+
+    /// Top of the line at `index` when the box is drawn at `ypos`.
+    pub fn line_top(&self, ypos: f32, index: usize) -> f32 {
+        ypos + self.header_height() + self.padding + index as f32 * self.line_height
+    }
+
+    /// Left edge of the character at `column` of the line, when the box is
+    /// drawn at `xpos`.
+    pub fn column_x(&self, xpos: f32, line: &CodeLine, column: usize) -> f32 {
+        let indent = match line.continuation {
+            true => WRAP_INDENT,
+            false => 0,
+        };
+        let column = line.columns_before(column) + indent;
+        xpos + self.padding + self.gutter_width() + column as f32 * self.char_width
+    }
+
+    /// Draw code text, with emoji in the emoji font if there is one, and
+    /// characters the code font doesn't have in the fallback fonts.
+    fn draw_code_text(&self, text: &str, x: f32, baseline: f32, format: Format) {
+        self.draw_code_run(text, x, baseline, format);
+        // Macroquad has no font weights, so the strokes of bold code are
+        // thickened by drawing it twice
+        if self.bold_offset > 0. {
+            self.draw_code_run(text, x + self.bold_offset, baseline, format);
+        }
+        if format.style.contains(FontStyle::UNDERLINE) {
+            let y = (baseline + self.font_size as f32 * UNDERLINE_OFFSET).round() + 0.5;
+            let width = text_columns(text) as f32 * self.char_width;
+            draw_line(x, y, x + width, y, 1., format.color);
+        }
+    }
+
+    fn draw_code_run(&self, text: &str, x: f32, baseline: f32, format: Format) {
+        let Format { color, kind, style } = format;
+        // Strings are drawn as they are written
+        let symbols = self.symbols && kind != SpanKind::String;
+        let style_font = self.style_font(style);
+        if self.emoji_font.is_none() && self.fallback_fonts.is_none() && style_font.is_none() {
+            self.draw_text_with_symbols(text, x, baseline, color, symbols);
+            return;
+        }
+        let mut x = x;
+        let mut rest = text;
+        while let Some(first) = rest.chars().next() {
+            let font = self.glyph_font(first).or(style_font);
+            let end = rest
+                .char_indices()
+                .find(|(_, c)| !same_font(self.glyph_font(*c).or(style_font), font))
+                .map(|(index, _)| index)
+                .unwrap_or(rest.len());
+            let run = &rest[..end];
+            match font {
+                // The glyphs of other fonts, like the bold and italic fonts
+                // of the theme, don't have the width of a cell, so each
+                // character is drawn at the start of its cells
+                Some(font) => {
+                    let mut x = x;
+                    let mut buffer = [0; 4];
+                    for c in run.chars() {
+                        let params = TextParams {
+                            font: Some(font),
+                            ..self.text_params(color)
+                        };
+                        draw_text_ex(c.encode_utf8(&mut buffer), x, baseline, params);
+                        x += char_columns(c) as f32 * self.char_width;
+                    }
+                }
+                None => self.draw_text_with_symbols(run, x, baseline, color, symbols),
+            }
+            x += text_columns(run) as f32 * self.char_width;
+            rest = &rest[end..];
+        }
+    }
+
+    /// The bold or italic font to draw code in the style with, if any.
+    fn style_font(&self, style: FontStyle) -> Option<&Font> {
+        if style.contains(FontStyle::BOLD) {
+            Some(&self.font_bold)
+        } else if style.contains(FontStyle::ITALIC) {
+            Some(&self.font_italic)
+        } else {
+            None
+        }
+    }
+
+    /// The font to draw the character with instead of the code font, if any.
+    fn glyph_font(&self, c: char) -> Option<&Font> {
+        match &self.emoji_font {
+            Some(emoji_font) if is_emoji(c) => Some(emoji_font),
+            _ => self
+                .fallback_fonts
+                .as_ref()
+                .and_then(|fallback_fonts| fallback_fonts.font_for(c)),
+        }
+    }
+
+    /// Draw text, with the symbols of the operators in it if `symbols` is set.
+    fn draw_text_with_symbols(
+        &self,
+        text: &str,
+        x: f32,
+        baseline: f32,
+        color: Color,
+        symbols: bool,
+    ) {
+        if !symbols {
+            draw_text_ex(text, x, baseline, self.text_params(color));
+            return;
+        }
+        let mut x = x;
+        let mut rest = text;
+        while !rest.is_empty() {
+            let Some((index, sequence, symbol)) = find_symbol(rest) else {
+                draw_text_ex(rest, x, baseline, self.text_params(color));
+                return;
+            };
+            let before = &rest[..index];
+            draw_text_ex(before, x, baseline, self.text_params(color));
+            x += text_columns(before) as f32 * self.char_width;
+            let cell_width = sequence.chars().count() as f32 * self.char_width;
+            let symbol_width = measure_text(symbol, Some(&self.font), self.font_size, 1.0).width;
+            draw_text_ex(
+                symbol,
+                x + (cell_width - symbol_width) / 2.,
+                baseline,
+                self.text_params(color),
+            );
+            x += cell_width;
+            rest = &rest[index + sequence.len()..];
+        }
+    }
+
+    fn text_params(&self, color: Color) -> TextParams {
+        TextParams {
+            font: Some(&self.font),
+            font_size: self.font_size,
+            color,
+            ..TextParams::default()
+        }
+    }
+}
+
+pub struct CodeBoxBuilder {
+    theme: Theme,
+    code_theme: CodeTheme,
+    font: Font,
+    font_bold: Font,
+    font_italic: Font,
+    emoji_font: Option<Font>,
+    fallback_fonts: Option<FallbackFonts>,
+    icon_font: Option<Font>,
+    line_numbers: bool,
+    wrap_width: Option<f32>,
+    highlighted_lines: LineRanges,
+    line_slice: Option<LineSlice>,
+    font_size: Option<f32>,
+    padding: Option<f32>,
+    tab_width: Option<usize>,
+    show_whitespace: bool,
+    highlight_trailing_whitespace: bool,
+    indent_guides: bool,
+    rulers: Vec<usize>,
+    rainbow_brackets: bool,
+    strip_comments: bool,
+    semantic_tokens: Vec<SemanticToken>,
+    blame: Vec<BlameLine>,
+    git_changes: HashMap<usize, LineChange>,
+    line_backgrounds: HashMap<usize, Color>,
+    folds: Vec<RangeInclusive<usize>>,
+    syntax_set: Option<Arc<SyntaxSet>>,
+    extension: Option<String>,
+    // Syntect is the only backend without the tree-sitter feature
+    #[cfg_attr(not(feature = "tree-sitter"), allow(dead_code))]
+    highlighter: Highlighter,
+    scale: f32,
+    header: Option<String>,
+    window_controls: bool,
+    high_contrast: bool,
+    colorblind: Option<Colorblind>,
+}
+
+impl CodeBoxBuilder {
+    pub fn new(
+        theme: Theme,
+        code_theme: CodeTheme,
+        font: Font,
+        font_bold: Font,
+        font_italic: Font,
+    ) -> Self {
+        Self {
+            theme,
+            code_theme,
+            font,
+            font_bold,
+            font_italic,
+            emoji_font: None,
+            fallback_fonts: None,
+            icon_font: None,
+            line_numbers: false,
+            wrap_width: None,
+            highlighted_lines: LineRanges::default(),
+            line_slice: None,
+            font_size: None,
+            padding: None,
+            tab_width: None,
+            show_whitespace: false,
+            highlight_trailing_whitespace: false,
+            indent_guides: false,
+            rulers: Vec::new(),
+            rainbow_brackets: false,
+            strip_comments: false,
+            semantic_tokens: Vec::new(),
+            blame: Vec::new(),
+            git_changes: HashMap::new(),
+            line_backgrounds: HashMap::new(),
+            folds: Vec::new(),
+            syntax_set: None,
+            extension: None,
+            highlighter: Highlighter::default(),
+            scale: 1.,
+            header: None,
+            window_controls: false,
+            high_contrast: false,
+            colorblind: None,
+        }
+    }
+
+    /// Draw emoji with this font, instead of the code font.
+    pub fn emoji_font(mut self, emoji_font: Option<Font>) -> Self {
+        self.emoji_font = emoji_font;
+        self
+    }
+
+    /// Draw characters that the code font doesn't have with these fonts.
+    pub fn fallback_fonts(mut self, fallback_fonts: Option<FallbackFonts>) -> Self {
+        self.fallback_fonts = fallback_fonts;
+        self
+    }
+
+    /// Draw an icon of the language before the filename in the header with
+    /// this icon font, like a Nerd Font.
+    pub fn icon_font(mut self, icon_font: Option<Font>) -> Self {
+        self.icon_font = icon_font;
+        self
+    }
+
+    /// Show a gutter with line numbers to the left of the code.
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Soft wrap lines at word boundaries to keep the box within `width` pixels.
+    pub fn wrap(mut self, width: Option<f32>) -> Self {
+        self.wrap_width = width;
+        self
+    }
+
+    /// Draw the given lines with a highlighted background.
+    pub fn highlight_lines(mut self, lines: LineRanges) -> Self {
+        self.highlighted_lines = lines;
+        self
+    }
+
+    /// Only show the lines in the slice. The whole code is still highlighted
+    /// so that the lines are highlighted in the right context.
+    pub fn line_slice(mut self, slice: Option<LineSlice>) -> Self {
+        self.line_slice = slice;
+        self
+    }
+
+    /// Show a header at the top of the box with the title, e.g. the filename.
+    pub fn header(mut self, title: Option<String>) -> Self {
+        self.header = title;
+        self
+    }
+
+    /// Decorate the box as a window, with rounded corners and window controls in the header.
+    pub fn window_controls(mut self, window_controls: bool) -> Self {
+        self.window_controls = window_controls;
+        self
+    }
+
+    /// Use this font size instead of the code font size of the theme.
+    pub fn font_size(mut self, font_size: Option<f32>) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Space between the code and the edge of the box, instead of the default.
+    pub fn padding(mut self, padding: Option<f32>) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Number of columns between tab stops, instead of the tab width of the theme.
+    pub fn tab_width(mut self, tab_width: Option<usize>) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Show spaces as middle dots and tabs as arrows.
+    pub fn show_whitespace(mut self, show_whitespace: bool) -> Self {
+        self.show_whitespace = show_whitespace;
+        self
+    }
+
+    /// Draw whitespace at the end of lines with a highlighted background.
+    pub fn highlight_trailing_whitespace(mut self, highlight: bool) -> Self {
+        self.highlight_trailing_whitespace = highlight;
+        self
+    }
+
+    /// Draw vertical lines at each level of indentation, even if the theme
+    /// doesn't enable them.
+    pub fn indent_guides(mut self, indent_guides: bool) -> Self {
+        self.indent_guides = indent_guides;
+        self
+    }
+
+    /// Draw vertical rulers at these columns, the box is made wide enough to
+    /// show all of them.
+    pub fn rulers(mut self, rulers: Vec<usize>) -> Self {
+        self.rulers = rulers;
+        self
+    }
+
+    /// Color brackets by their nesting depth.
+    pub fn rainbow_brackets(mut self, rainbow_brackets: bool) -> Self {
+        self.rainbow_brackets = rainbow_brackets;
+        self
+    }
+
+    /// Remove comments, and the lines that only have comments.
+    pub fn strip_comments(mut self, strip_comments: bool) -> Self {
+        self.strip_comments = strip_comments;
+        self
+    }
+
+    /// Recolor ranges of the code classified by a language server, on top of
+    /// the syntax highlighting.
+    pub fn semantic_tokens(mut self, semantic_tokens: Vec<SemanticToken>) -> Self {
+        self.semantic_tokens = semantic_tokens;
+        self
+    }
+
+    /// Show the author and age of each line, from `git blame`, in the gutter.
+    pub fn blame(mut self, blame: Vec<BlameLine>) -> Self {
+        self.blame = blame;
+        self
+    }
+
+    /// Mark lines changed since the last git commit, by line number.
+    pub fn git_changes(mut self, git_changes: HashMap<usize, LineChange>) -> Self {
+        self.git_changes = git_changes;
+        self
+    }
+
+    /// Draw these lines with a background color, by line number.
+    pub fn line_backgrounds(mut self, line_backgrounds: HashMap<usize, Color>) -> Self {
+        self.line_backgrounds = line_backgrounds;
+        self
+    }
+
+    /// Hide these ranges of line numbers behind a placeholder line.
+    pub fn folds(mut self, folds: Vec<RangeInclusive<usize>>) -> Self {
+        self.folds = folds;
+        self
+    }
+
+    /// Highlight with these syntax definitions, loaded once with
+    /// [`load_syntax_set`], instead of loading the ones bundled with syntect
+    /// for each code box.
+    pub fn syntax_set(mut self, syntax_set: Arc<SyntaxSet>) -> Self {
+        self.syntax_set = Some(syntax_set);
+        self
+    }
+
+    /// File extension of the code, to find the syntax by when there is no
+    /// syntax for the language.
+    pub fn extension(mut self, extension: Option<String>) -> Self {
+        self.extension = extension;
+        self
+    }
+
+    /// Highlight the syntax with this backend, languages it doesn't support
+    /// are highlighted with syntect.
+    pub fn highlighter(mut self, highlighter: Highlighter) -> Self {
+        self.highlighter = highlighter;
+        self
+    }
+
+    /// Remap the colors of the theme to a high-contrast palette on a black or
+    /// white background, and draw the code bold.
+    pub fn high_contrast(mut self, high_contrast: bool) -> Self {
+        self.high_contrast = high_contrast;
+        self
+    }
+
+    /// Adjust the colors of the code so that they can be told apart with the
+    /// color vision deficiency.
+    pub fn colorblind(mut self, colorblind: Option<Colorblind>) -> Self {
+        self.colorblind = colorblind;
+        self
+    }
+
+    /// Scale the font size and padding, e.g. by the DPI scale of the display.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn build_draw_box(&self, language: Option<String>, code: String) -> CodeBox {
+        let font_size = self.font_size.unwrap_or(self.theme.font_code_size as f32);
+        let font_size = (font_size * self.scale) as u16;
+        let padding = self.padding.unwrap_or(BOX_PADDING) * self.scale;
+        // The theme is remapped here rather than in the builder, so that high
+        // contrast can be turned off again
+        let code_theme = match self.high_contrast {
+            true => Cow::Owned(self.code_theme.high_contrast()),
+            false => Cow::Borrowed(&self.code_theme),
+        };
+        let dimensions = measure_text("M", Some(&self.font), font_size, 1.0);
+        let char_width = dimensions.width;
+        let diff = is_diff(language.as_deref(), &code);
+        let language = match diff {
+            true => Some("diff".to_string()),
+            false => language,
+        };
+        let icon = self.icon_font.clone().map(|font| FileIcon {
+            glyph: icons::glyph(language.as_deref(), &code_theme.file_icons),
+            font,
+            family: font_family(&code_theme.font_icons),
+        });
+        let mut lines = self.highlight(language, &code);
+        if !self.semantic_tokens.is_empty() {
+            self.apply_semantic_tokens(&mut lines);
+        }
+        if self.strip_comments {
+            lines = self.remove_comments(lines);
+        }
+        if let Some(colorblind) = self.colorblind {
+            for span in lines.iter_mut().flat_map(|line| line.spans.iter_mut()) {
+                span.color = colorblind.daltonize(span.color);
+            }
+        }
+        let tab_width = self
+            .tab_width
+            .unwrap_or(self.theme.code_tab_width as usize)
+            .max(1);
+        let whitespace_color = self
+            .show_whitespace
+            .then(|| hex_color(&code_theme.whitespace_color));
+        expand_tabs(&mut lines, tab_width);
+        if self.rainbow_brackets {
+            let colors: Vec<Color> = self
+                .code_theme
+                .rainbow_bracket_colors
+                .iter()
+                .map(|color| hex_color(color))
+                .map(|color| match self.colorblind {
+                    Some(colorblind) => colorblind.daltonize(color),
+                    None => color,
+                })
+                .collect();
+            color_brackets(&mut lines, &colors);
+        }
+        if diff {
+            self.color_diff_lines(&mut lines);
+        }
+        for line in lines.iter_mut() {
+            if let Some(color) = self.line_backgrounds.get(&line.number) {
+                line.background = Some(*color);
+            }
+        }
+        if self.high_contrast {
+            // The text of highlighted, added and removed lines is drawn over
+            // the background of the line instead of the code background
+            let code_background = hex_color(&code_theme.code_background_color);
+            let highlight = hex_color(&code_theme.highlight_line_color);
+            for line in lines.iter_mut() {
+                let background = match self.highlighted_lines.contains(line.number) {
+                    true => composite(highlight, code_background),
+                    false => line
+                        .background
+                        .map_or(code_background, |color| composite(color, code_background)),
+                };
+                for span in line.spans.iter_mut() {
+                    span.color = with_contrast(span.color, background, HIGH_CONTRAST_RATIO);
+                }
+            }
+        }
+        if let Some(slice) = self.line_slice {
+            lines.retain(|line| slice.contains(line.number));
+        }
+        if !self.folds.is_empty() {
+            lines = fold_lines(lines, &self.folds, hex_color(&code_theme.wrap_marker_color));
+        }
+        let gutter = self.line_numbers.then(|| {
+            let last_line = lines.last().map(|line| line.number).unwrap_or(1);
+            let digits = last_line.to_string().len();
+            Gutter {
+                width: (digits + 2) as f32 * char_width,
+                digits,
+                background_color: hex_color(&code_theme.gutter_background_color),
+                text_color: hex_color(&code_theme.gutter_text_color),
+            }
+        });
+        let blame = (!self.blame.is_empty()).then(|| Blame {
+            width: BLAME_COLUMNS as f32 * char_width,
+            background_color: hex_color(&code_theme.gutter_background_color),
+            annotations: blame_annotations(&self.blame, hex_color(&code_theme.gutter_text_color)),
+        });
+        if let Some(wrap_width) = self.wrap_width {
+            let gutter_width = gutter.as_ref().map(|gutter| gutter.width).unwrap_or(0.)
+                + blame.as_ref().map(|blame| blame.width).unwrap_or(0.);
+            let wrap_columns = ((wrap_width - padding * 2. - gutter_width) / char_width) as usize;
+            lines = wrap_lines(lines, wrap_columns.max(WRAP_INDENT + 1));
+        }
+        let visual_lines = visual_order(&lines);
+        let columns = lines
+            .iter()
+            .map(CodeLine::width)
+            .chain(self.rulers.iter().copied())
+            .max()
+            .unwrap_or(0);
+        let indent_guides = (self.indent_guides || code_theme.indent_guides).then(|| {
+            let indents = indents(&lines);
+            IndentGuides {
+                color: hex_color(&code_theme.indent_guide_color),
+                width: indent_width(&indents, tab_width),
+                indents,
+            }
+        });
+        let line_height = font_size as f32 * self.theme.code_line_height;
+        let header = (self.header.is_some() || self.window_controls).then(|| Header {
+            title: self.header.clone().unwrap_or_default(),
+            icon: icon.filter(|_| self.header.is_some()),
+            height: line_height * HEADER_LINES,
+            window_controls: self.window_controls,
+            background_color: hex_color(&code_theme.header_background_color),
+            text_color: hex_color(&code_theme.header_text_color),
+        });
+
+        CodeBox {
+            lines,
+            visual_lines,
+            font: self.font.clone(),
+            font_bold: self.font_bold.clone(),
+            font_italic: self.font_italic.clone(),
+            emoji_font: self.emoji_font.clone(),
+            fallback_fonts: self.fallback_fonts.clone(),
+            font_path: self.theme.font_code.clone(),
+            font_family: font_family(&self.theme.font_code),
+            font_size,
+            line_height,
+            ascent: dimensions.offset_y,
+            char_width,
+            columns,
+            padding,
+            corner_radius: self.corner_radius(),
+            symbols: code_theme.code_symbols,
+            background_color: hex_color(&code_theme.code_background_color),
+            wrap_marker_color: hex_color(&code_theme.wrap_marker_color),
+            highlighted_lines: self.highlighted_lines.clone(),
+            highlight_color: hex_color(&code_theme.highlight_line_color),
+            whitespace_color,
+            indent_guides,
+            rulers: self.rulers.clone(),
+            ruler_color: hex_color(&code_theme.ruler_color),
+            trailing_whitespace_color: self
+                .highlight_trailing_whitespace
+                .then(|| hex_color(&code_theme.trailing_whitespace_color)),
+            gutter,
+            blame,
+            git_gutter: (!self.git_changes.is_empty()).then(|| GitGutter {
+                changes: self.git_changes.clone(),
+                width: GIT_GUTTER_WIDTH * self.scale,
+                added_color: hex_color(&code_theme.git_added_color),
+                modified_color: hex_color(&code_theme.git_modified_color),
+                removed_color: hex_color(&code_theme.git_removed_color),
+            }),
+            header,
+            shadow: (code_theme.shadow_blur > 0.
+                || code_theme.shadow_offset_x != 0.
+                || code_theme.shadow_offset_y != 0.)
+                .then(|| Shadow {
+                    color: hex_color(&code_theme.shadow_color),
+                    blur: code_theme.shadow_blur * self.scale,
+                    offset: vec2(code_theme.shadow_offset_x, code_theme.shadow_offset_y)
+                        * self.scale,
+                }),
+            bold_offset: match self.high_contrast {
+                true => (font_size as f32 * BOLD_OFFSET).max(1.),
+                false => 0.,
+            },
+            high_contrast: self.high_contrast,
+        }
+    }
+
+    /// Radius of the corners of the box, the theme decides unless it has
+    /// square corners and the box is decorated as a window.
+    fn corner_radius(&self) -> f32 {
+        let radius = match self.code_theme.corner_radius {
+            radius if radius <= 0. && self.window_controls => WINDOW_CORNER_RADIUS,
+            radius => radius.max(0.),
+        };
+        radius * self.scale
+    }
+
+    /// Use background colors to show added and removed lines and hunk headers.
+    fn color_diff_lines(&self, lines: &mut [CodeLine]) {
+        let added = hex_color(&self.code_theme.diff_added_color);
+        let removed = hex_color(&self.code_theme.diff_removed_color);
+        let hunk = hex_color(&self.code_theme.diff_hunk_color);
+        for line in lines.iter_mut() {
+            let text = line.text();
+            line.background = match text.chars().next() {
+                _ if text.starts_with("+++") || text.starts_with("---") => None,
+                _ if text.starts_with("@@") => Some(hunk),
+                Some('+') => Some(added),
+                Some('-') => Some(removed),
+                _ => None,
+            };
+        }
+    }
+
+    /// The syntax definitions that were given, or the bundled ones.
+    fn syntax_set(&self) -> Arc<SyntaxSet> {
+        self.syntax_set
+            .clone()
+            .unwrap_or_else(|| Arc::new(load_syntax_set(None)))
+    }
+
+    /// The syntax highlighting theme, loaded from the theme file or one of
+    /// the themes bundled with syntect.
+    fn syntax_theme(&self) -> highlighting::Theme {
+        self.code_theme.syntax_theme().unwrap_or_else(|| {
+            let mut theme_set = ThemeSet::load_defaults();
+            theme_set
+                .themes
+                .remove(&self.theme.code_theme)
+                .unwrap_or_else(|| theme_set.themes.remove(FALLBACK_CODE_THEME).unwrap())
+        })
+    }
+
+    fn highlight(&self, language: Option<String>, code: &str) -> Vec<CodeLine> {
+        let syntax_set = self.syntax_set();
+        let code_theme = &self.syntax_theme();
+        #[cfg(feature = "tree-sitter")]
+        if self.highlighter == Highlighter::TreeSitter {
+            let lines = language
+                .as_deref()
+                .and_then(|language| crate::treesitter::highlight(language, code, code_theme));
+            if let Some(lines) = lines {
+                return lines;
+            }
+        }
+        let syntax = language
+            .and_then(|lang| syntax_set.find_syntax_by_token(&lang))
+            .or_else(|| {
+                self.extension
+                    .as_deref()
+                    .and_then(|extension| syntax_set.find_syntax_by_extension(extension))
+            })
+            .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+        let highlighter = highlighting::Highlighter::new(code_theme);
+        let mut parse_state = ParseState::new(syntax);
+        let mut stack = ScopeStack::new();
+        let comment = Scope::new("comment").expect("Comment scopes are valid");
+        let string = Scope::new("string").expect("String scopes are valid");
+
+        LinesWithEndings::from(code)
+            .enumerate()
+            .map(|(index, line)| {
+                let ops = parse_state
+                    .parse_line(line, &syntax_set)
+                    .unwrap_or_default();
+                let mut spans = Vec::new();
+                for (range, op) in ScopeRangeIterator::new(&ops, line) {
+                    if stack.apply(op).is_err() || range.is_empty() {
+                        continue;
+                    }
+                    let text = line[range].trim_end_matches(['\r', '\n']);
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let style = highlighter.style_for_stack(stack.as_slice());
+                    // Comments and strings are told apart by their scope,
+                    // since themes may give other code the same color
+                    let kind = stack
+                        .as_slice()
+                        .iter()
+                        .rev()
+                        .find_map(|scope| {
+                            if comment.is_prefix_of(*scope) {
+                                Some(SpanKind::Comment)
+                            } else if string.is_prefix_of(*scope) {
+                                Some(SpanKind::String)
+                            } else {
+                                None
+                            }
+                        })
+                        .unwrap_or_default();
+                    spans.push(Span {
+                        text: text.to_string(),
+                        color: Color::from_rgba(
+                            style.foreground.r,
+                            style.foreground.g,
+                            style.foreground.b,
+                            style.foreground.a,
+                        ),
+                        kind,
+                        style: style.font_style,
+                    });
+                }
+                CodeLine {
+                    number: index + 1,
+                    continuation: false,
+                    background: None,
+                    spans,
+                }
+            })
+            .collect()
+    }
+
+    /// Recolor the characters of each semantic token with the color the theme
+    /// gives its scope. Lines are still numbered from the start of the code.
+    fn apply_semantic_tokens(&self, lines: &mut [CodeLine]) {
+        let theme = self.syntax_theme();
+        let highlighter = highlighting::Highlighter::new(&theme);
+        for token in self.semantic_tokens.iter() {
+            let (Some(line), Ok(scope)) = (lines.get_mut(token.line), Scope::new(&token.scope))
+            else {
+                continue;
+            };
+            let color = highlighter.style_for_stack(&[scope]).foreground;
+            let color = Color::from_rgba(color.r, color.g, color.b, color.a);
+            let mut chars: Vec<(char, Format)> = line.spans.iter().flat_map(Span::chars).collect();
+            for (_, format) in chars.iter_mut().skip(token.start).take(token.length) {
+                format.color = color;
+            }
+            line.spans = spans_from_chars(&chars);
+        }
+    }
+
+    /// Remove the spans with a comment scope. Lines with nothing but
+    /// comments are removed, other lines keep their numbers.
+    fn remove_comments(&self, lines: Vec<CodeLine>) -> Vec<CodeLine> {
+        lines
+            .into_iter()
+            .filter_map(|mut line| {
+                let count = line.spans.len();
+                line.spans.retain(|span| span.kind != SpanKind::Comment);
+                if line.spans.len() == count {
+                    return Some(line);
+                }
+                while let Some(last) = line.spans.last_mut() {
+                    last.text.truncate(last.text.trim_end().len());
+                    if !last.text.is_empty() {
+                        break;
+                    }
+                    line.spans.pop();
+                }
+                (!line.spans.is_empty()).then_some(line)
+            })
+            .collect()
+    }
+}
+
+/// Draw a rectangle with rounded corners, with the radius of each corner
+/// given as top left, top right, bottom right and bottom left.
+fn draw_rounded_rectangle(rect: Rect, radii: [f32; 4], color: Color) {
+    if radii.iter().all(|radius| *radius <= 0.) {
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+        return;
+    }
+    let max_radius = rect.w.min(rect.h) / 2.;
+    let [top_left, top_right, bottom_right, bottom_left] = radii.map(|r| r.min(max_radius));
+    // Corner centers, radius and the angle each corner starts at, going clockwise
+    let corners = [
+        (
+            rect.point() + vec2(rect.w - top_right, top_right),
+            top_right,
+            -90.,
+        ),
+        (
+            rect.point() + vec2(rect.w - bottom_right, rect.h - bottom_right),
+            bottom_right,
+            0.,
+        ),
+        (
+            rect.point() + vec2(bottom_left, rect.h - bottom_left),
+            bottom_left,
+            90.,
+        ),
+        (rect.point() + vec2(top_left, top_left), top_left, 180.),
+    ];
+    let points: Vec<Vec2> = corners
+        .iter()
+        .flat_map(|&(corner, radius, start): &(Vec2, f32, f32)| {
+            (0..=CORNER_SEGMENTS).map(move |segment| {
+                let angle = (start + 90. * segment as f32 / CORNER_SEGMENTS as f32).to_radians();
+                corner + Vec2::from_angle(angle) * radius
+            })
+        })
+        .collect();
+    let center = rect.center();
+    for (index, point) in points.iter().enumerate() {
+        let next = points[(index + 1) % points.len()];
+        draw_triangle(center, *point, next, color);
+    }
+}
+
+/// Replace each tab with spaces up to the next tab stop, so that tabs line
+/// up the same way as in an editor. The spaces are marked as a tab, so that
+/// a tab marker can be drawn over them when whitespace is shown.
+fn expand_tabs(lines: &mut [CodeLine], tab_width: usize) {
+    let tab_width = tab_width.max(1);
+    for line in lines.iter_mut() {
+        if !line.spans.iter().any(|span| span.text.contains('\t')) {
+            continue;
+        }
+        let mut chars: Vec<(char, Format)> = Vec::with_capacity(line.char_count());
+        let mut column = 0;
+        for (c, format) in line.spans.iter().flat_map(Span::chars) {
+            if c != '\t' {
+                chars.push((c, format));
+                column += char_columns(c);
+                continue;
+            }
+            let spaces = tab_width - column % tab_width;
+            column += spaces;
+            let tab = Format {
+                kind: SpanKind::Tab,
+                ..format
+            };
+            let padding = Format {
+                kind: SpanKind::TabPadding,
+                ..format
+            };
+            chars.push((' ', tab));
+            chars.extend(std::iter::repeat((' ', padding)).take(spaces - 1));
+        }
+        line.spans = spans_from_chars(&chars);
+    }
+}
+
+/// The spans of the lines with right-to-left text, like Arabic or Hebrew
+/// strings and comments, by index, in the order they are displayed by the
+/// Unicode bidirectional algorithm. The code itself stays left-to-right, and
+/// brackets in right-to-left runs are mirrored. Arabic letters are replaced
+/// with their connected forms, since Macroquad can't shape text. The lines
+/// keep the logical order, for copying, searching and exports.
+fn visual_order(lines: &[CodeLine]) -> HashMap<usize, Vec<Span>> {
+    let mut visual_lines = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        let chars: Vec<(char, Format)> = line.spans.iter().flat_map(Span::chars).collect();
+        if !chars
+            .iter()
+            .any(|(c, _)| matches!(bidi_class(*c), BidiClass::R | BidiClass::AL))
+        {
+            continue;
+        }
+        let text: String = chars.iter().map(|(c, _)| c).collect();
+        let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        let bidi = BidiInfo::new(&text, Some(Level::ltr()));
+        let Some(paragraph) = bidi.paragraphs.first() else {
+            continue;
+        };
+        let shaped = shape_arabic(&chars.iter().map(|(c, _)| *c).collect::<Vec<char>>());
+        let chars: Vec<(char, Format)> = shaped
+            .into_iter()
+            .zip(chars.iter().map(|(_, format)| *format))
+            .collect();
+        let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+        let mut visual: Vec<(char, Format)> = Vec::with_capacity(chars.len());
+        for run in runs {
+            let start = offsets.partition_point(|offset| *offset < run.start);
+            let end = offsets.partition_point(|offset| *offset < run.end);
+            let run_chars = chars[start..end].iter().copied();
+            match levels[run.start].is_rtl() {
+                true => visual.extend(run_chars.rev().map(|(c, format)| (mirror(c), format))),
+                false => visual.extend(run_chars),
+            }
+        }
+        visual_lines.insert(index, spans_from_chars(&visual));
+    }
+    visual_lines
+}
+
+/// The bracket facing the other way, for brackets in right-to-left text.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        c => c,
+    }
+}
+
+/// Color each bracket, parenthesis and brace by how deeply it is nested,
+/// cycling through the colors. Matching pairs get the same color. Brackets
+/// in strings and comments aren't code, so they keep their color and don't
+/// change the depth.
+fn color_brackets(lines: &mut [CodeLine], colors: &[Color]) {
+    if colors.is_empty() {
+        return;
+    }
+    let mut depth: usize = 0;
+    for line in lines.iter_mut() {
+        let mut chars: Vec<(char, Format)> = line.spans.iter().flat_map(Span::chars).collect();
+        for (c, format) in chars.iter_mut() {
+            if matches!(format.kind, SpanKind::String | SpanKind::Comment) {
+                continue;
+            }
+            match c {
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    format.color = colors[(depth - 1) % colors.len()];
+                }
+                ')' | ']' | '}' => {
+                    depth = depth.saturating_sub(1);
+                    format.color = colors[depth % colors.len()];
+                }
+                _ => (),
+            }
+        }
+        line.spans = spans_from_chars(&chars);
+    }
+}
+
+/// Replace the lines in each folded range with a placeholder line, indented
+/// like the first hidden line and showing how many lines are hidden.
+fn fold_lines(
+    lines: Vec<CodeLine>,
+    folds: &[RangeInclusive<usize>],
+    color: Color,
+) -> Vec<CodeLine> {
+    let mut folded: Vec<CodeLine> = Vec::with_capacity(lines.len());
+    let mut lines = lines.into_iter().peekable();
+    while let Some(line) = lines.next() {
+        let Some(fold) = folds.iter().find(|fold| fold.contains(&line.number)) else {
+            folded.push(line);
+            continue;
+        };
+        let indent: String = line
+            .text()
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let mut hidden = 1;
+        while lines.next_if(|next| fold.contains(&next.number)).is_some() {
+            hidden += 1;
+        }
+        let unit = match hidden {
+            1 => "line",
+            _ => "lines",
+        };
+        folded.push(CodeLine {
+            number: line.number,
+            continuation: false,
+            background: None,
+            spans: vec![Span::new(
+                format!("{}{} {} {}", indent, FOLD_MARKER, hidden, unit),
+                color,
+            )],
+        });
+    }
+    folded
+}
+
+/// Author and age of each line, padded to the width of the blame column. The
+/// oldest lines fade towards the background.
+fn blame_annotations(blame: &[BlameLine], color: Color) -> Vec<Span> {
+    let now = macroquad::miniquad::date::now() as u64;
+    let oldest = blame.iter().map(|line| line.time).min().unwrap_or(0);
+    let newest = blame.iter().map(|line| line.time).max().unwrap_or(0);
+    blame
+        .iter()
+        .map(|line| {
+            let author: String = line.author.chars().take(BLAME_AUTHOR_COLUMNS).collect();
+            let recency = match newest > oldest {
+                true => (line.time - oldest) as f32 / (newest - oldest) as f32,
+                false => 1.,
+            };
+            Span::new(
+                format!(
+                    "{:<author_width$} {:>age_width$}",
+                    author,
+                    age(now.saturating_sub(line.time)),
+                    author_width = BLAME_AUTHOR_COLUMNS,
+                    age_width = BLAME_AGE_COLUMNS
+                ),
+                Color {
+                    a: color.a * (BLAME_MIN_ALPHA + (1. - BLAME_MIN_ALPHA) * recency),
+                    ..color
+                },
+            )
+        })
+        .collect()
+}
+
+/// Short description of an age in seconds, like `3 days` or `2 years`.
+fn age(seconds: u64) -> String {
+    const UNITS: &[(u64, &str)] = &[
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (7 * 24 * 3600, "week"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+    UNITS
+        .iter()
+        .find(|(length, _)| seconds >= *length)
+        .map(|(length, unit)| {
+            let count = seconds / length;
+            match count {
+                1 => format!("1 {}", unit),
+                _ => format!("{} {}s", count, unit),
+            }
+        })
+        .unwrap_or_else(|| "now".to_string())
+}
+
+/// Number of columns per level of indentation, the step that lines are most
+/// often indented by from the line before them, so that code indented with
+/// two spaces gets a guide at each level. Code that is never indented falls
+/// back to the tab width.
+fn indent_width(indents: &[usize], tab_width: usize) -> usize {
+    let mut steps: HashMap<usize, usize> = HashMap::new();
+    for pair in indents.windows(2) {
+        if pair[1] > pair[0] {
+            *steps.entry(pair[1] - pair[0]).or_default() += 1;
+        }
+    }
+    steps
+        .into_iter()
+        .max_by_key(|(step, count)| (*count, Reverse(*step)))
+        .map(|(step, _)| step)
+        .unwrap_or(tab_width)
+}
+
+/// Number of columns of indentation of each line. Continued lines have the
+/// indentation of the line they continue, and blank lines the smallest
+/// indentation of the lines around them, so the guides aren't interrupted.
+fn indents(lines: &[CodeLine]) -> Vec<usize> {
+    // None for blank lines
+    let mut indents: Vec<Option<usize>> = Vec::with_capacity(lines.len());
+    for line in lines.iter() {
+        let indent = match (line.continuation, indents.last()) {
+            (true, Some(previous)) => *previous,
+            _ => {
+                let indent = line
+                    .spans
+                    .iter()
+                    .flat_map(|span| span.text.chars())
+                    .take_while(|c| *c == ' ')
+                    .count();
+                (indent < line.char_count()).then_some(indent)
+            }
+        };
+        indents.push(indent);
+    }
+
+    let mut previous = 0;
+    indents
+        .iter()
+        .enumerate()
+        .map(|(index, indent)| match indent {
+            Some(indent) => {
+                previous = *indent;
+                *indent
+            }
+            None => {
+                let next = indents[index..]
+                    .iter()
+                    .flatten()
+                    .next()
+                    .copied()
+                    .unwrap_or(0);
+                previous.min(next)
+            }
+        })
+        .collect()
+}
+
+/// True for characters in the Unicode blocks of emoji and pictographs, and
+/// the joiners and variation selector that combine them.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x200D | 0xFE0F
+    )
+}
+
+/// True if both are the same font, or both are the code font.
+fn same_font(a: Option<&Font>, b: Option<&Font>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => std::ptr::eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// True if the language is diff, or if the code looks like a unified diff.
+fn is_diff(language: Option<&str>, code: &str) -> bool {
+    if let Some(language) = language {
+        return matches!(language.to_lowercase().as_str(), "diff" | "patch");
+    }
+    let mut lines = code.lines().skip_while(|line| line.trim().is_empty());
+    match lines.next() {
+        Some(line) if line.starts_with("diff --git ") => true,
+        Some(line) if line.starts_with("--- ") => {
+            lines.next().map(|line| line.starts_with("+++ ")) == Some(true)
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn wrap_lines(lines: Vec<CodeLine>, columns: usize) -> Vec<CodeLine> {
+    lines
+        .into_iter()
+        .flat_map(|line| wrap_line(line, columns))
+        .collect()
+}
+
+/// Split a line into rows of at most `columns` cells, breaking after
+/// whitespace when possible. The colors of the highlighted spans are kept.
+/// A line that is already the continuation of a wrapped line stays indented.
+fn wrap_line(line: CodeLine, columns: usize) -> Vec<CodeLine> {
+    if line.width() <= columns {
+        return vec![line];
+    }
+    let chars: Vec<(char, Format)> = line.spans.iter().flat_map(Span::chars).collect();
+    let mut rows = vec![];
+    let mut start = 0;
+    loop {
+        let available = match rows.is_empty() && !line.continuation {
+            true => columns,
+            false => columns - WRAP_INDENT,
+        };
+        // The first character that doesn't fit on the row
+        let mut width = 0;
+        let Some(end) = chars[start..].iter().position(|(c, _)| {
+            width += char_columns(*c);
+            width > available
+        }) else {
+            break;
+        };
+        // A wide character is put on a row of its own even if it doesn't fit
+        let end = (start + end).max(start + 1);
+        let split = (start + 1..end)
+            .rev()
+            .find(|&index| chars[index - 1].0.is_whitespace())
+            .unwrap_or(end);
+        rows.push(&chars[start..split]);
+        start = split;
+    }
+    rows.push(&chars[start..]);
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(index, row)| CodeLine {
+            number: line.number,
+            continuation: index > 0 || line.continuation,
+            background: line.background,
+            spans: spans_from_chars(row),
+        })
+        .collect()
+}
+
+/// The first operator in the text that has a symbol, with its index. An
+/// operator that is part of a longer one, like `<=` in `<<=` or `!=` in
+/// `!==`, is skipped, since its symbol would change what the code says.
+fn find_symbol(text: &str) -> Option<(usize, &'static str, &'static str)> {
+    let is_operator = |c: Option<char>| c.is_some_and(|c| "<>=!-".contains(c));
+    text.char_indices().find_map(|(index, _)| {
+        let (sequence, symbol) = SYMBOLS
+            .iter()
+            .find(|(sequence, _)| text[index..].starts_with(sequence))?;
+        let before = text[..index].chars().next_back();
+        let after = text[index + sequence.len()..].chars().next();
+        (!is_operator(before) && !is_operator(after)).then_some((index, *sequence, *symbol))
+    })
+}
+
+/// Group consecutive characters that are drawn the same way into spans.
+fn spans_from_chars(chars: &[(char, Format)]) -> Vec<Span> {
+    let mut spans: Vec<Span> = vec![];
+    for &(c, format) in chars {
+        match spans.last_mut() {
+            Some(span)
+                if span.color == format.color
+                    && span.kind == format.kind
+                    && span.style == format.style =>
+            {
+                span.text.push(c)
+            }
+            _ => spans.push(Span {
+                text: c.to_string(),
+                color: format.color,
+                kind: format.kind,
+                style: format.style,
+            }),
+        }
+    }
+    spans
+}
+
+/// Number of cells the character takes up in the monospace grid, two for
+/// wide characters like CJK and none for combining marks.
+pub fn char_columns(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Number of cells the text takes up in the monospace grid.
+pub fn text_columns(text: &str) -> usize {
+    text.chars().map(char_columns).sum()
+}
+
+/// The first `count` characters of `text`.
+fn prefix(text: &str, count: usize) -> &str {
+    let end = text
+        .char_indices()
+        .nth(count)
+        .map(|(index, _)| index)
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// The syntax definitions bundled with syntect, and the `.sublime-syntax`
+/// definitions in the directory if one is given.
+pub fn load_syntax_set(syntax_dir: Option<&Path>) -> SyntaxSet {
+    let Some(syntax_dir) = syntax_dir else {
+        return SyntaxSet::load_defaults_newlines();
+    };
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+    if let Err(e) = builder.add_from_folder(syntax_dir, true) {
+        warn!(
+            "Couldn't load syntax definitions from {}: {}",
+            syntax_dir.display(),
+            e
+        );
+    }
+    builder.build()
+}
+
+/// Guess the font family name from a font file path like `assets/Hack-Regular.ttf`.
+fn font_family(font_path: &str) -> String {
+    Path::new(font_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .and_then(|stem| stem.split('-').next().map(|family| family.to_string()))
+        .unwrap_or_else(|| "monospace".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(spans: Vec<Span>) -> CodeLine {
+        CodeLine {
+            number: 1,
+            continuation: false,
+            background: None,
+            spans,
+        }
+    }
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_the_indentation_width() {
+        assert_eq!(indent_width(&[0, 2, 4, 4, 2, 0, 2], 4), 2);
+        assert_eq!(indent_width(&[0, 4, 8, 9, 8, 4], 4), 4);
+        assert_eq!(indent_width(&[0, 4, 0, 3, 0, 2], 4), 2);
+    }
+
+    #[test]
+    fn falls_back_to_the_tab_width_without_indentation() {
+        assert_eq!(indent_width(&[0, 0, 0], 8), 8);
+        assert_eq!(indent_width(&[], 4), 4);
+    }
+
+    #[test]
+    fn leaves_left_to_right_lines_out() {
+        let lines = [line(vec![Span::new("let s = \"hello\";", WHITE)])];
+        assert!(visual_order(&lines).is_empty());
+    }
+
+    #[test]
+    fn reverses_right_to_left_text_in_code() {
+        let lines = [
+            line(vec![Span::new("let a = 1;", WHITE)]),
+            line(vec![
+                Span::new("let s = ", WHITE),
+                Span::new("\"שלום\"", GREEN),
+                Span::new(";", WHITE),
+            ]),
+        ];
+        let visual = visual_order(&lines);
+        assert_eq!(visual.len(), 1);
+        assert_eq!(text(&visual[&1]), "let s = \"םולש\";");
+        assert!(visual[&1]
+            .iter()
+            .any(|span| span.text == "\"םולש\"" && span.color == GREEN));
+    }
+
+    #[test]
+    fn mirrors_brackets_in_right_to_left_text() {
+        let lines = [line(vec![Span::new("// אב (גד) הו", GRAY)])];
+        assert_eq!(text(&visual_order(&lines)[&0]), "// וה (דג) בא");
+    }
+
+    #[test]
+    fn shapes_arabic_text() {
+        // Beh, seen and meem, drawn from right to left
+        let lines = [line(vec![Span::new("// \u{0628}\u{0633}\u{0645}", GRAY)])];
+        assert_eq!(
+            text(&visual_order(&lines)[&0]),
+            "// \u{FEE2}\u{FEB4}\u{FE91}"
+        );
+    }
+}
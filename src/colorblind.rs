@@ -0,0 +1,108 @@
+use {clap::ValueEnum, macroquad::prelude::*};
+
+/// Converts RGB colors to the LMS color space of the cones of the eye.
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [0.0809444479, -0.130504409, 0.116721066],
+    [-0.0102485335, 0.0540193266, -0.113614708],
+    [-0.000365296938, -0.00412161469, 0.693511405],
+];
+/// Moves the difference between a color and how it is seen into channels
+/// that can still be seen.
+const ERROR_SHIFT: [[f32; 3]; 3] = [[0., 0., 0.], [0.7, 1., 0.], [0.7, 0., 1.]];
+
+/// Color vision deficiency to adjust the colors of the code for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Colorblind {
+    /// Weak green cones, the most common deficiency
+    Deuteranopia,
+    /// Weak red cones
+    Protanopia,
+    /// Weak blue cones
+    Tritanopia,
+}
+
+impl Colorblind {
+    /// Daltonize the color, so that colors that look the same with the
+    /// deficiency are told apart by their brightness or other channels.
+    pub fn daltonize(self, color: Color) -> Color {
+        let rgb = [color.r, color.g, color.b];
+        let simulated = multiply(&LMS_TO_RGB, self.simulate(multiply(&RGB_TO_LMS, rgb)));
+        let error = [
+            rgb[0] - simulated[0],
+            rgb[1] - simulated[1],
+            rgb[2] - simulated[2],
+        ];
+        let shift = multiply(&ERROR_SHIFT, error);
+        Color::new(
+            (rgb[0] + shift[0]).clamp(0., 1.),
+            (rgb[1] + shift[1]).clamp(0., 1.),
+            (rgb[2] + shift[2]).clamp(0., 1.),
+            color.a,
+        )
+    }
+
+    /// How the cones with the deficiency perceive the LMS color.
+    fn simulate(self, [l, m, s]: [f32; 3]) -> [f32; 3] {
+        match self {
+            Colorblind::Deuteranopia => [l, 0.494207 * l + 1.24827 * s, s],
+            Colorblind::Protanopia => [2.02344 * m - 2.52581 * s, m, s],
+            Colorblind::Tritanopia => [l, m, -0.395913 * l + 0.801109 * m],
+        }
+    }
+}
+
+fn multiply(matrix: &[[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFICIENCIES: [Colorblind; 3] = [
+        Colorblind::Deuteranopia,
+        Colorblind::Protanopia,
+        Colorblind::Tritanopia,
+    ];
+
+    fn assert_close(a: Color, b: Color) {
+        let channels = [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)];
+        assert!(
+            channels.iter().all(|(a, b)| (a - b).abs() < 0.01),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn keeps_grays() {
+        for colorblind in DEFICIENCIES {
+            for gray in [BLACK, GRAY, WHITE] {
+                assert_close(colorblind.daltonize(gray), gray);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_the_opacity() {
+        let color = Color::new(0.8, 0.2, 0.1, 0.5);
+        for colorblind in DEFICIENCIES {
+            assert_eq!(colorblind.daltonize(color).a, 0.5);
+        }
+    }
+
+    #[test]
+    fn shifts_red_into_channels_that_can_be_seen() {
+        for colorblind in [Colorblind::Deuteranopia, Colorblind::Protanopia] {
+            let color = colorblind.daltonize(RED);
+            assert_eq!(color.r, 1.);
+            assert!(color.g > 0.1 && color.b > 0.1, "{:?}", color);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    codebox::CodeBox,
+    scroll::{Scroll, SCROLL_MARGIN},
+    Result,
+};
+use macroquad::prelude::*;
+use std::str::FromStr;
+
+/// Color of the border around the focused code box in a grid.
+const FOCUS_COLOR: Color = Color::new(1., 0.85, 0.2, 0.8);
+const FOCUS_BORDER_WIDTH: f32 = 3.;
+
+/// Number of columns and rows of code boxes on the screen, parsed from a
+/// string like `2x2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridLayout {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl GridLayout {
+    /// Area of the screen of the cell at `index`, counting left to right and
+    /// then top to bottom.
+    fn cell(&self, index: usize) -> Rect {
+        let width = screen_width() / self.columns as f32;
+        let height = screen_height() / self.rows as f32;
+        Rect::new(
+            (index % self.columns) as f32 * width,
+            (index / self.columns) as f32 * height,
+            width,
+            height,
+        )
+    }
+
+    pub fn cells(&self) -> usize {
+        self.columns * self.rows
+    }
+}
+
+impl FromStr for GridLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (columns, rows) = s
+            .to_lowercase()
+            .split_once('x')
+            .and_then(|(columns, rows)| {
+                Some((
+                    columns.trim().parse::<usize>().ok()?,
+                    rows.trim().parse::<usize>().ok()?,
+                ))
+            })
+            .ok_or_else(|| format!("Invalid layout, expected COLUMNSxROWS: {}", s))?;
+        match columns > 0 && rows > 0 {
+            true => Ok(GridLayout { columns, rows }),
+            false => Err(format!("Invalid layout, needs at least one cell: {}", s)),
+        }
+    }
+}
+
+/// Draw code boxes next to each other in equally wide panes. All panes share
+/// the same scroll position so that matching lines stay next to each other.
+/// Returns the position of the code in the first pane.
+pub fn draw_side_by_side(
+    codeboxes: &[&Result<CodeBox>],
+    scroll: &mut Scroll,
+    font_size: u16,
+) -> (f32, f32) {
+    let loaded: Vec<&CodeBox> = codeboxes
+        .iter()
+        .filter_map(|codebox| codebox.as_ref().ok())
+        .collect();
+    if let Some(codebox) = loaded.first() {
+        scroll.handle_keys(codebox.line_height());
+        scroll.handle_mouse_wheel(codebox.line_height());
+        scroll.handle_mouse_drag();
+    }
+    let width = loaded
+        .iter()
+        .map(|codebox| codebox.width_with_padding())
+        .fold(0., f32::max);
+    let height = loaded
+        .iter()
+        .map(|codebox| codebox.height_with_padding())
+        .fold(0., f32::max);
+
+    let pane_width = screen_width() / codeboxes.len().max(1) as f32;
+    let (xpos, ypos) = scroll.position_in(
+        Rect::new(0., 0., pane_width, screen_height()),
+        width,
+        height,
+    );
+    for (index, codebox) in codeboxes.iter().enumerate() {
+        let pane = Rect::new(index as f32 * pane_width, 0., pane_width, screen_height());
+        clip(Some(pane));
+        match codebox {
+            Ok(codebox) => codebox.draw(pane.x + xpos, ypos),
+            Err(e) => draw_pane_message(&e.to_string(), pane, font_size),
+        }
+    }
+    clip(None);
+    (xpos, ypos)
+}
+
+/// Draw the code boxes in the cells of a grid, each scrolled on its own.
+/// The mouse scrolls the box under it, and the keys scroll the focused box.
+/// Returns the index of a box that was clicked, to focus it.
+pub fn draw_grid(
+    codeboxes: &[&Result<CodeBox>],
+    layout: GridLayout,
+    scrolls: &mut [Scroll],
+    focused: usize,
+    font_size: u16,
+) -> Option<usize> {
+    let mouse = Vec2::from(mouse_position());
+    let mut clicked = None;
+    for (index, (codebox, scroll)) in codeboxes
+        .iter()
+        .zip(scrolls.iter_mut())
+        .take(layout.cells())
+        .enumerate()
+    {
+        let cell = layout.cell(index);
+        let hovered = cell.contains(mouse);
+        if hovered && is_mouse_button_pressed(MouseButton::Left) {
+            clicked = Some(index);
+        }
+        clip(Some(cell));
+        match codebox {
+            Ok(codebox) => {
+                if index == focused {
+                    scroll.handle_keys(codebox.line_height());
+                }
+                if hovered {
+                    scroll.handle_mouse_wheel(codebox.line_height());
+                    scroll.handle_mouse_drag();
+                }
+                let (xpos, ypos) = scroll.position_in(
+                    cell,
+                    codebox.width_with_padding(),
+                    codebox.height_with_padding(),
+                );
+                codebox.draw(xpos, ypos);
+            }
+            Err(e) => draw_pane_message(&e.to_string(), cell, font_size),
+        }
+        if index == focused {
+            draw_rectangle_lines(
+                cell.x,
+                cell.y,
+                cell.w - SCROLL_MARGIN / 2.,
+                cell.h,
+                FOCUS_BORDER_WIDTH * 2.,
+                FOCUS_COLOR,
+            );
+        }
+    }
+    clip(None);
+    clicked
+}
+
+/// Only draw inside the pane, so wide code doesn't spill into the next pane.
+fn clip(pane: Option<Rect>) {
+    let mut gl = unsafe { get_internal_gl() };
+    gl.flush();
+    // The scissor rectangle starts at the bottom of the screen
+    gl.quad_gl.scissor(pane.map(|pane| {
+        (
+            pane.x as i32,
+            (screen_height() - pane.y - pane.h) as i32,
+            pane.w as i32 - SCROLL_MARGIN as i32 / 2,
+            pane.h as i32,
+        )
+    }));
+}
+
+fn draw_pane_message(message: &str, pane: Rect, font_size: u16) {
+    let text_dim = measure_text(message, None, font_size, 1.0);
+    draw_text(
+        message,
+        pane.x + pane.w / 2. - text_dim.width / 2.,
+        pane.y + pane.h / 2. - text_dim.height / 2.,
+        font_size as f32,
+        WHITE,
+    );
+}
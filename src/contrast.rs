@@ -0,0 +1,92 @@
+use macroquad::prelude::*;
+
+/// Contrast ratio that WCAG level AAA requires between text and its
+/// background.
+pub const HIGH_CONTRAST_RATIO: f32 = 7.;
+/// Number of steps a color is mixed towards black or white in, until it has
+/// enough contrast.
+const CONTRAST_STEPS: usize = 20;
+
+/// Relative luminance of the color, as defined by WCAG, from 0 for black to
+/// 1 for white.
+fn luminance(color: Color) -> f32 {
+    let channel = |c: f32| match c <= 0.03928 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    };
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+/// Contrast ratio between two colors, as defined by WCAG, from 1 for the
+/// same color to 21 for black on white.
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Whether black or white has the most contrast against the color.
+pub fn extreme_for(background: Color) -> Color {
+    match contrast_ratio(background, BLACK) > contrast_ratio(background, WHITE) {
+        true => BLACK,
+        false => WHITE,
+    }
+}
+
+/// The opaque color mixed with black or white until it has at least the
+/// contrast ratio against the background, keeping as much of its hue as
+/// possible.
+pub fn with_contrast(color: Color, background: Color, ratio: f32) -> Color {
+    let target = extreme_for(background);
+    (0..=CONTRAST_STEPS)
+        .map(|step| mix(color, target, step as f32 / CONTRAST_STEPS as f32))
+        .find(|color| contrast_ratio(*color, background) >= ratio)
+        .unwrap_or(target)
+}
+
+/// The color drawn over the opaque background, like a translucent line
+/// highlight.
+pub fn composite(color: Color, background: Color) -> Color {
+    mix(background, color, color.a)
+}
+
+/// Opaque mix of two colors, with `amount` of the second.
+fn mix(a: Color, b: Color, amount: f32) -> Color {
+    Color::new(
+        a.r + (b.r - a.r) * amount,
+        a.g + (b.g - a.g) * amount,
+        a.b + (b.b - a.b) * amount,
+        1.,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn black_on_white_has_the_highest_ratio() {
+        assert!((contrast_ratio(BLACK, WHITE) - 21.).abs() < 0.01);
+        assert!((contrast_ratio(GRAY, GRAY) - 1.).abs() < 0.01);
+    }
+
+    #[test]
+    fn composites_translucent_colors_over_the_background() {
+        let color = composite(Color::new(1., 1., 1., 0.25), BLACK);
+        assert_eq!(color, Color::new(0.25, 0.25, 0.25, 1.));
+    }
+
+    #[test]
+    fn keeps_colors_with_enough_contrast() {
+        assert_eq!(with_contrast(BLACK, WHITE, HIGH_CONTRAST_RATIO), BLACK);
+    }
+
+    #[test]
+    fn mixes_colors_until_they_have_enough_contrast() {
+        let background = Color::new(0.1, 0.1, 0.1, 1.);
+        let color = with_contrast(DARKBLUE, background, HIGH_CONTRAST_RATIO);
+        assert!(contrast_ratio(color, background) >= HIGH_CONTRAST_RATIO);
+        // Mixed towards white, since the background is dark
+        assert!(color.r > DARKBLUE.r && color.g > DARKBLUE.g && color.b > DARKBLUE.b);
+        assert_eq!(color.a, 1.);
+    }
+}
@@ -0,0 +1,96 @@
+/// Number of lines at the start and end of a file that are searched for modelines.
+const MODELINE_LINES: usize = 5;
+
+/// Guess the language of code without a known file extension from its
+/// contents: the interpreter of a shebang line or the first characters of
+/// the file.
+pub fn from_content(code: &str) -> Option<String> {
+    from_shebang(code).or_else(|| from_start(code))
+}
+
+/// Language of the interpreter in a shebang line, e.g. `#!/usr/bin/env python3`.
+fn from_shebang(code: &str) -> Option<String> {
+    let shebang = code.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+    // Versioned interpreters, like python3 or ruby2.7
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let language = match interpreter {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "sh",
+        "python" => "py",
+        "node" | "nodejs" | "deno" => "js",
+        "ruby" => "rb",
+        "perl" => "pl",
+        "php" => "php",
+        "lua" => "lua",
+        "Rscript" => "r",
+        "groovy" => "groovy",
+        "make" => "makefile",
+        _ => return None,
+    };
+    Some(language.to_string())
+}
+
+/// Language set in a Vim or Emacs modeline near the start or end of the file,
+/// e.g. `# vim: ft=python` or `// -*- mode: ruby -*-`.
+pub fn from_modeline(code: &str) -> Option<String> {
+    let lines: Vec<&str> = code.lines().collect();
+    let end = lines.len().saturating_sub(MODELINE_LINES);
+    lines
+        .iter()
+        .take(MODELINE_LINES)
+        .chain(lines.iter().skip(end.max(MODELINE_LINES)))
+        .find_map(|line| vim_modeline(line).or_else(|| emacs_modeline(line)))
+}
+
+fn vim_modeline(line: &str) -> Option<String> {
+    let (_, options) = line
+        .split_once("vim:")
+        .or_else(|| line.split_once("vi:"))
+        .or_else(|| line.split_once("ex:"))?;
+    options
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .filter_map(|option| option.split_once('='))
+        .find(|(name, _)| matches!(*name, "ft" | "filetype" | "syntax" | "syn"))
+        .map(|(_, language)| language.to_string())
+        .filter(|language| !language.is_empty())
+}
+
+fn emacs_modeline(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (variables, _) = rest.split_once("-*-")?;
+    let variables = variables.trim();
+    if !variables.contains(':') {
+        // The whole modeline is the mode, e.g. `-*- ruby -*-`
+        return Some(variables.to_lowercase()).filter(|mode| !mode.is_empty());
+    }
+    variables
+        .split(';')
+        .filter_map(|variable| variable.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("mode"))
+        .map(|(_, mode)| mode.trim().to_lowercase())
+        .filter(|mode| !mode.is_empty())
+}
+
+/// Recognize file formats by how the file starts, like `<?php` or a JSON object.
+fn from_start(code: &str) -> Option<String> {
+    let code = code.trim();
+    let start: String = code.chars().take(20).collect::<String>().to_lowercase();
+    let language = if start.starts_with("<?php") {
+        "php"
+    } else if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        "html"
+    } else if start.starts_with("<?xml") || (code.starts_with('<') && code.ends_with('>')) {
+        "xml"
+    } else if (code.starts_with('{') && code.ends_with('}') && code.contains("\":"))
+        || (code.starts_with('[') && code.ends_with(']'))
+    {
+        "json"
+    } else {
+        return None;
+    };
+    Some(language.to_string())
+}
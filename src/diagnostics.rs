@@ -0,0 +1,185 @@
+use {
+    crate::{
+        code_theme::{hex_color, CodeTheme},
+        codebox::CodeBox,
+    },
+    macroquad::prelude::*,
+    nanoserde::DeJson,
+    std::path::{Component, Path, PathBuf},
+};
+
+/// Height of the waves of the squiggly underline.
+const SQUIGGLE_HEIGHT: f32 = 2.;
+/// Length of each half wave of the squiggly underline.
+const SQUIGGLE_LENGTH: f32 = 3.;
+const POPOVER_PADDING: f32 = 8.;
+/// Distance between the mouse cursor and the popover.
+const POPOVER_OFFSET: f32 = 16.;
+const POPOVER_BACKGROUND_COLOR: Color = Color::new(0.1, 0.1, 0.12, 0.95);
+const POPOVER_TEXT_COLOR: Color = Color::new(0.95, 0.95, 0.95, 1.);
+
+/// A problem on a line of the code, like a compiler error.
+#[derive(Clone, Debug, DeJson)]
+pub struct Diagnostic {
+    /// File the diagnostic is in, like `src/main.rs`, or the first file
+    /// given on the command line if it's left out.
+    #[nserde(default)]
+    pub file: String,
+    pub line: usize,
+    /// `error`, `warning`, `info` or `hint`.
+    #[nserde(default)]
+    pub severity: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn color(&self, code_theme: &CodeTheme) -> Color {
+        hex_color(match self.severity.to_lowercase().as_str() {
+            "warning" | "warn" => &code_theme.diagnostic_warning_color,
+            "info" | "information" | "hint" | "note" => &code_theme.diagnostic_info_color,
+            _ => &code_theme.diagnostic_error_color,
+        })
+    }
+}
+
+/// Diagnostics loaded from a JSON file with a list of objects like
+/// `{"file": "src/main.rs", "line": 3, "severity": "error", "message": "mismatched types"}`,
+/// shown as squiggly underlines with the message in a popover on hover.
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+    /// File of the diagnostics without one.
+    default_file: Option<String>,
+}
+
+impl Diagnostics {
+    pub async fn load(path: &str, default_file: Option<String>) -> Option<Self> {
+        let json = load_string(path)
+            .await
+            .map_err(|e| warn!("Couldn't load diagnostics {}: {}", path, e))
+            .ok()?;
+        match Vec::<Diagnostic>::deserialize_json(&json) {
+            Ok(diagnostics) => Some(Self {
+                diagnostics,
+                default_file,
+            }),
+            Err(e) => {
+                warn!("Couldn't parse diagnostics {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Underline the code of each line with diagnostics, from its first
+    /// non-whitespace character, for the code of the file.
+    pub fn draw(
+        &self,
+        filename: &str,
+        codebox: &CodeBox,
+        xpos: f32,
+        ypos: f32,
+        code_theme: &CodeTheme,
+    ) {
+        for (index, line) in codebox.lines().iter().enumerate() {
+            let Some(diagnostic) = self.at_line(filename, line.number).next() else {
+                continue;
+            };
+            let text = line.text();
+            let indent = text.chars().take_while(|c| c.is_whitespace()).count();
+            let length = text.chars().count();
+            if length == indent {
+                continue;
+            }
+            let start = codebox.column_x(xpos, line, indent);
+            let end = codebox.column_x(xpos, line, length);
+            let y = codebox.line_top(ypos, index) + codebox.line_height() - SQUIGGLE_HEIGHT;
+            draw_squiggle(start, end, y, diagnostic.color(code_theme));
+        }
+    }
+
+    /// Show the messages of the line under the mouse cursor next to it.
+    pub fn draw_popover(&self, filename: &str, codebox: &CodeBox, ypos: f32, camera: &Camera2D) {
+        let mouse = camera.screen_to_world(Vec2::from(mouse_position()));
+        let row = (mouse.y - codebox.line_top(ypos, 0)) / codebox.line_height();
+        let Some(line) = codebox.lines().get(row as usize).filter(|_| row >= 0.) else {
+            return;
+        };
+        let messages: Vec<String> = self
+            .at_line(filename, line.number)
+            .map(|diagnostic| match diagnostic.severity.is_empty() {
+                true => diagnostic.message.clone(),
+                false => format!("{}: {}", diagnostic.severity, diagnostic.message),
+            })
+            .collect();
+        if messages.is_empty() {
+            return;
+        }
+        let font_size = codebox.font_size();
+        let line_height = font_size as f32 * 1.3;
+        let width = messages
+            .iter()
+            .map(|message| measure_text(message, None, font_size, 1.).width)
+            .fold(0., f32::max)
+            + POPOVER_PADDING * 2.;
+        let height = messages.len() as f32 * line_height + POPOVER_PADDING * 2.;
+        let (mouse_x, mouse_y) = mouse_position();
+        let x = (mouse_x + POPOVER_OFFSET)
+            .min(screen_width() - width)
+            .max(0.);
+        let y = (mouse_y + POPOVER_OFFSET)
+            .min(screen_height() - height)
+            .max(0.);
+        draw_rectangle(x, y, width, height, POPOVER_BACKGROUND_COLOR);
+        for (index, message) in messages.iter().enumerate() {
+            draw_text(
+                message,
+                x + POPOVER_PADDING,
+                y + POPOVER_PADDING + (index as f32 + 0.8) * line_height,
+                font_size as f32,
+                POPOVER_TEXT_COLOR,
+            );
+        }
+    }
+
+    fn at_line<'a>(
+        &'a self,
+        filename: &'a str,
+        number: usize,
+    ) -> impl Iterator<Item = &'a Diagnostic> {
+        self.diagnostics.iter().filter(move |diagnostic| {
+            let file = match diagnostic.file.is_empty() {
+                true => self.default_file.as_deref(),
+                false => Some(diagnostic.file.as_str()),
+            };
+            diagnostic.line == number && file.is_some_and(|file| same_file(file, filename))
+        })
+    }
+}
+
+/// Whether the paths are the same file, where one of them may be relative to
+/// a directory further up, like `src/main.rs` and `project/src/main.rs`.
+fn same_file(a: &str, b: &str) -> bool {
+    let components = |path: &str| -> PathBuf {
+        Path::new(path)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect()
+    };
+    let (a, b) = (components(a), components(b));
+    a.ends_with(&b) || b.ends_with(&a)
+}
+
+/// Draw a wavy line from `start` to `end` at the height `y`.
+fn draw_squiggle(start: f32, end: f32, y: f32, color: Color) {
+    let mut x = start;
+    let mut up = true;
+    while x < end {
+        let next = (x + SQUIGGLE_LENGTH).min(end);
+        let (from, to) = match up {
+            true => (y + SQUIGGLE_HEIGHT, y - SQUIGGLE_HEIGHT),
+            false => (y - SQUIGGLE_HEIGHT, y + SQUIGGLE_HEIGHT),
+        };
+        draw_line(x, from, next, to, 1., color);
+        x = next;
+        up = !up;
+    }
+}
@@ -0,0 +1,739 @@
+use {
+    crate::{
+        animation::Animation,
+        background::Background,
+        code_theme::color_to_hex,
+        codebox::{CodeBox, MarkerEdge, WRAP_INDENT, WRAP_MARKER},
+        html::render_html,
+        pdf::render_pdf,
+        watermark::Watermark,
+    },
+    chrono::Local,
+    macroquad::prelude::*,
+    std::{
+        error,
+        fmt::{self, Write},
+        fs,
+        fs::File,
+        io,
+        path::{Path, PathBuf},
+    },
+    syntect::highlighting::FontStyle,
+};
+
+/// Default margin of background around the code box in exported images.
+const EXPORT_MARGIN: f32 = 40.;
+
+/// Seconds to keep showing the finished code at the end of an animation.
+const ANIMATION_HOLD_SECONDS: f32 = 2.;
+
+pub struct ExportOptions {
+    pub animation: Option<Animation>,
+    pub fps: u16,
+    /// Number of frames to export, instead of the length of the animation.
+    pub frames: Option<usize>,
+    pub watermark: Option<Watermark>,
+    /// Margin of background around the code box, instead of the default.
+    pub margin: Option<f32>,
+}
+
+impl ExportOptions {
+    /// Margin around the code box, wide enough for its drop shadow.
+    fn margin(&self, codebox: &CodeBox) -> f32 {
+        self.margin
+            .unwrap_or(EXPORT_MARGIN)
+            .max(codebox.shadow_extent().ceil())
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "{}", e),
+            ExportError::Png(e) => write!(f, "Couldn't encode PNG image: {}", e),
+            ExportError::Gif(e) => write!(f, "Couldn't encode GIF animation: {}", e),
+        }
+    }
+}
+
+impl error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> ExportError {
+        ExportError::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for ExportError {
+    fn from(err: png::EncodingError) -> ExportError {
+        ExportError::Png(err)
+    }
+}
+
+impl From<gif::EncodingError> for ExportError {
+    fn from(err: gif::EncodingError) -> ExportError {
+        ExportError::Gif(err)
+    }
+}
+
+/// Export the code box to a file, the format is decided by the file extension.
+pub async fn export(
+    codebox: &CodeBox,
+    background: &Background,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<(), ExportError> {
+    match extension(path).as_str() {
+        "svg" => export_svg(codebox, background, path, options)?,
+        "pdf" => export_pdf(codebox, path)?,
+        "html" | "htm" => export_html(codebox, path)?,
+        "gif" => export_gif(codebox, background, path, options).await?,
+        #[cfg(feature = "record")]
+        "mp4" | "webm" | "mkv" | "mov" => record_video(codebox, background, path, options).await?,
+        _ => export_png(codebox, background, path, options).await?,
+    }
+    info!("Exported code to: {}", path.display());
+    Ok(())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Offscreen render target with room for the code box and a margin of
+/// background around it.
+struct Canvas {
+    render_target: RenderTarget,
+    width: f32,
+    height: f32,
+    margin: f32,
+}
+
+impl Canvas {
+    fn new(codebox: &CodeBox, margin: f32) -> Self {
+        let width = (codebox.width_with_padding() + margin * 2.).ceil();
+        let height = (codebox.height_with_padding() + margin * 2.).ceil();
+        let render_target = render_target(width as u32, height as u32);
+        render_target.texture.set_filter(FilterMode::Linear);
+        Self {
+            render_target,
+            width,
+            height,
+            margin,
+        }
+    }
+
+    async fn render(
+        &self,
+        codebox: &CodeBox,
+        background: &Background,
+        watermark: Option<&Watermark>,
+        visible_chars: usize,
+    ) -> Image {
+        // A positive y zoom puts the top of the code box in the first row of
+        // the texture data, so the exported image isn't upside down.
+        set_camera(&Camera2D {
+            zoom: vec2(2. / self.width, 2. / self.height),
+            target: vec2(self.width / 2., self.height / 2.),
+            render_target: Some(self.render_target.clone()),
+            ..Default::default()
+        });
+        background.draw(self.width, self.height, 0.);
+        codebox.draw_visible(self.margin, self.margin, visible_chars);
+        if let Some(watermark) = watermark {
+            watermark.draw(self.width, self.height);
+        }
+        set_default_camera();
+
+        // Let macroquad flush the draw calls before reading back the pixels.
+        next_frame().await;
+        self.render_target.texture.get_texture_data()
+    }
+}
+
+/// Render the code box on top of the background to an offscreen render
+/// target and save it as a PNG file.
+pub async fn export_png(
+    codebox: &CodeBox,
+    background: &Background,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<(), png::EncodingError> {
+    let png = render_png(codebox, background, options).await?;
+    fs::write(path, png)?;
+    Ok(())
+}
+
+/// File in the working directory to save a screenshot to, named by the
+/// local time down to the millisecond, like
+/// `rusty-code-20231015-142501-042.png`.
+pub fn screenshot_path() -> PathBuf {
+    PathBuf::from(
+        Local::now()
+            .format("rusty-code-%Y%m%d-%H%M%S-%3f.png")
+            .to_string(),
+    )
+}
+
+/// Render the code box on top of the background to an image.
+async fn render_image(
+    codebox: &CodeBox,
+    background: &Background,
+    options: &ExportOptions,
+) -> Image {
+    Canvas::new(codebox, options.margin(codebox))
+        .render(codebox, background, options.watermark.as_ref(), usize::MAX)
+        .await
+}
+
+/// Render the code box on top of the background and put the image on the
+/// system clipboard. On Linux the clipboard is served by the program that
+/// copied, for as long as the clipboard is kept, so with `wait` the program
+/// keeps serving the image until something else is copied, to let it exit
+/// afterwards.
+pub async fn copy_image(
+    codebox: &CodeBox,
+    background: &Background,
+    options: &ExportOptions,
+    clipboard: &mut arboard::Clipboard,
+    wait: bool,
+) -> Result<(), arboard::Error> {
+    let image = render_image(codebox, background, options).await;
+    let image = arboard::ImageData {
+        width: image.width as usize,
+        height: image.height as usize,
+        bytes: image.bytes.into(),
+    };
+    #[cfg(target_os = "linux")]
+    if wait {
+        use arboard::SetExtLinux;
+        return clipboard.set().wait().image(image);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = wait;
+    clipboard.set_image(image)
+}
+
+/// Render the code box on top of the background and encode it as a PNG
+/// image, to send it without saving it to a file.
+pub async fn render_png(
+    codebox: &CodeBox,
+    background: &Background,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, png::EncodingError> {
+    encode_png(&render_image(codebox, background, options).await)
+}
+
+fn encode_png(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image.bytes)?;
+    Ok(bytes)
+}
+
+/// Render each frame of the animation and save them as an animated GIF.
+async fn export_gif(
+    codebox: &CodeBox,
+    background: &Background,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<(), gif::EncodingError> {
+    let canvas = Canvas::new(codebox, options.margin(codebox));
+    let fps = options.fps.max(1);
+    let size = |length: f32| {
+        u16::try_from(length as u32).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "GIF images can be at most {} pixels wide and high",
+                    u16::MAX
+                ),
+            )
+        })
+    };
+    let (width, height) = (size(canvas.width)?, size(canvas.height)?);
+    let mut encoder = gif::Encoder::new(File::create(path)?, width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    // Hundredths of a second that the frames so far are shown for
+    let mut shown = 0;
+    for (index, visible_chars) in frames(codebox, options).into_iter().enumerate() {
+        // The delay is in whole hundredths of a second, so each frame is
+        // shown until the hundredth closest to its end, which keeps the
+        // length of the animation. Frames that would be shown for less than
+        // a hundredth are left out.
+        let end = ((index + 1) as f32 * 100. / fps as f32).round() as u32;
+        if end == shown {
+            continue;
+        }
+        let delay = end - shown;
+        shown = end;
+        let mut image = canvas
+            .render(
+                codebox,
+                background,
+                options.watermark.as_ref(),
+                visible_chars,
+            )
+            .await;
+        let mut frame =
+            gif::Frame::from_rgba_speed(image.width, image.height, &mut image.bytes, 10);
+        frame.delay = delay as u16;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+/// Render each frame of the animation and encode them as a video with
+/// ffmpeg, which picks the codec from the file extension.
+#[cfg(feature = "record")]
+async fn record_video(
+    codebox: &CodeBox,
+    background: &Background,
+    path: &Path,
+    options: &ExportOptions,
+) -> io::Result<()> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let canvas = Canvas::new(codebox, options.margin(codebox));
+    let fps = options.fps.max(1);
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgba",
+        ])
+        .args(["-s", &format!("{}x{}", canvas.width, canvas.height)])
+        .args(["-r", &fps.to_string(), "-i", "-"])
+        // Most encoders need even dimensions
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    let mut stdin = ffmpeg
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "ffmpeg has no stdin"))?;
+    let mut written = Ok(());
+    for visible_chars in frames(codebox, options) {
+        let image = canvas
+            .render(
+                codebox,
+                background,
+                options.watermark.as_ref(),
+                visible_chars,
+            )
+            .await;
+        written = stdin.write_all(&image.bytes);
+        if written.is_err() {
+            break;
+        }
+    }
+    // The exit status of ffmpeg tells why it stopped reading the frames
+    drop(stdin);
+    match ffmpeg.wait()? {
+        status if status.success() => written,
+        status => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("ffmpeg exited with {}", status),
+        )),
+    }
+}
+
+/// Render each frame of the animation and save them as numbered PNG files
+/// in the directory.
+pub async fn export_frames(
+    codebox: &CodeBox,
+    background: &Background,
+    directory: &Path,
+    options: &ExportOptions,
+) -> Result<(), ExportError> {
+    fs::create_dir_all(directory)?;
+    let canvas = Canvas::new(codebox, options.margin(codebox));
+    let frames = frames(codebox, options);
+    for (index, visible_chars) in frames.iter().enumerate() {
+        let image = canvas
+            .render(
+                codebox,
+                background,
+                options.watermark.as_ref(),
+                *visible_chars,
+            )
+            .await;
+        let path = directory.join(format!("frame_{:05}.png", index + 1));
+        fs::write(path, encode_png(&image)?)?;
+    }
+    info!(
+        "Exported {} frames to: {}",
+        frames.len(),
+        directory.display()
+    );
+    Ok(())
+}
+
+/// Number of visible characters in each frame of an animation.
+fn frames(codebox: &CodeBox, options: &ExportOptions) -> Vec<usize> {
+    let fps = options.fps.max(1) as f32;
+    let frame_count = match (options.frames, options.animation) {
+        (Some(frames), _) => frames,
+        (None, Some(animation)) => {
+            let duration = animation.duration(codebox.char_count()) + ANIMATION_HOLD_SECONDS;
+            (duration * fps).ceil() as usize
+        }
+        (None, None) => 1,
+    };
+    (0..frame_count)
+        .map(|frame| match options.animation {
+            Some(animation) => animation.visible_chars(frame as f32 / fps),
+            None => usize::MAX,
+        })
+        .collect()
+}
+
+/// Save the highlighted code as an SVG file with text elements.
+fn export_svg(
+    codebox: &CodeBox,
+    background: &Background,
+    path: &Path,
+    options: &ExportOptions,
+) -> io::Result<()> {
+    let svg = render_svg(
+        codebox,
+        background,
+        options.watermark.as_ref(),
+        options.margin(codebox),
+    );
+    fs::write(path, svg)
+}
+
+pub fn render_svg(
+    codebox: &CodeBox,
+    background: &Background,
+    watermark: Option<&Watermark>,
+    margin: f32,
+) -> String {
+    let box_width = codebox.width_with_padding();
+    let box_height = codebox.height_with_padding();
+    let width = box_width + margin * 2.;
+    let height = box_height + margin * 2.;
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    match background {
+        Background::Color(color) => {
+            let _ = writeln!(
+                svg,
+                r#"<rect width="100%" height="100%" fill="{}" fill-opacity="{}"/>"#,
+                color_to_hex(*color),
+                color.a
+            );
+        }
+        // Same colors as the gradient shader: red increases to the right, green
+        // increases upwards and blue decreases to the right. Custom shaders
+        // can't be drawn in SVG, so they also get the gradient.
+        Background::Shader(_) => svg.push_str(
+            r##"<defs>
+<linearGradient id="horizontal" x1="0" y1="0" x2="1" y2="0"><stop offset="0" stop-color="#0000ff"/><stop offset="1" stop-color="#ff0000"/></linearGradient>
+<linearGradient id="vertical" x1="0" y1="0" x2="0" y2="1"><stop offset="0" stop-color="#00ff00"/><stop offset="1" stop-color="#000000"/></linearGradient>
+</defs>
+<rect width="100%" height="100%" fill="url(#horizontal)"/>
+<rect width="100%" height="100%" fill="url(#vertical)" style="mix-blend-mode:screen"/>
+"##,
+        ),
+    }
+    let box_filter = match codebox.shadow() {
+        Some(shadow) => {
+            let _ = writeln!(
+                svg,
+                r#"<defs><filter id="shadow" x="-50%" y="-50%" width="200%" height="200%"><feDropShadow dx="{}" dy="{}" stdDeviation="{}" flood-color="{}" flood-opacity="{}"/></filter></defs>"#,
+                shadow.offset.x,
+                shadow.offset.y,
+                shadow.blur / 2.,
+                color_to_hex(shadow.color),
+                shadow.color.a
+            );
+            r#" filter="url(#shadow)""#
+        }
+        None => "",
+    };
+    let _ = writeln!(
+        svg,
+        r#"<path d="{}" fill="{}"{box_filter}/>"#,
+        rounded_rect_path(
+            Rect::new(margin, margin, box_width, box_height),
+            codebox.corners(true, true, true, true)
+        ),
+        color_to_hex(codebox.background_color())
+    );
+    let _ = writeln!(
+        svg,
+        r#"<g font-family="{}, monospace" font-size="{}px" xml:space="preserve">"#,
+        escape_xml(codebox.font_family()),
+        codebox.font_size()
+    );
+
+    if let Some(header) = codebox.header() {
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" fill="{}"/>"#,
+            rounded_rect_path(
+                Rect::new(margin, margin, box_width, header.height),
+                codebox.corners(true, true, false, true)
+            ),
+            color_to_hex(header.background_color)
+        );
+        for (center, radius, color) in codebox.window_controls(margin, margin) {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{radius}" fill="{}"/>"#,
+                center.x,
+                center.y,
+                color_to_hex(color)
+            );
+        }
+        if let Some(icon) = &header.icon {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="{}" fill="{}">{}</text>"#,
+                codebox.header_title_x(margin),
+                margin + codebox.header_baseline_offset(),
+                escape_xml(&icon.family),
+                color_to_hex(header.text_color),
+                icon.glyph
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+            codebox.header_title_x(margin) + codebox.header_icon_width(),
+            margin + codebox.header_baseline_offset(),
+            color_to_hex(header.text_color),
+            escape_xml(&header.title)
+        );
+    }
+
+    let code_x = margin + codebox.padding() + codebox.gutter_width();
+    for (index, line) in codebox.lines().iter().enumerate() {
+        if let Some(color) = codebox.line_background(line) {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{code_x}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                codebox.line_top(margin, index),
+                box_width - codebox.padding() - codebox.gutter_width(),
+                codebox.line_height(),
+                color_to_hex(color)
+            );
+        }
+    }
+
+    let mut text_x = margin + codebox.padding();
+    if let Some(background_color) = codebox.gutter_background_color() {
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" fill="{}"/>"#,
+            rounded_rect_path(
+                Rect::new(
+                    margin,
+                    margin + codebox.header_height(),
+                    codebox.padding() + codebox.gutter_width(),
+                    box_height - codebox.header_height(),
+                ),
+                codebox.corners(codebox.header().is_none(), false, true, true)
+            ),
+            color_to_hex(background_color)
+        );
+    }
+    if let Some(gutter) = codebox.gutter() {
+        for (index, line) in codebox.lines().iter().enumerate() {
+            if line.continuation {
+                continue;
+            }
+            let _ = writeln!(
+                svg,
+                r#"<text x="{text_x}" y="{}" fill="{}">{:>width$}</text>"#,
+                margin + codebox.baseline_offset(index),
+                color_to_hex(gutter.text_color),
+                line.number,
+                width = gutter.digits
+            );
+        }
+        text_x += gutter.width;
+    }
+    if let Some(blame) = codebox.blame() {
+        for (index, line) in codebox.lines().iter().enumerate() {
+            if let Some(annotation) = blame.annotation(line) {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{text_x}" y="{}" fill="{}" fill-opacity="{}">{}</text>"#,
+                    margin + codebox.baseline_offset(index),
+                    color_to_hex(annotation.color),
+                    annotation.color.a,
+                    escape_xml(&annotation.text)
+                );
+            }
+        }
+        text_x += blame.width;
+    }
+    if let Some(git_gutter) = codebox.git_gutter() {
+        let x = codebox.git_gutter_x(margin, git_gutter);
+        for (index, line) in codebox.lines().iter().enumerate() {
+            let top = codebox.line_top(margin, index);
+            if let Some(color) = git_gutter.bar_color(line) {
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{x}" y="{top}" width="{}" height="{}" fill="{}"/>"#,
+                    git_gutter.width,
+                    codebox.line_height(),
+                    color_to_hex(color)
+                );
+            }
+            if let Some(edge) = git_gutter.removed_marker(codebox.lines(), index) {
+                let y = match edge {
+                    MarkerEdge::Top => top,
+                    MarkerEdge::Bottom => top + codebox.line_height(),
+                };
+                let size = git_gutter.width * 2.;
+                let _ = writeln!(
+                    svg,
+                    r#"<polygon points="{x},{} {x},{} {},{y}" fill="{}"/>"#,
+                    y - size,
+                    y + size,
+                    x + size,
+                    color_to_hex(git_gutter.removed_color)
+                );
+            }
+        }
+    }
+
+    for (index, line) in codebox.lines().iter().enumerate() {
+        let _ = write!(
+            svg,
+            r#"<text x="{text_x}" y="{}">"#,
+            margin + codebox.baseline_offset(index)
+        );
+        if line.continuation {
+            let _ = write!(
+                svg,
+                r#"<tspan fill="{}">{:<width$}</tspan>"#,
+                color_to_hex(codebox.wrap_marker_color()),
+                WRAP_MARKER,
+                width = WRAP_INDENT
+            );
+        }
+        for span in line.spans.iter() {
+            let _ = write!(
+                svg,
+                r#"<tspan fill="{}"{}>{}</tspan>"#,
+                color_to_hex(span.color),
+                svg_font_style(span.style),
+                escape_xml(&span.text)
+            );
+        }
+        svg.push_str("</text>\n");
+    }
+    svg.push_str("</g>\n");
+
+    if let Some(watermark) = watermark {
+        let (image_rect, baseline) = watermark.layout(width, height);
+        if let (Some(path), Some(rect)) = (watermark.image_path(), image_rect) {
+            let _ = writeln!(
+                svg,
+                r#"<image href="{}" x="{}" y="{}" width="{}" height="{}"/>"#,
+                escape_xml(path),
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}px" fill="{}" fill-opacity="{}">{}</text>"#,
+            baseline.x,
+            baseline.y,
+            watermark.font_size(),
+            color_to_hex(watermark.color()),
+            watermark.color().a,
+            escape_xml(watermark.text())
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// SVG attributes for the bold, italic and underlined parts of the style.
+fn svg_font_style(style: FontStyle) -> String {
+    let mut attributes = String::new();
+    if style.contains(FontStyle::BOLD) {
+        attributes.push_str(r#" font-weight="bold""#);
+    }
+    if style.contains(FontStyle::ITALIC) {
+        attributes.push_str(r#" font-style="italic""#);
+    }
+    if style.contains(FontStyle::UNDERLINE) {
+        attributes.push_str(r#" text-decoration="underline""#);
+    }
+    attributes
+}
+
+/// SVG path data for a rectangle with the radius of each corner given as
+/// top left, top right, bottom right and bottom left.
+fn rounded_rect_path(rect: Rect, radii: [f32; 4]) -> String {
+    let [top_left, top_right, bottom_right, bottom_left] = radii;
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    format!(
+        "M{} {top} H{} A{top_right} {top_right} 0 0 1 {right} {} V{} A{bottom_right} {bottom_right} 0 0 1 {} {bottom} H{} A{bottom_left} {bottom_left} 0 0 1 {left} {} V{} A{top_left} {top_left} 0 0 1 {} {top} Z",
+        left + top_left,
+        right - top_right,
+        top + top_right,
+        bottom - bottom_right,
+        right - bottom_right,
+        left + bottom_left,
+        bottom - bottom_left,
+        top + top_left,
+        left + top_left,
+    )
+}
+
+/// Save the highlighted code as a PDF document, split into A4 pages.
+fn export_pdf(codebox: &CodeBox, path: &Path) -> io::Result<()> {
+    fs::write(path, render_pdf(codebox))
+}
+
+/// Save the highlighted code as a standalone HTML page with inline CSS.
+fn export_html(codebox: &CodeBox, path: &Path) -> io::Result<()> {
+    fs::write(path, render_html(codebox))
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,111 @@
+use {
+    crate::{codebox::CodeBox, scroll::Scroll},
+    macroquad::prelude::*,
+    std::ops::RangeInclusive,
+};
+
+/// Blocks of code that can be folded, found by indentation, and which of
+/// them are folded.
+#[derive(Clone, Debug, Default)]
+pub struct Folds {
+    /// Line numbers of each block, from the line that starts it to its last
+    /// indented line. Nested blocks come after the blocks they are in.
+    regions: Vec<RangeInclusive<usize>>,
+    /// Indexes of the folded regions.
+    folded: Vec<usize>,
+}
+
+impl Folds {
+    /// Find the blocks of the code. A block starts at a line followed by more
+    /// indented lines, which works for both brace and indentation based
+    /// languages, since closing braces are indented like the opening line.
+    pub fn new(code: &str) -> Self {
+        let indents: Vec<Option<usize>> = code.lines().map(indent).collect();
+        let mut regions = Vec::new();
+        for (start, start_indent) in indents.iter().enumerate() {
+            let Some(start_indent) = start_indent else {
+                continue;
+            };
+            let end = indents
+                .iter()
+                .enumerate()
+                .skip(start + 1)
+                .filter_map(|(index, indent)| indent.map(|indent| (index, indent)))
+                .take_while(|(_, indent)| indent > start_indent)
+                .last()
+                .map(|(index, _)| index);
+            if let Some(end) = end {
+                regions.push(start + 1..=end + 1);
+            }
+        }
+        Self {
+            regions,
+            folded: Vec::new(),
+        }
+    }
+
+    /// Unfold the folded block that the line is in, or else fold the
+    /// innermost block that it is in. Returns true if a block was toggled.
+    pub fn toggle(&mut self, line: usize) -> bool {
+        let outermost_folded = self
+            .folded
+            .iter()
+            .position(|index| self.regions[*index].contains(&line));
+        if let Some(position) = outermost_folded {
+            self.folded.remove(position);
+            return true;
+        }
+        let innermost = self
+            .regions
+            .iter()
+            .rposition(|region| region.contains(&line));
+        if let Some(index) = innermost {
+            self.folded.push(index);
+            self.folded.sort_unstable();
+            return true;
+        }
+        false
+    }
+
+    /// Unfold all blocks if any block is folded, otherwise fold all of them.
+    pub fn toggle_all(&mut self) {
+        self.folded = match self.folded.is_empty() {
+            true => (0..self.regions.len()).collect(),
+            false => Vec::new(),
+        };
+    }
+
+    /// Ranges of line numbers hidden by the folded blocks, which is every
+    /// line of a block except the one starting it.
+    pub fn hidden(&self) -> Vec<RangeInclusive<usize>> {
+        self.folded
+            .iter()
+            .map(|index| &self.regions[*index])
+            .map(|region| region.start() + 1..=*region.end())
+            .collect()
+    }
+}
+
+/// Number of whitespace characters at the start of the line, None for blank
+/// lines which don't end a block.
+fn indent(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    (!trimmed.is_empty()).then(|| line.chars().count() - trimmed.chars().count())
+}
+
+/// Line number of the code under the mouse cursor, or in the middle of the
+/// screen if the mouse isn't over the code.
+pub(crate) fn line_at_mouse(codebox: &CodeBox, scroll: &mut Scroll) -> Option<usize> {
+    let (_, ypos) = scroll.position(codebox.width_with_padding(), codebox.height_with_padding());
+    let camera = scroll.camera();
+    let mouse = camera.screen_to_world(Vec2::from(mouse_position()));
+    let center = camera.screen_to_world(vec2(screen_width() / 2., screen_height() / 2.));
+    let line_at = |y: f32| {
+        let row = (y - codebox.line_top(ypos, 0)) / codebox.line_height();
+        match row >= 0. {
+            true => codebox.lines().get(row as usize).map(|line| line.number),
+            false => None,
+        }
+    };
+    line_at(mouse.y).or_else(|| line_at(center.y))
+}
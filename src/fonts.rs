@@ -0,0 +1,54 @@
+use {
+    crate::code::{CodeError, Result},
+    macroquad::prelude::*,
+    std::sync::Arc,
+};
+
+/// Fonts to draw the characters that the code font doesn't have with, like
+/// CJK or box-drawing characters, tried in order.
+#[derive(Clone)]
+pub struct FallbackFonts {
+    code_font: Arc<fontdue::Font>,
+    fonts: Vec<(Font, Arc<fontdue::Font>)>,
+}
+
+impl FallbackFonts {
+    /// Load the fallback fonts, and the code font to check which characters
+    /// it has.
+    pub async fn load(code_font: &str, fallbacks: &[String]) -> Result<Self> {
+        let code_font = parse_font(&load_file(code_font).await?)?;
+        let mut fonts = Vec::with_capacity(fallbacks.len());
+        for path in fallbacks {
+            let bytes = load_file(path).await?;
+            let font = load_ttf_font_from_bytes(&bytes)?;
+            fonts.push((font, Arc::new(parse_font(&bytes)?)));
+        }
+        Ok(Self {
+            code_font: Arc::new(code_font),
+            fonts,
+        })
+    }
+
+    /// The first fallback font with the character, if the code font doesn't
+    /// have it.
+    pub fn font_for(&self, c: char) -> Option<&Font> {
+        if has_glyph(&self.code_font, c) {
+            return None;
+        }
+        self.fonts
+            .iter()
+            .find(|(_, glyphs)| has_glyph(glyphs, c))
+            .map(|(font, _)| font)
+    }
+}
+
+/// Parse the font to look up its glyphs, since Macroquad can't tell whether
+/// a font has a character.
+fn parse_font(bytes: &[u8]) -> Result<fontdue::Font> {
+    fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+        .map_err(|e| CodeError::Font(e.to_string()))
+}
+
+fn has_glyph(font: &fontdue::Font, c: char) -> bool {
+    c.is_whitespace() || font.lookup_glyph_index(c) != 0
+}
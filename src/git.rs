@@ -0,0 +1,166 @@
+use {
+    crate::codebox::{BlameLine, LineChange},
+    std::{
+        collections::HashMap,
+        ffi::OsStr,
+        fs,
+        io::{self, ErrorKind},
+        path::Path,
+        process::Command,
+    },
+};
+
+/// Author and commit time of each line of the file, from `git blame`.
+pub fn blame(path: &Path) -> io::Result<Vec<BlameLine>> {
+    let output = git(path, &["blame", "--line-porcelain", "--"])?;
+    let mut lines = Vec::new();
+    let mut author = String::new();
+    let mut time = 0;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            lines.push(BlameLine {
+                author: std::mem::take(&mut author),
+                time,
+            });
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author = match name {
+                "Not Committed Yet" => "uncommitted".to_string(),
+                name => name.to_string(),
+            };
+        } else if let Some(timestamp) = line.strip_prefix("author-time ") {
+            time = timestamp.parse().unwrap_or(0);
+        }
+    }
+    Ok(lines)
+}
+
+/// Lines of the file that have been added, modified or removed since the
+/// last commit, by line number. Removed lines are marked on the line above
+/// them, or on line 0 if they were at the start of the file. Every line of
+/// a file that isn't in the last commit, or of a repository without commits,
+/// has been added.
+pub fn changes(path: &Path) -> io::Result<HashMap<usize, LineChange>> {
+    // Fails outside of a repository, which isn't a file with all lines added
+    git_in_directory(path, &["rev-parse", "--git-dir"])?;
+    let has_head = git_in_directory(path, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok();
+    if !has_head
+        || git(path, &["ls-tree", "--name-only", "HEAD", "--"])?
+            .trim()
+            .is_empty()
+    {
+        let line_count = String::from_utf8_lossy(&fs::read(path)?).lines().count();
+        return Ok((1..=line_count)
+            .map(|line| (line, LineChange::Added))
+            .collect());
+    }
+    let output = git(
+        path,
+        &[
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--unified=0",
+            "HEAD",
+            "--",
+        ],
+    )?;
+    let mut changes = HashMap::new();
+    for (old_count, start, count) in output.lines().filter_map(parse_hunk_header) {
+        if count == 0 {
+            changes.insert(start, LineChange::Removed);
+            continue;
+        }
+        let change = match old_count {
+            0 => LineChange::Added,
+            _ => LineChange::Modified,
+        };
+        for line in start..start + count {
+            changes.insert(line, change);
+        }
+    }
+    Ok(changes)
+}
+
+/// Number of removed lines, and the first line and number of lines added, from
+/// a hunk header like `@@ -12,2 +12,3 @@`.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = line.strip_prefix("@@ ")?.split_whitespace();
+    let (_, old_count) = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let (start, count) = parse_range(ranges.next()?.strip_prefix('+')?)?;
+    Some((old_count, start, count))
+}
+
+/// A range like `12,3`, where the count is 1 if it is left out.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Run git in the directory of the file, with the file name as the last
+/// argument, and return what it prints.
+fn git(path: &Path, args: &[&str]) -> io::Result<String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Not a file"))?;
+    let mut args: Vec<&OsStr> = args.iter().map(OsStr::new).collect();
+    args.push(file_name);
+    run(path, &args)
+}
+
+/// Run git in the directory of the file, without the file name, and return
+/// what it prints.
+fn git_in_directory(path: &Path, args: &[&str]) -> io::Result<String> {
+    let args: Vec<&OsStr> = args.iter().map(OsStr::new).collect();
+    run(path, &args)
+}
+
+fn run(path: &Path, args: &[&OsStr]) -> io::Result<String> {
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hunk_headers() {
+        assert_eq!(parse_hunk_header("@@ -12,2 +12,3 @@"), Some((2, 12, 3)));
+        assert_eq!(
+            parse_hunk_header("@@ -12,2 +12,3 @@ fn main() {"),
+            Some((2, 12, 3))
+        );
+    }
+
+    #[test]
+    fn counts_one_line_when_the_count_is_left_out() {
+        assert_eq!(parse_hunk_header("@@ -5 +5 @@"), Some((1, 5, 1)));
+    }
+
+    #[test]
+    fn parses_removed_and_added_lines() {
+        assert_eq!(parse_hunk_header("@@ -3,2 +2,0 @@"), Some((2, 2, 0)));
+        assert_eq!(parse_hunk_header("@@ -0,0 +1,4 @@"), Some((0, 1, 4)));
+    }
+
+    #[test]
+    fn skips_other_lines() {
+        assert_eq!(parse_hunk_header("+++ b/src/main.rs"), None);
+        assert_eq!(parse_hunk_header("@@ -x +1 @@"), None);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::line_ranges::LineSlice;
+
+/// A file in a GitHub repository, parsed from a blob URL like
+/// `https://github.com/user/repo/blob/sha/path/file.rs#L10-L40`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GithubPermalink {
+    pub raw_url: String,
+    pub filename: String,
+    pub lines: Option<LineSlice>,
+}
+
+impl GithubPermalink {
+    pub fn parse(url: &str) -> Option<Self> {
+        let (url, fragment) = url.split_once('#').unwrap_or((url, ""));
+        let path = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.")
+            .strip_prefix("github.com/")?;
+        let parts: Vec<&str> = path.splitn(5, '/').collect();
+        let [user, repo, "blob", reference, file_path] = parts[..] else {
+            return None;
+        };
+        let filename = file_path.rsplit('/').next()?.to_string();
+
+        Some(Self {
+            raw_url: format!(
+                "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                user, repo, reference, file_path
+            ),
+            filename,
+            lines: parse_line_fragment(fragment),
+        })
+    }
+}
+
+/// Parse a line fragment like `L10` or `L10-L40`.
+fn parse_line_fragment(fragment: &str) -> Option<LineSlice> {
+    let (start, end) = fragment.split_once('-').unwrap_or((fragment, fragment));
+    let start = start.strip_prefix('L')?.parse().ok()?;
+    let end = end.strip_prefix('L')?.parse().ok()?;
+    Some(LineSlice {
+        start,
+        end: Some(end),
+    })
+}
@@ -0,0 +1,225 @@
+use {
+    crate::code::Code,
+    macroquad::prelude::*,
+    nanoserde::{DeJson, SerJson},
+    std::{
+        collections::HashMap,
+        sync::mpsc::{self, Receiver},
+        thread,
+    },
+};
+
+const COMPILER_EXPLORER_URL: &str = "https://godbolt.org/api/compiler";
+/// Backgrounds of the lines of source and the assembly compiled from them,
+/// cycled through by source line like on Compiler Explorer.
+const LINE_COLORS: [Color; 6] = [
+    Color::new(0.95, 0.35, 0.35, 0.18),
+    Color::new(0.35, 0.75, 0.95, 0.18),
+    Color::new(0.95, 0.8, 0.3, 0.18),
+    Color::new(0.45, 0.9, 0.45, 0.18),
+    Color::new(0.8, 0.45, 0.95, 0.18),
+    Color::new(0.95, 0.6, 0.3, 0.18),
+];
+
+/// Assembly that Compiler Explorer compiled the code to.
+pub struct Assembly {
+    pub text: String,
+    /// Line of the source that each line of assembly was compiled from.
+    source_lines: Vec<Option<usize>>,
+}
+
+impl Assembly {
+    /// Background colors of the lines of the source, by line number, for
+    /// the lines that assembly was compiled from.
+    pub fn source_backgrounds(&self) -> HashMap<usize, Color> {
+        self.source_lines
+            .iter()
+            .flatten()
+            .map(|line| (*line, line_color(*line)))
+            .collect()
+    }
+
+    /// Background colors of the lines of assembly, by line number, matching
+    /// the lines of the source they were compiled from.
+    pub fn assembly_backgrounds(&self) -> HashMap<usize, Color> {
+        self.source_lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| line.map(|line| (index + 1, line_color(line))))
+            .collect()
+    }
+}
+
+fn line_color(source_line: usize) -> Color {
+    LINE_COLORS[source_line % LINE_COLORS.len()]
+}
+
+/// Compiler id on Compiler Explorer and the compiler flags used for the
+/// language, optimized like in a release build.
+pub fn default_compiler(language: Option<&str>) -> Option<(&'static str, &'static str)> {
+    match language?.to_lowercase().as_str() {
+        "rust" | "rs" => Some(("nightly", "-C opt-level=3")),
+        "cpp" | "c++" => Some(("clang_trunk", "-O2")),
+        "c" => Some(("cclang_trunk", "-O2")),
+        "go" => Some(("gltip", "")),
+        "zig" => Some(("ztrunk", "-O ReleaseFast")),
+        _ => None,
+    }
+}
+
+/// Compiles code on Compiler Explorer in the background.
+pub struct CompilerExplorer {
+    /// Compiler id to use, empty to choose one for the language.
+    compiler: String,
+    receiver: Option<Receiver<Result<Assembly, String>>>,
+}
+
+impl CompilerExplorer {
+    pub fn new(compiler: String) -> Self {
+        Self {
+            compiler,
+            receiver: None,
+        }
+    }
+
+    /// Start compiling the code. The result of a compilation that is still
+    /// in progress is ignored.
+    pub fn start(&mut self, code: &Code, language: Option<&str>) {
+        let (sender, receiver) = mpsc::channel();
+        self.receiver = Some(receiver);
+        let (compiler, flags) = match (self.compiler.is_empty(), default_compiler(language)) {
+            (false, default) => (
+                self.compiler.clone(),
+                default.map(|(_, flags)| flags).unwrap_or_default(),
+            ),
+            (true, Some((compiler, flags))) => (compiler.to_string(), flags),
+            (true, None) => {
+                let _ = sender.send(Err(format!(
+                    "No compiler for {}, give one to --godbolt",
+                    language.unwrap_or("the code")
+                )));
+                return;
+            }
+        };
+        let request = CompileRequest {
+            source: code.sourcecode.clone(),
+            options: CompileOptions {
+                user_arguments: flags.to_string(),
+                filters: Filters::default(),
+            },
+        };
+        thread::spawn(move || {
+            let _ = sender.send(compile(&compiler, &request));
+        });
+    }
+
+    /// The assembly, or the error message, once the compilation is done.
+    pub fn output(&mut self) -> Option<Result<Assembly, String>> {
+        let output = self.receiver.as_ref()?.try_recv().ok()?;
+        self.receiver = None;
+        Some(output)
+    }
+}
+
+#[derive(SerJson)]
+struct CompileRequest {
+    source: String,
+    options: CompileOptions,
+}
+
+#[derive(SerJson)]
+struct CompileOptions {
+    #[nserde(rename = "userArguments")]
+    user_arguments: String,
+    filters: Filters,
+}
+
+/// Leave out everything but the instructions and labels of the code.
+#[derive(SerJson)]
+struct Filters {
+    intel: bool,
+    directives: bool,
+    #[nserde(rename = "commentOnly")]
+    comment_only: bool,
+    labels: bool,
+    demangle: bool,
+    #[nserde(rename = "libraryCode")]
+    library_code: bool,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self {
+            intel: true,
+            directives: true,
+            comment_only: true,
+            labels: true,
+            demangle: true,
+            library_code: true,
+        }
+    }
+}
+
+#[derive(DeJson)]
+struct CompileResponse {
+    #[nserde(default)]
+    code: i32,
+    #[nserde(default)]
+    asm: Vec<AsmLine>,
+    #[nserde(default)]
+    stderr: Vec<OutputLine>,
+}
+
+#[derive(DeJson)]
+struct AsmLine {
+    text: String,
+    #[nserde(default)]
+    source: Option<AsmSource>,
+}
+
+#[derive(DeJson)]
+struct AsmSource {
+    #[nserde(default)]
+    line: Option<usize>,
+    /// Set for lines compiled from other files, like the standard library.
+    #[nserde(default)]
+    file: Option<String>,
+}
+
+#[derive(DeJson)]
+struct OutputLine {
+    text: String,
+}
+
+fn compile(compiler: &str, request: &CompileRequest) -> Result<Assembly, String> {
+    let json = ureq::post(&format!("{}/{}/compile", COMPILER_EXPLORER_URL, compiler))
+        .set("Content-Type", "application/json")
+        .set("Accept", "application/json")
+        .send_string(&request.serialize_json())
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    let response = CompileResponse::deserialize_json(&json).map_err(|e| e.to_string())?;
+    if response.code != 0 {
+        let errors: Vec<String> = response.stderr.into_iter().map(|line| line.text).collect();
+        return Err(errors.join("\n"));
+    }
+    Ok(Assembly {
+        text: response
+            .asm
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n"),
+        source_lines: response
+            .asm
+            .iter()
+            .map(|line| {
+                line.source
+                    .as_ref()
+                    .filter(|source| source.file.is_none())
+                    .and_then(|source| source.line)
+            })
+            .collect(),
+    })
+}
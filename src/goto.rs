@@ -0,0 +1,89 @@
+use {
+    crate::{codebox::CodeBox, line_ranges::LinePosition, scroll::Scroll},
+    macroquad::prelude::*,
+};
+
+/// Seconds that the line jumped to is highlighted, fading out.
+const FLASH_SECONDS: f64 = 1.5;
+const LINE_COLOR: Color = Color::new(1., 0.85, 0.2, 0.35);
+const COLUMN_COLOR: Color = Color::new(1., 0.6, 0.1, 0.7);
+
+/// A position in the code box that the view jumps to, which is briefly
+/// flashed to draw attention to it.
+#[derive(Debug)]
+pub struct Goto {
+    /// Index of the row in the code box, after wrapping lines.
+    row: usize,
+    /// Column on the row, if a column was given.
+    column: Option<usize>,
+    start_time: f64,
+}
+
+impl Goto {
+    /// Find the row and column of the position in the code box. Returns None
+    /// if the line isn't displayed.
+    pub fn new(codebox: &CodeBox, position: LinePosition) -> Option<Self> {
+        let lines = codebox.lines();
+        let mut row = lines
+            .iter()
+            .position(|line| line.number == position.line && !line.continuation)?;
+        // Columns of wrapped lines continue on the following rows
+        let column = position.column.map(|column| {
+            let mut column = column - 1;
+            while column >= lines[row].char_count()
+                && lines.get(row + 1).map(|next| next.continuation) == Some(true)
+            {
+                column -= lines[row].char_count();
+                row += 1;
+            }
+            column
+        });
+        Some(Self {
+            row,
+            column,
+            start_time: get_time(),
+        })
+    }
+
+    /// Scroll so that the position is in the middle of the screen.
+    pub fn scroll_to(&self, codebox: &CodeBox, scroll: &mut Scroll) {
+        scroll.center_on(codebox.line_top(0., self.row));
+    }
+
+    /// Returns true while the flash is still visible.
+    pub fn is_flashing(&self) -> bool {
+        get_time() - self.start_time < FLASH_SECONDS
+    }
+
+    /// Draw the fading highlight of the row and column, on top of the code
+    /// box drawn at `xpos`, `ypos`.
+    pub fn draw(&self, codebox: &CodeBox, xpos: f32, ypos: f32) {
+        let fade = (1. - (get_time() - self.start_time) / FLASH_SECONDS).max(0.) as f32;
+        let line = &codebox.lines()[self.row];
+        let top = codebox.line_top(ypos, self.row);
+        let left = codebox.column_x(xpos, line, 0);
+        draw_rectangle(
+            left,
+            top,
+            xpos + codebox.width_with_padding() - codebox.padding() - left,
+            codebox.line_height(),
+            Color {
+                a: LINE_COLOR.a * fade,
+                ..LINE_COLOR
+            },
+        );
+        if let Some(column) = self.column {
+            let x = codebox.column_x(xpos, line, column);
+            draw_rectangle(
+                x,
+                top,
+                codebox.column_x(xpos, line, column + 1) - x,
+                codebox.line_height(),
+                Color {
+                    a: COLUMN_COLOR.a * fade,
+                    ..COLUMN_COLOR
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    crate::{
+        codebox::{CodeBox, WRAP_INDENT, WRAP_MARKER},
+        export::escape_xml,
+    },
+    macroquad::prelude::*,
+    std::fmt::Write,
+    syntect::highlighting::FontStyle,
+};
+
+/// Render the highlighted code as a self-contained HTML page, with the
+/// colors and sizes of the code box in inline CSS.
+pub fn render_html(codebox: &CodeBox) -> String {
+    let padding = codebox.padding();
+    let gutter_width = codebox.gutter_width();
+    let title = codebox
+        .header()
+        .map(|header| header.title.as_str())
+        .filter(|title| !title.is_empty())
+        .unwrap_or("Code");
+    let mut html = String::new();
+
+    let _ = writeln!(
+        html,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+.rusty-code {{ display: inline-block; overflow: hidden; background: {}; border-radius: {}px;{} }}
+.rusty-code pre {{ margin: 0; padding: {padding}px 0; font-family: "{}", monospace; font-size: {}px; line-height: {}px; background: linear-gradient(to right, {} {}px, transparent {}px); }}
+.rusty-code .header {{ position: relative; height: {}px; line-height: {}px; padding: 0 {padding}px; font-family: "{}", monospace; font-size: {}px; background: {}; color: {}; white-space: pre; }}
+.rusty-code .controls {{ position: absolute; left: {padding}px; top: 0; }}
+.rusty-code .controls span {{ display: inline-block; border-radius: 50%; vertical-align: middle; }}
+.rusty-code .line {{ display: flex; }}
+.rusty-code .gutter {{ flex: none; width: {}px; padding-left: {padding}px; color: {}; user-select: none; }}
+.rusty-code .code {{ flex: 1; padding-right: {padding}px; }}
+.rusty-code .wrap {{ color: {}; user-select: none; }}
+</style>
+</head>
+<body>
+<div class="rusty-code">"#,
+        escape_xml(title),
+        css_color(codebox.background_color()),
+        codebox.corners(true, true, true, true)[0],
+        codebox
+            .shadow()
+            .map(|shadow| format!(
+                " box-shadow: {}px {}px {}px {};",
+                shadow.offset.x,
+                shadow.offset.y,
+                shadow.blur,
+                css_color(shadow.color)
+            ))
+            .unwrap_or_default(),
+        escape_xml(codebox.font_family()),
+        codebox.font_size(),
+        codebox.line_height(),
+        codebox
+            .gutter()
+            .map(|gutter| css_color(gutter.background_color))
+            .unwrap_or_else(|| "transparent".to_string()),
+        padding + gutter_width,
+        padding + gutter_width,
+        codebox.header().map(|header| header.height).unwrap_or(0.),
+        codebox.header().map(|header| header.height).unwrap_or(0.),
+        escape_xml(codebox.font_family()),
+        codebox.font_size(),
+        codebox
+            .header()
+            .map(|header| css_color(header.background_color))
+            .unwrap_or_default(),
+        codebox
+            .header()
+            .map(|header| css_color(header.text_color))
+            .unwrap_or_default(),
+        gutter_width,
+        codebox
+            .gutter()
+            .map(|gutter| css_color(gutter.text_color))
+            .unwrap_or_default(),
+        css_color(codebox.wrap_marker_color()),
+    );
+
+    if let Some(header) = codebox.header() {
+        let controls = codebox.window_controls(0., 0.);
+        let title_indent = codebox.header_title_x(0.) - padding;
+        html.push_str(r#"<div class="header">"#);
+        if !controls.is_empty() {
+            html.push_str(r#"<span class="controls">"#);
+            for (center, radius, color) in controls.iter() {
+                let _ = write!(
+                    html,
+                    r#"<span style="position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; background: {};"></span>"#,
+                    center.x - radius - padding,
+                    center.y - radius,
+                    radius * 2.,
+                    radius * 2.,
+                    css_color(*color)
+                );
+            }
+            html.push_str("</span>");
+        }
+        let _ = write!(html, r#"<span style="padding-left: {}px;">"#, title_indent);
+        if let Some(icon) = &header.icon {
+            let _ = write!(
+                html,
+                r#"<span style="display: inline-block; width: {}px; font-family: '{}';">{}</span>"#,
+                codebox.header_icon_width(),
+                escape_xml(&icon.family),
+                icon.glyph
+            );
+        }
+        let _ = writeln!(html, "{}</span></div>", escape_xml(&header.title));
+    }
+
+    html.push_str("<pre>");
+    for line in codebox.lines() {
+        html.push_str(r#"<div class="line">"#);
+        if let Some(gutter) = codebox.gutter() {
+            let number = match line.continuation {
+                true => String::new(),
+                false => format!("{:>width$}", line.number, width = gutter.digits),
+            };
+            let _ = write!(html, r#"<span class="gutter">{}</span>"#, number);
+        }
+        match codebox.line_background(line) {
+            Some(color) => {
+                let _ = write!(
+                    html,
+                    r#"<span class="code" style="background: {};">"#,
+                    css_color(color)
+                );
+            }
+            None => html.push_str(r#"<span class="code">"#),
+        }
+        if line.continuation {
+            let _ = write!(
+                html,
+                r#"<span class="wrap">{:<width$}</span>"#,
+                WRAP_MARKER,
+                width = WRAP_INDENT
+            );
+        }
+        for span in line.spans.iter() {
+            let _ = write!(
+                html,
+                r#"<span style="color: {};{}">{}</span>"#,
+                css_color(span.color),
+                css_font_style(span.style),
+                escape_xml(&span.text)
+            );
+        }
+        // An empty line would collapse without any content
+        if line.spans.iter().all(|span| span.text.is_empty()) && !line.continuation {
+            html.push(' ');
+        }
+        html.push_str("</span></div>");
+    }
+    html.push_str("</pre>\n</div>\n</body>\n</html>\n");
+    html
+}
+
+/// CSS declarations for the bold, italic and underlined parts of the style.
+fn css_font_style(style: FontStyle) -> String {
+    let mut css = String::new();
+    if style.contains(FontStyle::BOLD) {
+        css.push_str(" font-weight: bold;");
+    }
+    if style.contains(FontStyle::ITALIC) {
+        css.push_str(" font-style: italic;");
+    }
+    if style.contains(FontStyle::UNDERLINE) {
+        css.push_str(" text-decoration: underline;");
+    }
+    css
+}
+
+/// Convert a Color into a CSS color, keeping the transparency.
+fn css_color(color: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = color.into();
+    format!("rgba({}, {}, {}, {:.3})", r, g, b, color.a)
+}
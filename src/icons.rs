@@ -0,0 +1,68 @@
+use {macroquad::prelude::*, std::collections::HashMap};
+
+/// Nerd Font glyph for files of languages without an icon of their own.
+const DEFAULT_ICON: char = '\u{f15c}';
+
+/// Icon drawn before the filename in the header of the code box.
+#[derive(Clone)]
+pub struct FileIcon {
+    pub glyph: char,
+    pub font: Font,
+    /// Name of the icon font, for exports that draw the glyph with it.
+    pub family: String,
+}
+
+/// Nerd Font glyph for the language, from the icons of the theme or the
+/// devicons bundled with Nerd Fonts. The theme maps language names to the
+/// glyph to use, which may be any character in its icon font.
+pub fn glyph(language: Option<&str>, theme_icons: &HashMap<String, String>) -> char {
+    let names = names(&language.unwrap_or_default().to_lowercase());
+    names
+        .iter()
+        .find_map(|name| theme_icons.get(name).and_then(|glyph| glyph.chars().next()))
+        .or_else(|| names.iter().find_map(|name| devicon(name)))
+        .unwrap_or(DEFAULT_ICON)
+}
+
+/// Names to look the language up by. Syntect names languages like
+/// `Bourne Again Shell (bash)` or `JavaScript (Babel)`, so the name in
+/// parentheses, the name before them and the first word are tried after the
+/// whole name.
+fn names(language: &str) -> Vec<String> {
+    let mut names = vec![language.to_string()];
+    if let Some((before, after)) = language.split_once('(') {
+        names.push(after.trim_end_matches(')').trim().to_string());
+        names.push(before.trim().to_string());
+    }
+    if let Some(first) = language.split_whitespace().next() {
+        names.push(first.to_string());
+    }
+    names
+}
+
+fn devicon(language: &str) -> Option<char> {
+    let glyph = match language {
+        "rust" | "rs" => '\u{e7a8}',
+        "python" | "py" => '\u{e73c}',
+        "javascript" | "js" => '\u{e74e}',
+        "typescript" | "ts" => '\u{e628}',
+        "go" => '\u{e627}',
+        "c" => '\u{e61e}',
+        "cpp" | "c++" => '\u{e61d}',
+        "java" => '\u{e738}',
+        "ruby" | "rb" => '\u{e739}',
+        "php" => '\u{e73d}',
+        "swift" => '\u{e755}',
+        "haskell" | "hs" => '\u{e777}',
+        "lua" => '\u{e620}',
+        "html" => '\u{e736}',
+        "css" => '\u{e749}',
+        "json" => '\u{e60b}',
+        "markdown" | "md" => '\u{e73e}',
+        "shell" | "bash" | "sh" | "zsh" => '\u{e795}',
+        "dockerfile" | "docker" => '\u{e7b0}',
+        "diff" => '\u{f440}',
+        _ => return None,
+    };
+    Some(glyph)
+}
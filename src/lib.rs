@@ -0,0 +1,79 @@
+//! Display source code with syntax highlighting in Macroquad.
+//!
+//! The `rusty-code` binary is a command line wrapper around this library.
+//! Load code with [`Code::load`] and draw it with a [`CodeRenderer`] to show
+//! code in another Macroquad app, or run the whole app with extra overlays
+//! with [`app::run`].
+
+pub(crate) mod animation;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod ansi;
+pub mod app;
+pub(crate) mod audio;
+pub(crate) mod auto_advance;
+pub(crate) mod background;
+pub(crate) mod base16;
+pub mod code;
+pub mod code_theme;
+pub mod codebox;
+pub mod colorblind;
+pub(crate) mod compare;
+pub(crate) mod contrast;
+pub(crate) mod detect;
+pub(crate) mod diagnostics;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod export;
+pub mod folding;
+pub mod fonts;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod git;
+pub(crate) mod github;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod godbolt;
+pub(crate) mod goto;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod html;
+pub mod icons;
+pub(crate) mod keys;
+pub mod line_ranges;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod lsp;
+pub(crate) mod minimap;
+pub(crate) mod notes;
+pub mod overlay;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod pdf;
+pub(crate) mod picker;
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod post_message;
+pub(crate) mod progress;
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod reduced_motion;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod remote;
+pub mod renderer;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod run;
+#[cfg(feature = "scripting")]
+pub(crate) mod script;
+pub(crate) mod scroll;
+pub(crate) mod search;
+pub(crate) mod selection;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod server;
+pub(crate) mod shaping;
+pub(crate) mod slides;
+pub(crate) mod tabs;
+pub(crate) mod timer;
+pub(crate) mod toast;
+pub(crate) mod transition;
+#[cfg(feature = "tree-sitter")]
+pub(crate) mod treesitter;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod watcher;
+pub(crate) mod watermark;
+
+pub use {
+    code::{Code, CodeError, CodeSource, Gist, Result},
+    renderer::CodeRenderer,
+};
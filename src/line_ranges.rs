@@ -0,0 +1,114 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+/// A list of line numbers and line ranges, parsed from a string like `3,7-12`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineRanges(Vec<RangeInclusive<usize>>);
+
+impl LineRanges {
+    pub fn contains(&self, line: usize) -> bool {
+        self.0.iter().any(|range| range.contains(&line))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromStr for LineRanges {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let (start, end) = part.split_once('-').unwrap_or((part, part));
+                let start = parse_line_number(start)?;
+                let end = parse_line_number(end)?;
+                match start <= end {
+                    true => Ok(start..=end),
+                    false => Err(format!("Invalid line range: {}", part)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(LineRanges)
+    }
+}
+
+fn parse_line_number(number: &str) -> Result<usize, String> {
+    number
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| format!("Invalid line number: {}", number))
+}
+
+/// A slice of lines to display, parsed from a string like `40:80`. Either
+/// end can be left out to start from the first line or end at the last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineSlice {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl LineSlice {
+    pub fn contains(&self, line: usize) -> bool {
+        line >= self.start && self.end.map(|end| line <= end).unwrap_or(true)
+    }
+}
+
+impl FromStr for LineSlice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid line slice, expected START:END: {}", s))?;
+        let start = match start.trim() {
+            "" => 1,
+            start => parse_line_number(start)?,
+        };
+        let end = match end.trim() {
+            "" => None,
+            end => Some(parse_line_number(end)?),
+        };
+        match end.map(|end| start <= end).unwrap_or(true) {
+            true => Ok(LineSlice { start, end }),
+            false => Err(format!("Invalid line slice: {}", s)),
+        }
+    }
+}
+
+/// A line, and optionally a column on it, parsed from a string like `120`
+/// or `120:8`. Both start at 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinePosition {
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
+impl FromStr for LinePosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (line, column) = match s.split_once(':') {
+            Some((line, column)) => (line, Some(column)),
+            None => (s, None),
+        };
+        let line = parse_line_number(line)?;
+        let column = column
+            .map(|column| {
+                column
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid column: {}", column))
+            })
+            .transpose()?;
+        match line > 0 && column != Some(0) {
+            true => Ok(LinePosition { line, column }),
+            false => Err(format!(
+                "Invalid position, lines and columns start at 1: {}",
+                s
+            )),
+        }
+    }
+}
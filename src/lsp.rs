@@ -0,0 +1,485 @@
+use {
+    crate::codebox::SemanticToken,
+    nanoserde::{DeJson, SerJson},
+    std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        path::Path,
+        process::{Child, ChildStdin, Command, Stdio},
+        sync::mpsc::{self, Receiver, Sender},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// How long to wait for the language server to analyze the file.
+const LSP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time between requests for semantic tokens while the server is still
+/// loading the project.
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+/// Files found at the root of a project.
+const PROJECT_MARKERS: &[&str] = &[
+    ".git",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
+
+/// Semantic token types of the LSP specification, with the TextMate scope
+/// used to look up their color in the syntax highlighting theme. Types that
+/// aren't listed keep their syntactic colors.
+const TOKEN_SCOPES: &[(&str, &str)] = &[
+    ("namespace", "entity.name.namespace"),
+    ("type", "entity.name.type"),
+    ("class", "entity.name.class"),
+    ("enum", "entity.name.enum"),
+    ("interface", "entity.name.interface"),
+    ("struct", "entity.name.struct"),
+    ("typeParameter", "entity.name.type"),
+    ("parameter", "variable.parameter"),
+    ("variable", "variable"),
+    ("property", "variable.other.member"),
+    ("enumMember", "constant.other"),
+    ("function", "entity.name.function"),
+    ("method", "entity.name.function"),
+    ("macro", "support.function"),
+    ("decorator", "entity.name.function"),
+    ("builtinType", "storage.type"),
+    ("lifetime", "storage.modifier.lifetime"),
+    ("selfKeyword", "variable.language"),
+];
+
+#[derive(DeJson)]
+struct Envelope {
+    #[nserde(default)]
+    id: Option<u64>,
+    #[nserde(default)]
+    error: Option<ResponseError>,
+}
+
+#[derive(DeJson)]
+struct ResponseError {
+    message: String,
+}
+
+#[derive(DeJson)]
+struct InitializeResponse {
+    result: InitializeResult,
+}
+
+#[derive(DeJson)]
+struct InitializeResult {
+    capabilities: ServerCapabilities,
+}
+
+#[derive(DeJson)]
+struct ServerCapabilities {
+    #[nserde(rename = "semanticTokensProvider")]
+    #[nserde(default)]
+    semantic_tokens_provider: Option<SemanticTokensOptions>,
+}
+
+#[derive(DeJson)]
+struct SemanticTokensOptions {
+    legend: SemanticTokensLegend,
+}
+
+#[derive(DeJson)]
+struct SemanticTokensLegend {
+    #[nserde(rename = "tokenTypes")]
+    token_types: Vec<String>,
+}
+
+#[derive(DeJson)]
+struct SemanticTokensResponse {
+    #[nserde(default)]
+    result: Option<SemanticTokensResult>,
+}
+
+#[derive(DeJson)]
+struct SemanticTokensResult {
+    data: Vec<usize>,
+}
+
+/// Semantic tokens of code, or why the language server couldn't give them.
+pub struct TokensResponse {
+    /// File the code was loaded from.
+    pub filename: String,
+    /// The code the tokens were asked for, which may have been reloaded since.
+    pub sourcecode: String,
+    pub tokens: io::Result<Vec<SemanticToken>>,
+}
+
+/// Asks a language server for semantic tokens on background threads, so
+/// that the window isn't blocked while the server loads the project.
+pub struct SemanticTokensClient {
+    command: String,
+    sender: Sender<TokensResponse>,
+    receiver: Receiver<TokensResponse>,
+}
+
+impl SemanticTokensClient {
+    pub fn new(command: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            command,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Start a language server for the file and ask it for the semantic
+    /// tokens of the code.
+    pub fn request(&self, filename: String, sourcecode: String, language: String) {
+        let command = self.command.clone();
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let tokens = semantic_tokens(&command, Path::new(&filename), &sourcecode, &language);
+            let _ = sender.send(TokensResponse {
+                filename,
+                sourcecode,
+                tokens,
+            });
+        });
+    }
+
+    /// Responses received since the last call.
+    pub fn responses(&self) -> Vec<TokensResponse> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Wait for the next response, for exports that can't be drawn without
+    /// the tokens.
+    pub fn wait(&self) -> Option<TokensResponse> {
+        self.receiver.recv_timeout(LSP_TIMEOUT).ok()
+    }
+}
+
+/// Launch the language server `command` for the file and ask it for the
+/// semantic tokens of the code.
+pub fn semantic_tokens(
+    command: &str,
+    path: &Path,
+    code: &str,
+    language: &str,
+) -> io::Result<Vec<SemanticToken>> {
+    let mut client = Client::start(command)?;
+    let result = client.semantic_tokens(path, code, language);
+    client.stop();
+    result
+}
+
+/// A language server speaking JSON-RPC over its stdin and stdout.
+struct Client {
+    process: Child,
+    stdin: ChildStdin,
+    messages: Receiver<String>,
+    next_id: u64,
+    deadline: Instant,
+}
+
+impl Client {
+    fn start(command: &str) -> io::Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| error("No language server given"))?;
+        let mut process = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = process.stdin.take().ok_or_else(|| error("No stdin"))?;
+        let stdout = process.stdout.take().ok_or_else(|| error("No stdout"))?;
+
+        // Reading blocks, so it is done on a thread to be able to give up
+        // on a server that doesn't answer.
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(message) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            process,
+            stdin,
+            messages,
+            next_id: 1,
+            deadline: Instant::now() + LSP_TIMEOUT,
+        })
+    }
+
+    fn semantic_tokens(
+        &mut self,
+        path: &Path,
+        code: &str,
+        language: &str,
+    ) -> io::Result<Vec<SemanticToken>> {
+        let path = path.canonicalize()?;
+        let uri = file_uri(&path);
+        let root = project_root(&path);
+
+        let response = self.request(
+            "initialize",
+            &format!(
+                r#"{{"processId":{},"rootUri":{},"capabilities":{{"textDocument":{{"semanticTokens":{{"requests":{{"full":true}},"tokenTypes":[],"tokenModifiers":[],"formats":["relative"]}}}}}}}}"#,
+                std::process::id(),
+                json_string(&file_uri(root))
+            ),
+        )?;
+        let token_types = InitializeResponse::deserialize_json(&response)
+            .map_err(|e| error(&e.to_string()))?
+            .result
+            .capabilities
+            .semantic_tokens_provider
+            .ok_or_else(|| error("The language server doesn't provide semantic tokens"))?
+            .legend
+            .token_types;
+        self.notify("initialized", "{}")?;
+        self.notify(
+            "textDocument/didOpen",
+            &format!(
+                r#"{{"textDocument":{{"uri":{},"languageId":{},"version":1,"text":{}}}}}"#,
+                json_string(&uri),
+                json_string(language),
+                json_string(code)
+            ),
+        )?;
+
+        // Servers like rust-analyzer answer with an error or without a
+        // result until they have loaded the project. Code without tokens has
+        // an empty result.
+        let params = format!(r#"{{"textDocument":{{"uri":{}}}}}"#, json_string(&uri));
+        loop {
+            if let Ok(response) = self.request("textDocument/semanticTokens/full", &params) {
+                let result = SemanticTokensResponse::deserialize_json(&response)
+                    .ok()
+                    .and_then(|response| response.result);
+                if let Some(result) = result {
+                    return Ok(decode_tokens(&result.data, &token_types, code));
+                }
+            }
+            if Instant::now() + RETRY_INTERVAL > self.deadline {
+                return Err(error("Timed out waiting for semantic tokens"));
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+
+    /// Send a request and wait for the response with the same id.
+    fn request(&mut self, method: &str, params: &str) -> io::Result<String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+            id, method, params
+        ))?;
+        loop {
+            let timeout = self.deadline.saturating_duration_since(Instant::now());
+            let message = self
+                .messages
+                .recv_timeout(timeout)
+                .map_err(|_| error("The language server didn't answer"))?;
+            // Notifications and requests from the server are ignored
+            let Ok(envelope) = Envelope::deserialize_json(&message) else {
+                continue;
+            };
+            if envelope.id == Some(id) {
+                return match envelope.error {
+                    Some(e) => Err(error(&e.message)),
+                    None => Ok(message),
+                };
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: &str) -> io::Result<()> {
+        self.send(&format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#,
+            method, params
+        ))
+    }
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )?;
+        self.stdin.flush()
+    }
+
+    /// Ask the server to shut down, and kill it if it doesn't exit by itself.
+    fn stop(mut self) {
+        self.deadline = Instant::now() + Duration::from_secs(1);
+        let _ = self.request("shutdown", "null");
+        let _ = self.notify("exit", "null");
+        thread::sleep(Duration::from_millis(100));
+        if !matches!(self.process.try_wait(), Ok(Some(_))) {
+            let _ = self.process.kill();
+        }
+        let _ = self.process.wait();
+    }
+}
+
+/// Read one message, which is a JSON body after a `Content-Length` header.
+fn read_message(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length.ok_or_else(|| error("Missing Content-Length"))?];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body).map_err(|e| error(&e.to_string()))
+}
+
+/// Decode the tokens, which are given as groups of five numbers: the line
+/// relative to the previous token, the start relative to the previous token
+/// on the same line, the length, the token type and its modifiers. Positions
+/// are counted in UTF-16 code units, and are converted to characters.
+fn decode_tokens(data: &[usize], token_types: &[String], code: &str) -> Vec<SemanticToken> {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut line = 0;
+    let mut start = 0;
+    let mut tokens = Vec::new();
+    for token in data.chunks_exact(5) {
+        let [delta_line, delta_start, length, token_type, _modifiers] =
+            [token[0], token[1], token[2], token[3], token[4]];
+        if delta_line > 0 {
+            line += delta_line;
+            start = 0;
+        }
+        start += delta_start;
+        let scope = token_types
+            .get(token_type)
+            .and_then(|name| TOKEN_SCOPES.iter().find(|(token, _)| token == name))
+            .map(|(_, scope)| scope.to_string());
+        let (Some(scope), Some(text)) = (scope, lines.get(line)) else {
+            continue;
+        };
+        let start_char = utf16_to_char_index(text, start);
+        tokens.push(SemanticToken {
+            line,
+            start: start_char,
+            length: utf16_to_char_index(text, start + length) - start_char,
+            scope,
+        });
+    }
+    tokens
+}
+
+fn utf16_to_char_index(text: &str, utf16_index: usize) -> usize {
+    let mut units = 0;
+    for (index, c) in text.chars().enumerate() {
+        if units >= utf16_index {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.chars().count()
+}
+
+/// The closest directory above the file with a version control or build
+/// file, or the directory of the file.
+fn project_root(path: &Path) -> &Path {
+    let directory = path.parent().unwrap_or(path);
+    directory
+        .ancestors()
+        .find(|dir| {
+            PROJECT_MARKERS
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+        })
+        .unwrap_or(directory)
+}
+
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded: String = path
+        .chars()
+        .map(|c| match c {
+            ' ' => "%20".to_string(),
+            '#' => "%23".to_string(),
+            '%' => "%25".to_string(),
+            '?' => "%3F".to_string(),
+            _ => c.to_string(),
+        })
+        .collect();
+    match encoded.starts_with('/') {
+        true => format!("file://{}", encoded),
+        false => format!("file:///{}", encoded),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    text.to_string().serialize_json()
+}
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[usize], code: &str) -> Vec<(usize, usize, usize, String)> {
+        let token_types = ["variable".to_string(), "function".to_string()];
+        decode_tokens(data, &token_types, code)
+            .into_iter()
+            .map(|token| (token.line, token.start, token.length, token.scope))
+            .collect()
+    }
+
+    #[test]
+    fn counts_characters_outside_the_basic_plane_once() {
+        // The crab takes two UTF-16 code units
+        let code = "let c = \"🦀\"; call(c);";
+        assert_eq!(utf16_to_char_index(code, 11), 10);
+        assert_eq!(
+            decode(&[0, 14, 4, 1, 0, 0, 5, 1, 0, 0], code),
+            [
+                (0, 13, 4, "entity.name.function".to_string()),
+                (0, 18, 1, "variable".to_string()),
+            ]
+        );
+        assert_eq!(
+            decode(&[0, 8, 4, 0, 0], code),
+            [(0, 8, 3, "variable".to_string())]
+        );
+    }
+
+    #[test]
+    fn decodes_positions_relative_to_the_previous_token() {
+        let code = "fn main() {\n    let x = 1;\n\n    print(x);\n}";
+        assert_eq!(
+            decode(
+                &[1, 8, 1, 0, 0, 2, 4, 5, 1, 0, 0, 6, 1, 0, 0, 1, 0, 1, 7, 0],
+                code
+            ),
+            [
+                (1, 8, 1, "variable".to_string()),
+                (3, 4, 5, "entity.name.function".to_string()),
+                (3, 10, 1, "variable".to_string()),
+            ]
+        );
+    }
+}
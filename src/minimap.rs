@@ -0,0 +1,86 @@
+use {
+    crate::{
+        codebox::{text_columns, CodeBox},
+        scroll::{Scroll, SCROLL_MARGIN},
+    },
+    macroquad::prelude::*,
+};
+
+const MINIMAP_WIDTH: f32 = 120.;
+/// Height of each line in the minimap, unless the file is too long to fit.
+const MAX_ROW_HEIGHT: f32 = 3.;
+const BACKGROUND_COLOR: Color = Color::new(0., 0., 0., 0.4);
+const VIEWPORT_COLOR: Color = Color::new(1., 1., 1., 0.15);
+
+/// Area of the screen the minimap is drawn in, and the height of each line.
+fn bounds(codebox: &CodeBox) -> (Rect, f32) {
+    let area = Rect::new(
+        screen_width() - MINIMAP_WIDTH - SCROLL_MARGIN,
+        SCROLL_MARGIN,
+        MINIMAP_WIDTH,
+        screen_height() - SCROLL_MARGIN * 2.,
+    );
+    let rows = codebox.lines().len().max(1) as f32;
+    let row_height = (area.h / rows).min(MAX_ROW_HEIGHT);
+    (
+        Rect::new(area.x, area.y, area.w, row_height * rows),
+        row_height,
+    )
+}
+
+/// Scroll to the part of the code that is clicked in the minimap. Returns
+/// true if the mouse is over the minimap.
+pub fn handle_mouse(codebox: &CodeBox, scroll: &mut Scroll) -> bool {
+    let (area, row_height) = bounds(codebox);
+    let (mouse_x, mouse_y) = mouse_position();
+    if !area.contains(vec2(mouse_x, mouse_y)) {
+        return false;
+    }
+    if is_mouse_button_down(MouseButton::Left) {
+        let row = (mouse_y - area.y) / row_height;
+        scroll.center_on(codebox.line_top(0., 0) + row * codebox.line_height());
+    }
+    true
+}
+
+/// Draw a scaled down view of all lines of the code on the right edge of
+/// the screen, with the part that is visible on screen marked.
+pub fn draw(codebox: &CodeBox, scroll: &Scroll, ypos: f32) {
+    let (area, row_height) = bounds(codebox);
+    draw_rectangle(area.x, area.y, area.w, area.h, BACKGROUND_COLOR);
+
+    let columns = codebox
+        .lines()
+        .iter()
+        .map(|line| line.width())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let char_width = (area.w / columns as f32).min(1.5);
+    for (index, line) in codebox.lines().iter().enumerate() {
+        let y = area.y + index as f32 * row_height;
+        let mut column = 0;
+        for span in line.spans.iter() {
+            let length = text_columns(&span.text);
+            let indent = span.text.chars().take_while(|c| c.is_whitespace()).count();
+            let text_length = text_columns(span.text.trim());
+            if text_length > 0 {
+                draw_rectangle(
+                    area.x + (column + indent) as f32 * char_width,
+                    y,
+                    text_length as f32 * char_width,
+                    (row_height * 0.8).max(1.),
+                    span.color,
+                );
+            }
+            column += length;
+        }
+    }
+
+    // The visible part of the code box, converted to rows of the minimap
+    let visible_top = -codebox.line_top(ypos, 0) / codebox.line_height();
+    let visible_rows = screen_height() / scroll.zoom / codebox.line_height();
+    let top = area.y + visible_top.max(0.) * row_height;
+    let bottom = (area.y + (visible_top + visible_rows) * row_height).min(area.bottom());
+    draw_rectangle(area.x, top, area.w, (bottom - top).max(1.), VIEWPORT_COLOR);
+}
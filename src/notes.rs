@@ -0,0 +1,145 @@
+use macroquad::prelude::*;
+
+const FONT_SIZE: f32 = 24.;
+const NOTES_PADDING: f32 = 20.;
+/// Largest part of the screen height covered by the notes.
+const MAX_HEIGHT: f32 = 0.4;
+const BACKGROUND_COLOR: Color = Color::new(0.05, 0.05, 0.08, 0.92);
+const HEADING_COLOR: Color = Color::new(1., 0.85, 0.2, 1.);
+
+/// A section of the notes, starting at a Markdown heading.
+#[derive(Debug)]
+struct Section {
+    heading: String,
+    text: String,
+}
+
+/// Presenter notes from a Markdown sidecar file. Each section, starting at
+/// a heading, belongs to the file named in the heading, like `## main.rs`.
+/// Sections whose heading doesn't name a file belong to the file at the same
+/// position in the list of files.
+#[derive(Debug)]
+pub struct Notes {
+    sections: Vec<Section>,
+    pub visible: bool,
+}
+
+impl Notes {
+    /// Load the notes from the Markdown file.
+    pub async fn load(path: &str) -> Option<Self> {
+        match load_string(path).await {
+            Ok(markdown) => Some(Self::parse(&markdown)),
+            Err(e) => {
+                warn!("Couldn't load notes {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    fn parse(markdown: &str) -> Self {
+        let mut sections: Vec<Section> = Vec::new();
+        for line in markdown.lines() {
+            let heading = line
+                .strip_prefix('#')
+                .map(|heading| heading.trim_start_matches('#').trim());
+            match (heading, sections.last_mut()) {
+                (Some(heading), _) => sections.push(Section {
+                    heading: heading.to_string(),
+                    text: String::new(),
+                }),
+                (None, Some(section)) => {
+                    section.text.push_str(line);
+                    section.text.push('\n');
+                }
+                // Text before the first heading belongs to the first file
+                (None, None) => sections.push(Section {
+                    heading: String::new(),
+                    text: format!("{}\n", line),
+                }),
+            }
+        }
+        for section in sections.iter_mut() {
+            section.text = section.text.trim().to_string();
+        }
+        Self {
+            sections,
+            visible: false,
+        }
+    }
+
+    /// Heading and text of the notes for the file at `index` of the files
+    /// with these titles.
+    fn for_slide<'a>(&'a self, index: usize, titles: &[String]) -> Option<&'a Section> {
+        let names_file = |section: &Section| {
+            titles
+                .iter()
+                .any(|title| title.eq_ignore_ascii_case(&section.heading))
+        };
+        let title = titles.get(index)?;
+        self.sections
+            .iter()
+            .find(|section| section.heading.eq_ignore_ascii_case(title))
+            .or_else(|| {
+                self.sections
+                    .get(index)
+                    .filter(|section| !names_file(section))
+            })
+    }
+
+    /// Draw the notes of the current file at the bottom of the screen, if
+    /// the notes are shown.
+    pub fn draw(&self, index: usize, titles: &[String], scale: f32) {
+        if !self.visible {
+            return;
+        }
+        let font_size = FONT_SIZE * scale;
+        let padding = NOTES_PADDING * scale;
+        let line_height = font_size * 1.3;
+        let (heading, text) = match self.for_slide(index, titles) {
+            Some(section) => (section.heading.as_str(), section.text.as_str()),
+            None => ("", "No notes for this file"),
+        };
+        let width = screen_width() - padding * 2.;
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            lines.extend(wrap(paragraph, width, font_size as u16));
+        }
+        let heading_lines = usize::from(!heading.is_empty());
+        let height = ((lines.len() + heading_lines) as f32 * line_height + padding * 2.)
+            .min(screen_height() * MAX_HEIGHT);
+        let top = screen_height() - height;
+        draw_rectangle(0., top, screen_width(), height, BACKGROUND_COLOR);
+        let mut y = top + padding + font_size;
+        if !heading.is_empty() {
+            draw_text(heading, padding, y, font_size, HEADING_COLOR);
+            y += line_height;
+        }
+        for line in lines {
+            if y > screen_height() - padding {
+                break;
+            }
+            draw_text(&line, padding, y, font_size, WHITE);
+            y += line_height;
+        }
+    }
+}
+
+/// Break a paragraph into lines that fit the width, between words.
+fn wrap(paragraph: &str, width: f32, font_size: u16) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in paragraph.split_whitespace() {
+        let Some(line) = lines.last_mut() else {
+            break;
+        };
+        let candidate = match line.is_empty() {
+            true => word.to_string(),
+            false => format!("{} {}", line, word),
+        };
+        if line.is_empty() || measure_text(&candidate, None, font_size, 1.).width <= width {
+            *line = candidate;
+        } else {
+            lines.push(word.to_string());
+        }
+    }
+    lines
+}
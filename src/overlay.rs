@@ -0,0 +1,168 @@
+use {
+    crate::{code::CodeSource, codebox::CodeBox, slides::Slides},
+    clap::ValueEnum,
+    macroquad::prelude::*,
+    qrcodegen::{QrCode, QrCodeEcc},
+};
+
+const FONT_SIZE: f32 = 20.;
+/// Distance between the overlays and the edges of the screen.
+const OVERLAY_MARGIN: f32 = 12.;
+const TEXT_COLOR: Color = Color::new(1., 1., 1., 0.7);
+/// Size of each module of the QR code in pixels, before scaling.
+const QR_MODULE_SIZE: f32 = 4.;
+/// Light modules around the QR code, which readers need to find it. The QR
+/// code standard asks for four.
+const QR_QUIET_ZONE: i32 = 4;
+
+/// What is displayed in the current frame, given to the hooks of the
+/// renderers.
+pub struct Frame<'a> {
+    /// The displayed code box, if the code could be loaded.
+    pub codebox: Option<&'a CodeBox>,
+    /// Where the displayed code is loaded from.
+    pub source: &'a CodeSource,
+    /// Index of the displayed file.
+    pub slide: usize,
+    /// Number of loaded files.
+    pub slides: usize,
+    /// Seconds since the program started.
+    pub time: f64,
+    /// Scale of fonts and padding.
+    pub scale: f32,
+}
+
+impl<'a> Frame<'a> {
+    pub(crate) fn new(slides: &'a Slides, scale: f32) -> Self {
+        Self {
+            codebox: slides.current().codebox.as_ref().ok(),
+            source: &slides.current().source,
+            slide: slides.current_index(),
+            slides: slides.iter().count(),
+            time: get_time(),
+            scale,
+        }
+    }
+}
+
+/// Hooks to draw extra overlays, like timers, logos or live data, on the
+/// screen each frame.
+pub trait Renderer {
+    /// Called each frame after the background is drawn, before the code.
+    fn pre_frame(&mut self, _frame: &Frame) {}
+
+    /// Called each frame after the code and the built-in overlays are drawn,
+    /// to draw on top of them.
+    fn post_frame(&mut self, _frame: &Frame) {}
+}
+
+/// Call the pre-frame hook of each renderer.
+pub fn pre_frame(renderers: &mut [Box<dyn Renderer>], frame: &Frame) {
+    for renderer in renderers.iter_mut() {
+        renderer.pre_frame(frame);
+    }
+}
+
+/// Call the post-frame hook of each renderer.
+pub fn post_frame(renderers: &mut [Box<dyn Renderer>], frame: &Frame) {
+    for renderer in renderers.iter_mut() {
+        renderer.post_frame(frame);
+    }
+}
+
+/// Built-in overlays that can be selected on the command line. The elapsed
+/// time is shown by the timer instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Overlay {
+    /// Frames per second
+    Fps,
+    /// QR code linking to the Gist or URL the code is loaded from
+    Qr,
+}
+
+impl Overlay {
+    pub fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            Overlay::Fps => Box::new(Fps),
+            Overlay::Qr => Box::new(SourceQr::default()),
+        }
+    }
+}
+
+struct Fps;
+
+impl Renderer for Fps {
+    fn post_frame(&mut self, frame: &Frame) {
+        draw_corner_text(&format!("{} fps", get_fps()), frame.scale);
+    }
+}
+
+/// QR code in the bottom right corner pointing at the source of the code,
+/// so that the audience can open it on their own devices.
+#[derive(Default)]
+struct SourceQr {
+    url: Option<String>,
+    texture: Option<Texture2D>,
+}
+
+impl Renderer for SourceQr {
+    fn post_frame(&mut self, frame: &Frame) {
+        let url = frame.source.url();
+        if url != self.url {
+            self.texture = url.as_deref().and_then(qr_texture);
+            self.url = url;
+        }
+        if let Some(texture) = &self.texture {
+            let size = texture.width() * QR_MODULE_SIZE * frame.scale;
+            let margin = OVERLAY_MARGIN * frame.scale;
+            draw_texture_ex(
+                texture,
+                screen_width() - size - margin,
+                screen_height() - size - margin,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(size, size)),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+/// Texture with one pixel per module of the QR code of the text, with a
+/// quiet zone around it. URLs too long for a QR code give no texture.
+fn qr_texture(text: &str) -> Option<Texture2D> {
+    let qr = QrCode::encode_text(text, QrCodeEcc::Low)
+        .map_err(|e| warn!("Couldn't create a QR code for {}: {:?}", text, e))
+        .ok()?;
+    let size = qr.size() + QR_QUIET_ZONE * 2;
+    let mut image = Image::gen_image_color(size as u16, size as u16, WHITE);
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                image.set_pixel(
+                    (x + QR_QUIET_ZONE) as u32,
+                    (y + QR_QUIET_ZONE) as u32,
+                    BLACK,
+                );
+            }
+        }
+    }
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    Some(texture)
+}
+
+/// Draw text in the top right corner.
+fn draw_corner_text(text: &str, scale: f32) {
+    let font_size = FONT_SIZE * scale;
+    let margin = OVERLAY_MARGIN * scale;
+    let dimensions = measure_text(text, None, font_size as u16, 1.);
+    draw_text(
+        text,
+        screen_width() - dimensions.width - margin,
+        margin + dimensions.offset_y,
+        font_size,
+        TEXT_COLOR,
+    );
+}
@@ -0,0 +1,486 @@
+use {
+    crate::codebox::{wrap_lines, CodeBox, CodeLine, HEADER_LINES, WRAP_INDENT, WRAP_MARKER},
+    macroquad::prelude::*,
+    std::{borrow::Cow, collections::BTreeMap, fmt::Write, fs},
+};
+
+/// Size of an A4 page in points.
+const PAGE_WIDTH: f32 = 595.;
+const PAGE_HEIGHT: f32 = 842.;
+const PAGE_MARGIN: f32 = 40.;
+/// Space between the code and the edge of the box on each page.
+const BOX_PADDING: f32 = 10.;
+/// Font size of the code, unless the longest line needs a smaller font to fit.
+const MAX_FONT_SIZE: f32 = 10.;
+/// Lines that don't fit at this font size are wrapped.
+const MIN_FONT_SIZE: f32 = 5.;
+/// Width of each character of the Courier font, relative to the font size.
+const COURIER_CHAR_WIDTH: f32 = 0.6;
+
+/// The code font, embedded in the document to draw any character it has.
+/// Without it the text is drawn with the standard Courier font, which only
+/// has the ASCII and Latin-1 characters.
+struct EmbeddedFont {
+    bytes: Vec<u8>,
+    font: fontdue::Font,
+    /// Name of the font in the document, without spaces.
+    name: String,
+}
+
+impl EmbeddedFont {
+    /// Load the TrueType font at the path, or None with a warning if it
+    /// can't be read or isn't a TrueType font.
+    fn load(path: &str, family: &str) -> Option<Self> {
+        let bytes = fs::read(path)
+            .map_err(|e| warn!("Couldn't read font {} for the PDF: {}", path, e))
+            .ok()?;
+        // OpenType fonts with CFF outlines would need to be embedded another way
+        if bytes.starts_with(b"OTTO") {
+            warn!(
+                "Couldn't embed font {} in the PDF, it isn't a TrueType font",
+                path
+            );
+            return None;
+        }
+        let font = fontdue::Font::from_bytes(bytes.as_slice(), fontdue::FontSettings::default())
+            .map_err(|e| warn!("Couldn't parse font {} for the PDF: {}", path, e))
+            .ok()?;
+        let name: String = family.chars().filter(char::is_ascii_alphanumeric).collect();
+        Some(Self {
+            bytes,
+            font,
+            name: match name.is_empty() {
+                true => "Code".to_string(),
+                false => name,
+            },
+        })
+    }
+
+    /// Width of each character, relative to the font size.
+    fn char_width(&self) -> f32 {
+        self.font.metrics('M', 1.).advance_width
+    }
+}
+
+/// Encodes text for the font of the document, and keeps track of the
+/// characters of each glyph used, so that the text can be copied.
+struct TextEncoder<'a> {
+    font: Option<&'a EmbeddedFont>,
+    glyphs: BTreeMap<u16, char>,
+}
+
+impl<'a> TextEncoder<'a> {
+    fn new(font: Option<&'a EmbeddedFont>) -> Self {
+        Self {
+            font,
+            glyphs: BTreeMap::new(),
+        }
+    }
+
+    /// The text as a string operand: the glyph IDs of the embedded font in
+    /// hex, or the escaped text for Courier.
+    fn encode(&mut self, text: &str) -> String {
+        let Some(font) = self.font else {
+            return format!("({})", escape_pdf(text));
+        };
+        let mut hex = String::from("<");
+        for c in text.chars() {
+            let glyph = font.font.lookup_glyph_index(c);
+            self.glyphs.entry(glyph).or_insert(c);
+            let _ = write!(hex, "{:04X}", glyph);
+        }
+        hex.push('>');
+        hex
+    }
+}
+
+/// Sizes of the code box on each page, in points.
+struct Layout {
+    font_size: f32,
+    line_height: f32,
+    char_width: f32,
+    gutter_width: f32,
+    header_height: f32,
+    lines_per_page: usize,
+    /// Number of columns to wrap the lines at, if they don't fit at the
+    /// smallest font size.
+    wrap_columns: Option<usize>,
+}
+
+impl Layout {
+    fn new(codebox: &CodeBox, relative_char_width: f32) -> Self {
+        let gutter_columns = codebox
+            .gutter()
+            .map(|gutter| gutter.digits + 2)
+            .unwrap_or(0);
+        let columns = codebox
+            .lines()
+            .iter()
+            .map(CodeLine::width)
+            .max()
+            .unwrap_or(0)
+            + gutter_columns;
+        let available_width = PAGE_WIDTH - (PAGE_MARGIN + BOX_PADDING) * 2.;
+        let fitting_size = available_width / (columns.max(1) as f32 * relative_char_width);
+        let font_size = fitting_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+        let wrap_columns = (fitting_size < MIN_FONT_SIZE).then(|| {
+            ((available_width / (MIN_FONT_SIZE * relative_char_width)) as usize)
+                .saturating_sub(gutter_columns)
+                .max(WRAP_INDENT + 1)
+        });
+        let line_height = font_size * codebox.line_height() / codebox.font_size() as f32;
+        let char_width = font_size * relative_char_width;
+        let header_height = match codebox.header() {
+            Some(_) => line_height * HEADER_LINES,
+            None => 0.,
+        };
+        let available_height = PAGE_HEIGHT - PAGE_MARGIN * 2. - BOX_PADDING * 2. - header_height;
+        Self {
+            font_size,
+            line_height,
+            char_width,
+            gutter_width: gutter_columns as f32 * char_width,
+            header_height,
+            lines_per_page: ((available_height / line_height) as usize).max(1),
+            wrap_columns,
+        }
+    }
+
+    /// Offset from the top of a line down to the baseline of its text.
+    fn baseline_offset(&self) -> f32 {
+        (self.line_height + self.font_size * 0.6) / 2.
+    }
+}
+
+/// Lay out the highlighted code on A4 pages, with the colors and the font of
+/// the code box, and return the PDF document.
+pub fn render_pdf(codebox: &CodeBox) -> Vec<u8> {
+    let font = EmbeddedFont::load(codebox.font_path(), codebox.font_family());
+    let char_width = font
+        .as_ref()
+        .map(EmbeddedFont::char_width)
+        .unwrap_or(COURIER_CHAR_WIDTH);
+    let layout = Layout::new(codebox, char_width);
+    let mut encoder = TextEncoder::new(font.as_ref());
+    let lines = match layout.wrap_columns {
+        Some(columns) => Cow::Owned(wrap_lines(codebox.lines().to_vec(), columns)),
+        None => Cow::Borrowed(codebox.lines()),
+    };
+    let mut pages: Vec<String> = lines
+        .chunks(layout.lines_per_page)
+        .map(|lines| page_content(codebox, &layout, &mut encoder, lines))
+        .collect();
+    // A document needs at least one page, even without any code
+    if pages.is_empty() {
+        pages.push(page_content(codebox, &layout, &mut encoder, &[]));
+    }
+    let font_objects = match &font {
+        Some(font) => embedded_font_objects(font, &encoder.glyphs),
+        None => vec![
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        ],
+    };
+    write_document(&pages, font_objects)
+}
+
+/// Drawing operators for one page of the code box.
+fn page_content(
+    codebox: &CodeBox,
+    layout: &Layout,
+    encoder: &mut TextEncoder,
+    lines: &[CodeLine],
+) -> String {
+    let background = codebox.background_color();
+    let box_width = PAGE_WIDTH - PAGE_MARGIN * 2.;
+    let box_height =
+        BOX_PADDING * 2. + layout.header_height + lines.len() as f32 * layout.line_height;
+    let box_top = PAGE_HEIGHT - PAGE_MARGIN;
+    let mut content = String::new();
+
+    fill_rect(
+        &mut content,
+        Rect::new(PAGE_MARGIN, box_top - box_height, box_width, box_height),
+        background,
+        background,
+    );
+
+    if let Some(header) = codebox.header() {
+        fill_rect(
+            &mut content,
+            Rect::new(
+                PAGE_MARGIN,
+                box_top - layout.header_height,
+                box_width,
+                layout.header_height,
+            ),
+            header.background_color,
+            background,
+        );
+        let baseline = box_top - (layout.header_height + layout.font_size * 0.6) / 2.;
+        write_text(
+            &mut content,
+            layout,
+            encoder,
+            (PAGE_MARGIN + BOX_PADDING, baseline),
+            &[(header.title.as_str(), header.text_color)],
+            background,
+        );
+    }
+
+    let lines_top = box_top - layout.header_height - BOX_PADDING;
+    let code_x = PAGE_MARGIN + BOX_PADDING + layout.gutter_width;
+    if let Some(gutter) = codebox.gutter() {
+        fill_rect(
+            &mut content,
+            Rect::new(
+                PAGE_MARGIN,
+                box_top - box_height,
+                BOX_PADDING + layout.gutter_width,
+                box_height - layout.header_height,
+            ),
+            gutter.background_color,
+            background,
+        );
+        for (index, line) in lines.iter().enumerate() {
+            if line.continuation {
+                continue;
+            }
+            let number = format!("{:>width$}", line.number, width = gutter.digits);
+            let baseline = lines_top - index as f32 * layout.line_height - layout.baseline_offset();
+            write_text(
+                &mut content,
+                layout,
+                encoder,
+                (PAGE_MARGIN + BOX_PADDING, baseline),
+                &[(number.as_str(), gutter.text_color)],
+                background,
+            );
+        }
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let line_top = lines_top - index as f32 * layout.line_height;
+        if let Some(color) = codebox.line_background(line) {
+            fill_rect(
+                &mut content,
+                Rect::new(
+                    code_x,
+                    line_top - layout.line_height,
+                    PAGE_MARGIN + box_width - code_x,
+                    layout.line_height,
+                ),
+                color,
+                background,
+            );
+        }
+        let mut x = code_x;
+        if line.continuation {
+            // Courier only has the Latin-1 characters
+            let marker = match encoder.font {
+                Some(_) => WRAP_MARKER,
+                None => ">",
+            };
+            write_text(
+                &mut content,
+                layout,
+                encoder,
+                (x, line_top - layout.baseline_offset()),
+                &[(marker, codebox.wrap_marker_color())],
+                background,
+            );
+            x += WRAP_INDENT as f32 * layout.char_width;
+        }
+        let spans: Vec<(&str, Color)> = line
+            .spans
+            .iter()
+            .map(|span| (span.text.as_str(), span.color))
+            .collect();
+        write_text(
+            &mut content,
+            layout,
+            encoder,
+            (x, line_top - layout.baseline_offset()),
+            &spans,
+            background,
+        );
+    }
+    content
+}
+
+fn fill_rect(content: &mut String, rect: Rect, color: Color, background: Color) {
+    let _ = writeln!(
+        content,
+        "{} rg {:.2} {:.2} {:.2} {:.2} re f",
+        pdf_color(color, background),
+        rect.x,
+        rect.y,
+        rect.w,
+        rect.h
+    );
+}
+
+/// Write spans of colored text, one after the other, starting at the baseline.
+fn write_text(
+    content: &mut String,
+    layout: &Layout,
+    encoder: &mut TextEncoder,
+    (x, y): (f32, f32),
+    spans: &[(&str, Color)],
+    background: Color,
+) {
+    let _ = write!(
+        content,
+        "BT /F1 {:.2} Tf {:.2} {:.2} Td",
+        layout.font_size, x, y
+    );
+    for (text, color) in spans {
+        let _ = write!(
+            content,
+            " {} rg {} Tj",
+            pdf_color(*color, background),
+            encoder.encode(text)
+        );
+    }
+    content.push_str(" ET\n");
+}
+
+/// PDF colors are opaque, so translucent colors are blended with the
+/// background of the code box.
+fn pdf_color(color: Color, background: Color) -> String {
+    let blend = |value: f32, background: f32| value * color.a + background * (1. - color.a);
+    format!(
+        "{:.3} {:.3} {:.3}",
+        blend(color.r, background.r),
+        blend(color.g, background.g),
+        blend(color.b, background.b)
+    )
+}
+
+/// Escape a string for the standard Courier font, which only covers ASCII
+/// and Latin-1 in the WinAnsi encoding, so other characters are replaced.
+fn escape_pdf(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' | '(' | ')' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            // Latin-1 has the same codes in WinAnsi, written as octal escapes
+            // to keep the content stream ASCII
+            '\u{a0}'..='\u{ff}' => format!("\\{:03o}", c as u32),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+/// Objects of a Type 0 font with the embedded TrueType font, starting with
+/// the font itself as object 3. The glyphs are addressed by their IDs, and
+/// mapped back to their characters so that the text can be copied.
+fn embedded_font_objects(font: &EmbeddedFont, glyphs: &BTreeMap<u16, char>) -> Vec<Vec<u8>> {
+    let name = &font.name;
+    let width = (font.char_width() * 1000.).round();
+    let (ascent, descent) = font
+        .font
+        .horizontal_line_metrics(1000.)
+        .map(|metrics| (metrics.ascent.round(), metrics.descent.round()))
+        .unwrap_or((800., -200.));
+    let mut to_unicode = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n/CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let glyphs: Vec<(&u16, &char)> = glyphs.iter().collect();
+    // Each block of mappings may have at most 100 entries
+    for block in glyphs.chunks(100) {
+        let _ = writeln!(to_unicode, "{} beginbfchar", block.len());
+        for (glyph, c) in block {
+            let mut utf16 = [0; 2];
+            let code: String = c
+                .encode_utf16(&mut utf16)
+                .iter()
+                .map(|unit| format!("{:04X}", unit))
+                .collect();
+            let _ = writeln!(to_unicode, "<{:04X}> <{}>", glyph, code);
+        }
+        to_unicode.push_str("endbfchar\n");
+    }
+    to_unicode.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+
+    let mut font_file = format!(
+        "<< /Length {} /Length1 {} >>\nstream\n",
+        font.bytes.len(),
+        font.bytes.len()
+    )
+    .into_bytes();
+    font_file.extend_from_slice(&font.bytes);
+    font_file.extend_from_slice(b"\nendstream");
+    vec![
+        format!("<< /Type /Font /Subtype /Type0 /BaseFont /{name} /Encoding /Identity-H /DescendantFonts [4 0 R] /ToUnicode 7 0 R >>").into_bytes(),
+        format!("<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{name} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor 5 0 R /DW {width} /CIDToGIDMap /Identity >>").into_bytes(),
+        // Flags mark a fixed-pitch font with non-symbolic characters
+        format!("<< /Type /FontDescriptor /FontName /{name} /Flags 33 /FontBBox [0 {descent} {width} {ascent}] /ItalicAngle 0 /Ascent {ascent} /Descent {descent} /CapHeight {ascent} /StemV 80 /FontFile2 6 0 R >>").into_bytes(),
+        font_file,
+        stream(&to_unicode).into_bytes(),
+    ]
+}
+
+fn stream(content: &str) -> String {
+    format!(
+        "<< /Length {} >>\nstream\n{}endstream",
+        content.len(),
+        content
+    )
+}
+
+/// Write the objects of the document, with a cross-reference table of their
+/// byte offsets.
+fn write_document(pages: &[String], font_objects: Vec<Vec<u8>>) -> Vec<u8> {
+    // Object 1 is the catalog, 2 the page tree and 3 the font, followed by
+    // the other objects of the font, and a page and a content stream for each
+    // page.
+    let first_page = 3 + font_objects.len();
+    let page_ids: Vec<usize> = (0..pages.len())
+        .map(|index| first_page + index * 2)
+        .collect();
+    let mut objects = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{} 0 R", id))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    ];
+    objects.extend(font_objects);
+    for (content, id) in pages.iter().zip(page_ids.iter()) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            id + 1
+        ).into_bytes());
+        objects.push(stream(content).into_bytes());
+    }
+
+    // The embedded font is binary, a comment with high bytes tells readers so
+    let mut document = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        document.extend_from_slice(object);
+        document.extend_from_slice(b"\nendobj\n");
+    }
+    let xref_offset = document.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    );
+    document.extend_from_slice(trailer.as_bytes());
+    document
+}
@@ -0,0 +1,96 @@
+use macroquad::prelude::*;
+
+const FONT_SIZE: u16 = 32;
+const ROW_HEIGHT: f32 = 48.;
+const MARGIN: f32 = 40.;
+const BACKGROUND_COLOR: Color = Color::new(0.1, 0.1, 0.12, 1.0);
+const SELECTED_COLOR: Color = Color::new(0.25, 0.25, 0.35, 1.0);
+
+/// Show a list of items on screen and let the user pick one with the
+/// keyboard or mouse. Returns None if the list is empty or if the user
+/// cancels with Escape.
+pub async fn pick(title: &str, items: &[String]) -> Option<usize> {
+    if items.is_empty() {
+        return None;
+    }
+    let mut selected = 0;
+    loop {
+        if is_key_pressed(KeyCode::Down) {
+            selected = (selected + 1).min(items.len() - 1);
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected = selected.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            wait_for_release().await;
+            return Some(selected);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            wait_for_release().await;
+            return None;
+        }
+
+        let list_top = MARGIN + ROW_HEIGHT * 1.5;
+        let visible_rows = (((screen_height() - list_top - MARGIN) / ROW_HEIGHT) as usize).max(1);
+        let first_row = (selected + 1).saturating_sub(visible_rows);
+
+        let (_, mouse_y) = mouse_position();
+        let hovered = (mouse_y >= list_top)
+            .then(|| first_row + ((mouse_y - list_top) / ROW_HEIGHT) as usize)
+            .filter(|&row| row < items.len() && row < first_row + visible_rows);
+        if let Some(row) = hovered {
+            if mouse_delta_position() != Vec2::ZERO {
+                selected = row;
+            }
+            if is_mouse_button_pressed(MouseButton::Left) {
+                wait_for_release().await;
+                return Some(row);
+            }
+        }
+
+        clear_background(BACKGROUND_COLOR);
+        draw_text(
+            title,
+            MARGIN,
+            MARGIN + FONT_SIZE as f32,
+            FONT_SIZE as f32,
+            GRAY,
+        );
+        for (row, item) in items.iter().enumerate().skip(first_row).take(visible_rows) {
+            let ypos = list_top + (row - first_row) as f32 * ROW_HEIGHT;
+            if row == selected {
+                draw_rectangle(
+                    MARGIN / 2.,
+                    ypos,
+                    screen_width() - MARGIN,
+                    ROW_HEIGHT,
+                    SELECTED_COLOR,
+                );
+            }
+            draw_text(
+                item,
+                MARGIN,
+                ypos + ROW_HEIGHT * 0.7,
+                FONT_SIZE as f32,
+                WHITE,
+            );
+        }
+        next_frame().await;
+    }
+}
+
+/// Wait until the key or mouse button that picked or cancelled is let go,
+/// so that the same press isn't read again by whatever is shown next, like
+/// another picker or the first frame of the code.
+async fn wait_for_release() {
+    loop {
+        next_frame().await;
+        let released = [KeyCode::Enter, KeyCode::KpEnter, KeyCode::Escape]
+            .into_iter()
+            .all(|key| !is_key_down(key))
+            && !is_mouse_button_down(MouseButton::Left);
+        if released {
+            return;
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use {macroquad::prelude::*, nanoserde::DeJson, sapp_jsutils::JsObject};
+
+extern "C" {
+    /// Next message posted to the window, from `demo/post-message.js`.
+    fn rusty_code_next_message() -> JsObject;
+}
+
+/// Version of the JavaScript plugin, which miniquad compares to the version
+/// the plugin registered with.
+#[no_mangle]
+extern "C" fn post_message_crate_version() -> u32 {
+    1 << 16
+}
+
+/// A message from the page embedding the canvas, with the code to display
+/// and how to display it. Fields that are left out are kept as they are.
+#[derive(Debug, DeJson)]
+pub struct PostMessage {
+    #[nserde(default)]
+    pub code: Option<String>,
+    #[nserde(default)]
+    pub language: Option<String>,
+    /// Name of a bundled theme, or URL of a theme file.
+    #[nserde(default)]
+    pub theme: Option<String>,
+}
+
+/// Messages posted to the window since the last call. Messages that aren't
+/// JSON objects like `{code, language, theme}` are ignored, since the page
+/// may post messages meant for other listeners.
+pub fn messages() -> Vec<PostMessage> {
+    let mut messages = Vec::new();
+    loop {
+        let message = unsafe { rusty_code_next_message() };
+        if message.is_nil() {
+            return messages;
+        }
+        let mut json = String::new();
+        message.to_string(&mut json);
+        match PostMessage::deserialize_json(&json) {
+            Ok(message) => messages.push(message),
+            Err(e) => debug!("Ignoring posted message {}: {}", json, e),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use {
+    crate::code_theme::{hex_color, CodeTheme},
+    clap::ValueEnum,
+    macroquad::prelude::*,
+};
+
+const FONT_SIZE: f32 = 20.;
+/// Height of the progress bar at the bottom of the screen, before scaling.
+const BAR_HEIGHT: f32 = 3.;
+/// Distance between the counter and the edges of the screen.
+const COUNTER_MARGIN: f32 = 12.;
+
+/// How far along the files of a walkthrough the presentation is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Progress {
+    /// Thin bar along the bottom of the screen
+    Bar,
+    /// Number of the file and how many there are, like 3/12
+    Counter,
+}
+
+impl Progress {
+    /// Draw the progress with the progress color of the theme. Nothing is
+    /// drawn for a single file.
+    pub fn draw(self, index: usize, count: usize, code_theme: &CodeTheme, scale: f32) {
+        if count < 2 {
+            return;
+        }
+        let color = hex_color(&code_theme.progress_color);
+        match self {
+            Progress::Bar => {
+                let height = BAR_HEIGHT * scale;
+                let width = screen_width() * (index + 1) as f32 / count as f32;
+                draw_rectangle(0., screen_height() - height, width, height, color);
+            }
+            Progress::Counter => {
+                let text = format!("{}/{}", index + 1, count);
+                let font_size = FONT_SIZE * scale;
+                let margin = COUNTER_MARGIN * scale;
+                draw_text(&text, margin, screen_height() - margin, font_size, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+extern "C" {
+    /// Whether the `prefers-reduced-motion` media query of the browser
+    /// matches, from `demo/reduced-motion.js`.
+    fn rusty_code_prefers_reduced_motion() -> bool;
+}
+
+/// Version of the JavaScript plugin, which miniquad compares to the version
+/// the plugin registered with.
+#[no_mangle]
+extern "C" fn reduced_motion_crate_version() -> u32 {
+    1 << 16
+}
+
+/// Whether the visitor has asked their system to reduce motion.
+pub fn prefers_reduced_motion() -> bool {
+    unsafe { rusty_code_prefers_reduced_motion() }
+}
@@ -0,0 +1,215 @@
+use {
+    crate::{line_ranges::LinePosition, server::is_same_origin},
+    macroquad::prelude::*,
+    std::{
+        io,
+        net::{TcpListener, TcpStream},
+        path::PathBuf,
+        str::FromStr,
+        sync::mpsc::{self, Receiver, Sender},
+        thread,
+    },
+    tungstenite::{
+        handshake::server::{ErrorResponse, Request, Response},
+        http::StatusCode,
+        Message,
+    },
+};
+
+/// A command sent to the remote control.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Show the next file
+    Next,
+    /// Show the previous file
+    Previous,
+    /// Scroll to a line, and optionally column, and flash it
+    GotoLine(LinePosition),
+    /// Replace the displayed file with the file at the path
+    Load(PathBuf),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    /// Parse a command like `next`, `prev`, `goto-line 120:8` or
+    /// `load src/main.rs`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, argument) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let argument = argument.trim();
+        match (name, argument.is_empty()) {
+            ("next", true) => Ok(Command::Next),
+            ("prev" | "previous", true) => Ok(Command::Previous),
+            ("goto-line", false) => argument.parse().map(Command::GotoLine),
+            ("load", false) => Ok(Command::Load(PathBuf::from(argument))),
+            ("goto-line" | "load", true) => Err(format!("Missing argument to {}", name)),
+            ("next" | "prev" | "previous", false) => {
+                Err(format!("{} doesn't take an argument", name))
+            }
+            _ => Err(format!(
+                "Unknown command {}, use next, prev, goto-line <line[:column]> or load <path>",
+                name
+            )),
+        }
+    }
+}
+
+/// WebSocket server that a phone or a companion app can drive the display
+/// with during a talk. Each text message is a command, which is answered
+/// with `ok` or an error message.
+///
+/// With a token, clients must connect to a URL like `ws://host:port/?token=`
+/// followed by the token. Web pages can only connect from the origin of the
+/// server, so that a page open in the browser can't drive the display.
+pub struct RemoteControl {
+    receiver: Receiver<Command>,
+}
+
+impl RemoteControl {
+    /// Start listening for connections on the address and port. Listen on
+    /// `0.0.0.0` to let other devices on the network connect.
+    pub fn listen(address: &str, port: u16, token: Option<String>) -> io::Result<Self> {
+        let listener = TcpListener::bind((address, port))?;
+        info!(
+            "Listening for remote control commands on {}:{}",
+            address, port
+        );
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let sender = sender.clone();
+                        let token = token.clone();
+                        thread::spawn(move || handle_connection(stream, sender, token));
+                    }
+                    Err(e) => warn!("Remote control connection failed: {}", e),
+                }
+            }
+        });
+        Ok(Self { receiver })
+    }
+
+    /// Commands received since the last call.
+    pub fn commands(&self) -> Vec<Command> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+fn handle_connection(stream: TcpStream, sender: Sender<Command>, token: Option<String>) {
+    let peer = stream
+        .peer_addr()
+        .map(|address| address.to_string())
+        .unwrap_or_default();
+    let check = |request: &Request, response: Response| {
+        check_handshake(request, token.as_deref()).map(|()| response)
+    };
+    let mut socket = match tungstenite::accept_hdr(stream, check) {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("Remote control handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+    info!("Remote control connected from {}", peer);
+    loop {
+        let reply = match socket.read() {
+            Ok(Message::Text(text)) => match text.parse::<Command>() {
+                Ok(command) => {
+                    debug!("Remote control command: {:?}", command);
+                    if sender.send(command).is_err() {
+                        return;
+                    }
+                    "ok".to_string()
+                }
+                Err(e) => e,
+            },
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        if socket.send(Message::Text(reply)).is_err() {
+            break;
+        }
+    }
+    info!("Remote control disconnected from {}", peer);
+}
+
+/// Refuse connections from web pages on other origins, and connections
+/// without the token if one is required.
+fn check_handshake(request: &Request, token: Option<&str>) -> Result<(), ErrorResponse> {
+    let header = |name| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    if let Some(origin) = header("Origin") {
+        if !is_same_origin(origin, header("Host").unwrap_or_default()) {
+            return Err(error_response(
+                StatusCode::FORBIDDEN,
+                "Connections from other origins aren't allowed",
+            ));
+        }
+    }
+    if let Some(token) = token {
+        if query_token(request.uri().query().unwrap_or_default()) != Some(token) {
+            return Err(error_response(
+                StatusCode::UNAUTHORIZED,
+                "Connect with the token of the remote control, like ?token=...",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Value of the `token` argument in the query of a URL.
+fn query_token(query: &str) -> Option<&str> {
+    query
+        .split('&')
+        .find_map(|argument| argument.strip_prefix("token="))
+}
+
+fn error_response(status: StatusCode, message: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!("next".parse(), Ok(Command::Next));
+        assert_eq!(" prev\n".parse(), Ok(Command::Previous));
+        assert_eq!("previous".parse(), Ok(Command::Previous));
+        assert_eq!(
+            "goto-line 120:8".parse(),
+            Ok(Command::GotoLine(LinePosition {
+                line: 120,
+                column: Some(8)
+            }))
+        );
+        assert_eq!(
+            "load  src/main.rs".parse(),
+            Ok(Command::Load(PathBuf::from("src/main.rs")))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_commands() {
+        for command in ["", "jump", "next 2", "goto-line", "goto-line zero", "load"] {
+            assert!(command.parse::<Command>().is_err(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn finds_the_token_in_the_query() {
+        assert_eq!(query_token("token=secret"), Some("secret"));
+        assert_eq!(query_token("lang=en&token=secret"), Some("secret"));
+        assert_eq!(query_token("tokens=secret"), None);
+        assert_eq!(query_token(""), None);
+    }
+}
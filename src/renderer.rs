@@ -0,0 +1,170 @@
+use {
+    crate::{
+        code::{Code, Result},
+        code_theme::CodeTheme,
+        codebox::{load_syntax_set, CodeBox, CodeBoxBuilder},
+        fonts::FallbackFonts,
+    },
+    macroquad::prelude::*,
+    rusty_slider::prelude::Theme,
+    std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+    syntect::parsing::SyntaxSet,
+};
+
+/// Theme with the fonts of the code box, used when a bundled color scheme
+/// is chosen instead of a theme file.
+pub const DEFAULT_THEME: &str = "assets/theme.json";
+
+/// Load the theme from a file, or use one of the bundled color schemes with
+/// the fonts of the default theme.
+pub async fn load_themes(theme_path: &Path) -> (Theme, CodeTheme) {
+    let name = theme_path.to_string_lossy();
+    if let Some(code_theme) = CodeTheme::bundled(&name) {
+        let mut theme = Theme::load(PathBuf::from(DEFAULT_THEME)).await;
+        theme.code_theme = name.into_owned();
+        return (theme, code_theme);
+    }
+    (
+        Theme::load(theme_path.to_path_buf()).await,
+        CodeTheme::load(theme_path.to_path_buf()).await,
+    )
+}
+
+/// Load the font at the path, unless the path is empty. A font that can't be
+/// loaded is left out with a warning.
+async fn load_optional_font(path: &str) -> Option<Font> {
+    if path.is_empty() {
+        return None;
+    }
+    load_ttf_font(path)
+        .await
+        .map_err(|e| warn!("Couldn't load font {}: {:?}", path, e))
+        .ok()
+}
+
+/// Renders code into code boxes with a theme and its fonts, to draw code in
+/// another Macroquad app:
+///
+/// ```no_run
+/// # async fn example() -> rusty_code::Result<()> {
+/// use rusty_code::{Code, CodeRenderer};
+///
+/// let renderer = CodeRenderer::load("base16-ocean.dark").await?;
+/// let codebox = renderer.render(&Code::from_sourcecode("fn main() {}".to_string()));
+/// codebox.draw(20., 20.);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CodeRenderer {
+    theme: Theme,
+    code_theme: CodeTheme,
+    font_code: Font,
+    font_bold: Font,
+    font_italic: Font,
+    font_emoji: Option<Font>,
+    fallback_fonts: Option<FallbackFonts>,
+    font_icons: Option<Font>,
+    syntax_set: Arc<SyntaxSet>,
+}
+
+impl CodeRenderer {
+    /// Load the fonts of the theme. The code is drawn without the emoji,
+    /// fallback and icon fonts if they can't be loaded.
+    pub async fn new(theme: Theme, code_theme: CodeTheme) -> Result<Self> {
+        let font_code = load_ttf_font(&theme.font_code).await?;
+        let font_bold = load_ttf_font(&theme.font_bold).await?;
+        let font_italic = load_ttf_font(&theme.font_italic).await?;
+        let font_emoji = load_optional_font(&code_theme.font_emoji).await;
+        let fallback_fonts = match code_theme.font_fallbacks.is_empty() {
+            true => None,
+            false => FallbackFonts::load(&theme.font_code, &code_theme.font_fallbacks)
+                .await
+                .map_err(|e| warn!("Couldn't load fallback fonts: {}", e))
+                .ok(),
+        };
+        let font_icons = load_optional_font(&code_theme.font_icons).await;
+        Ok(Self {
+            theme,
+            code_theme,
+            font_code,
+            font_bold,
+            font_italic,
+            font_emoji,
+            fallback_fonts,
+            font_icons,
+            syntax_set: Arc::new(load_syntax_set(None)),
+        })
+    }
+
+    /// Add the `.sublime-syntax` definitions in the directory to the
+    /// syntaxes the code is highlighted with.
+    pub fn load_syntax_dir(&mut self, syntax_dir: &Path) {
+        self.syntax_set = Arc::new(load_syntax_set(Some(syntax_dir)));
+    }
+
+    /// Load a theme file, or a bundled theme by name, and its fonts.
+    pub async fn load(theme: impl AsRef<Path>) -> Result<Self> {
+        let (theme, code_theme) = load_themes(theme.as_ref()).await;
+        Self::new(theme, code_theme).await
+    }
+
+    /// Switch to other themes, only loading the fonts again if the themes
+    /// use other fonts. The current themes are kept if the fonts can't be
+    /// loaded.
+    pub async fn set_themes(&mut self, theme: Theme, code_theme: CodeTheme) -> Result<()> {
+        let same_fonts = theme.font_code == self.theme.font_code
+            && theme.font_bold == self.theme.font_bold
+            && theme.font_italic == self.theme.font_italic
+            && code_theme.font_emoji == self.code_theme.font_emoji
+            && code_theme.font_fallbacks == self.code_theme.font_fallbacks
+            && code_theme.font_icons == self.code_theme.font_icons;
+        match same_fonts {
+            true => {
+                self.theme = theme;
+                self.code_theme = code_theme;
+            }
+            false => {
+                *self = Self {
+                    syntax_set: self.syntax_set.clone(),
+                    ..Self::new(theme, code_theme).await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn code_theme(&self) -> &CodeTheme {
+        &self.code_theme
+    }
+
+    /// Builder of a code box with the theme and fonts, to set more options
+    /// like line numbers before building it.
+    pub fn builder(&self) -> CodeBoxBuilder {
+        CodeBoxBuilder::new(
+            self.theme.clone(),
+            self.code_theme.clone(),
+            self.font_code.clone(),
+            self.font_bold.clone(),
+            self.font_italic.clone(),
+        )
+        .emoji_font(self.font_emoji.clone())
+        .fallback_fonts(self.fallback_fonts.clone())
+        .icon_font(self.font_icons.clone())
+        .syntax_set(self.syntax_set.clone())
+    }
+
+    /// Highlight the code in its language with the default options.
+    pub fn render(&self, code: &Code) -> CodeBox {
+        self.builder()
+            .line_slice(code.lines)
+            .extension(code.extension())
+            .build_draw_box(code.language(None), code.sourcecode.clone())
+    }
+}
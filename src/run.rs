@@ -0,0 +1,284 @@
+use {
+    crate::{code::Code, codebox::CodeBox},
+    macroquad::prelude::*,
+    nanoserde::{DeJson, SerJson},
+    std::{
+        env,
+        fs::{self, OpenOptions},
+        io::{self, Read, Write},
+        path::{Path, PathBuf},
+        process::{self, Command, Stdio},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            mpsc::{self, Receiver},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Distance between the code box and the output box below it.
+const OUTPUT_MARGIN: f32 = 20.;
+/// Programs that run longer than this are killed, so that an endless loop
+/// doesn't keep running in the background.
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// Number of runs started by this process, to give each its own directory.
+static RUN_COUNT: AtomicUsize = AtomicUsize::new(0);
+const PLAYGROUND_EXECUTE_URL: &str = "https://play.rust-lang.org/execute";
+
+/// Where the code is run.
+enum Backend {
+    /// A command on this computer, empty to choose one for the language
+    Command(String),
+    /// The Rust Playground
+    Playground,
+}
+
+/// Runs the displayed code in the background, to show what it prints. With
+/// a command, the code is saved to a temporary file, whose path is given to
+/// the command as its last argument, like `python3 /tmp/rusty-code-1-0/main.py`.
+/// The command is split on whitespace and run without a shell.
+pub struct Runner {
+    backend: Backend,
+    receiver: Option<Receiver<String>>,
+    /// Set to stop the run in progress.
+    cancel: Arc<AtomicBool>,
+}
+
+impl Runner {
+    /// Run the code with the command, or with a runner for the language if
+    /// the command is empty.
+    pub fn new(command: String) -> Self {
+        Self {
+            backend: Backend::Command(command),
+            receiver: None,
+            cancel: Arc::default(),
+        }
+    }
+
+    /// Run Rust code on the Rust Playground.
+    pub fn playground() -> Self {
+        Self {
+            backend: Backend::Playground,
+            receiver: None,
+            cancel: Arc::default(),
+        }
+    }
+
+    /// Start running the code. A run that is still in progress is stopped.
+    pub fn start(&mut self, code: &Code, language: Option<&str>) {
+        let (sender, receiver) = mpsc::channel();
+        self.receiver = Some(receiver);
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::default();
+        let command = match &self.backend {
+            Backend::Playground => {
+                let sourcecode = code.sourcecode.clone();
+                thread::spawn(move || {
+                    let output = execute_on_playground(sourcecode).unwrap_or_else(|e| {
+                        format!("Couldn't run the code on the Rust Playground: {}", e)
+                    });
+                    let _ = sender.send(output);
+                });
+                return;
+            }
+            Backend::Command(command) if command.is_empty() => {
+                default_command(language).map(str::to_string)
+            }
+            Backend::Command(command) => Some(command.clone()),
+        };
+        let Some(command) = command else {
+            let _ = sender.send(format!(
+                "No command to run {} with, give one to --run",
+                language.unwrap_or("the code")
+            ));
+            return;
+        };
+        let filename = Path::new(&code.filename)
+            .file_name()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("noname.txt"));
+        let sourcecode = code.sourcecode.clone();
+        let cancel = self.cancel.clone();
+        thread::spawn(move || {
+            let output = run(&command, &filename, &sourcecode, &cancel)
+                .unwrap_or_else(|e| format!("Couldn't run {}: {}", command, e));
+            let _ = sender.send(output);
+        });
+    }
+
+    /// What the code printed, once it has finished running.
+    pub fn output(&mut self) -> Option<String> {
+        let output = self.receiver.as_ref()?.try_recv().ok()?;
+        self.receiver = None;
+        Some(output)
+    }
+}
+
+/// Command to run code in the language with, if the language is a script
+/// language or has a runner for single files.
+pub fn default_command(language: Option<&str>) -> Option<&'static str> {
+    match language?.to_lowercase().as_str() {
+        "rust" | "rs" => Some("rust-script"),
+        "python" | "py" => Some("python3"),
+        "javascript" | "js" => Some("node"),
+        "typescript" | "ts" => Some("deno run"),
+        "ruby" | "rb" => Some("ruby"),
+        "go" => Some("go run"),
+        "lua" => Some("lua"),
+        "php" => Some("php"),
+        "perl" | "pl" => Some("perl"),
+        "shell" | "bash" | "sh" => Some("sh"),
+        _ => None,
+    }
+}
+
+/// Save the code to a temporary file and run the command with it. Returns
+/// standard output followed by standard error.
+fn run(
+    command: &str,
+    filename: &Path,
+    sourcecode: &str,
+    cancel: &AtomicBool,
+) -> io::Result<String> {
+    let directory = env::temp_dir().join(format!(
+        "rusty-code-{}-{}",
+        process::id(),
+        RUN_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    // Fails if the directory exists, so that nobody else can have put a
+    // symlink where the code is written
+    fs::create_dir(&directory)?;
+    let output = run_in(&directory, command, filename, sourcecode, cancel);
+    let _ = fs::remove_dir_all(&directory);
+    output
+}
+
+fn run_in(
+    directory: &Path,
+    command: &str,
+    filename: &Path,
+    sourcecode: &str,
+    cancel: &AtomicBool,
+) -> io::Result<String> {
+    let path = directory.join(filename);
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?
+        .write_all(sourcecode.as_bytes())?;
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+    let mut child = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .current_dir(directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if cancel.load(Ordering::Relaxed) || started.elapsed() > RUN_TIMEOUT {
+            child.kill()?;
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    let mut text = stdout.join().unwrap_or_default();
+    text.push_str(&stderr.join().unwrap_or_default());
+    match status {
+        Some(status) if !status.success() => text.push_str(&format!("\n{}", status)),
+        Some(_) => (),
+        None => text.push_str(&format!(
+            "\nStopped after {} seconds",
+            RUN_TIMEOUT.as_secs()
+        )),
+    }
+    Ok(text)
+}
+
+/// Read everything from a pipe of the child process in a thread, so that
+/// the child doesn't block on a full pipe while it is waited for.
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+#[derive(SerJson)]
+struct ExecuteRequest {
+    channel: String,
+    mode: String,
+    edition: String,
+    #[nserde(rename = "crateType")]
+    crate_type: String,
+    tests: bool,
+    backtrace: bool,
+    code: String,
+}
+
+#[derive(DeJson)]
+struct ExecuteResponse {
+    #[nserde(default)]
+    stdout: String,
+    #[nserde(default)]
+    stderr: String,
+    #[nserde(default)]
+    error: Option<String>,
+}
+
+/// Compile and run the Rust code on the Rust Playground. Returns what the
+/// compiler and the program printed.
+fn execute_on_playground(code: String) -> Result<String, String> {
+    let request = ExecuteRequest {
+        channel: "stable".to_string(),
+        mode: "debug".to_string(),
+        edition: "2021".to_string(),
+        crate_type: "bin".to_string(),
+        tests: false,
+        backtrace: false,
+        code,
+    };
+    let json = ureq::post(PLAYGROUND_EXECUTE_URL)
+        .set("Content-Type", "application/json")
+        .send_string(&request.serialize_json())
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    let response = ExecuteResponse::deserialize_json(&json).map_err(|e| e.to_string())?;
+    if let Some(error) = response.error {
+        return Err(error);
+    }
+    Ok(format!("{}{}", response.stderr, response.stdout))
+}
+
+/// Height that the output box takes up below the code box, including the
+/// space between them.
+pub fn output_height(output: Option<&CodeBox>, scale: f32) -> f32 {
+    output
+        .map(|output| output.height_with_padding() + OUTPUT_MARGIN * scale)
+        .unwrap_or(0.)
+}
+
+/// Draw the output box below the code box that is drawn at the position.
+pub fn draw_output(output: &CodeBox, codebox: &CodeBox, xpos: f32, ypos: f32, scale: f32) {
+    output.draw(
+        xpos,
+        ypos + codebox.height_with_padding() + OUTPUT_MARGIN * scale,
+    );
+}
@@ -0,0 +1,137 @@
+use {
+    crate::line_ranges::{LinePosition, LineRanges},
+    macroquad::prelude::*,
+    rhai::{Engine, EvalAltResult, Scope, AST},
+    std::{cell::RefCell, rc::Rc},
+};
+
+/// A change to the presentation requested by a script.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Show the next file
+    Next,
+    /// Show the previous file
+    Previous,
+    /// Show the file at the index
+    Show(usize),
+    /// Highlight these lines of the code
+    Highlight(LineRanges),
+    /// Change the font size of the code
+    FontSize(f32),
+    /// Scroll to a line and flash it
+    GotoLine(LinePosition),
+}
+
+type Actions = Rc<RefCell<Vec<Action>>>;
+
+/// Most operations a single call of the script may run, so that an endless
+/// loop in a handler doesn't freeze the presentation.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A Rhai script that reacts to keypresses and frame ticks, for scripted
+/// code walkthroughs. The script may define these functions:
+///
+/// - `on_key(key)` is called with the name of each pressed key, like `"Right"`
+/// - `on_frame(time, file)` is called each frame with the seconds since the
+///   program started and the index of the displayed file
+///
+/// They control the presentation by calling `next()`, `previous()`,
+/// `show(file)`, `highlight("3,7-12")`, `font_size(24.0)` and
+/// `goto_line("120")`.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    actions: Actions,
+}
+
+impl Script {
+    /// Load and compile the script, and run its top level statements.
+    pub async fn load(path: &str) -> Result<Self, String> {
+        let source = load_string(path)
+            .await
+            .map_err(|e| format!("Couldn't load script {}: {}", path, e))?;
+        let actions = Actions::default();
+        let engine = engine(&actions);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("Couldn't compile script {}: {}", path, e))?;
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| format!("Couldn't run script {}: {}", path, e))?;
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            actions,
+        })
+    }
+
+    /// Let the script react to a pressed key.
+    pub fn on_key(&mut self, key: KeyCode) -> Vec<Action> {
+        self.call("on_key", (format!("{:?}", key),))
+    }
+
+    /// Let the script react to a frame being drawn.
+    pub fn on_frame(&mut self, time: f64, file: usize) -> Vec<Action> {
+        self.call("on_frame", (time, file as i64))
+    }
+
+    /// Call a function of the script, if it is defined, and return the
+    /// actions it requested. Errors are logged, so that a broken handler
+    /// doesn't stop the presentation.
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) -> Vec<Action> {
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == name);
+        if defined {
+            if let Err(e) =
+                self.engine
+                    .call_fn::<rhai::Dynamic>(&mut self.scope, &self.ast, name, args)
+            {
+                error!("Script error in {}: {}", name, e);
+            }
+        }
+        self.actions.take()
+    }
+}
+
+/// Engine with the functions that scripts control the presentation with.
+fn engine(actions: &Actions) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| info!("{}", text));
+    let push = |action: Action, actions: &Actions| actions.borrow_mut().push(action);
+
+    let queue = actions.clone();
+    engine.register_fn("next", move || push(Action::Next, &queue));
+    let queue = actions.clone();
+    engine.register_fn("previous", move || push(Action::Previous, &queue));
+    let queue = actions.clone();
+    engine.register_fn("show", move |file: i64| {
+        push(Action::Show(file.max(0) as usize), &queue)
+    });
+    let queue = actions.clone();
+    engine.register_fn(
+        "highlight",
+        move |lines: &str| -> Result<(), Box<EvalAltResult>> {
+            push(Action::Highlight(lines.parse()?), &queue);
+            Ok(())
+        },
+    );
+    let queue = actions.clone();
+    engine.register_fn("font_size", move |size: f64| {
+        push(Action::FontSize(size as f32), &queue)
+    });
+    let queue = actions.clone();
+    engine.register_fn(
+        "goto_line",
+        move |position: &str| -> Result<(), Box<EvalAltResult>> {
+            push(Action::GotoLine(position.parse()?), &queue);
+            Ok(())
+        },
+    );
+    engine
+}
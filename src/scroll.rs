@@ -0,0 +1,185 @@
+use macroquad::prelude::*;
+
+/// Default space between the code box and the edge of the screen when it
+/// doesn't fit.
+pub const SCROLL_MARGIN: f32 = 20.;
+
+/// Number of lines per second to scroll while an arrow key is held down.
+const LINES_PER_SECOND: f32 = 20.;
+
+/// Factor to zoom by for each step of the mouse wheel.
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 8.;
+
+/// Fraction of the speed that is left after one second of inertial scrolling.
+const INERTIA_FRICTION: f32 = 0.02;
+/// Inertial scrolling stops below this speed, in pixels per second.
+const MIN_INERTIA_SPEED: f32 = 5.;
+
+/// Scroll position and zoom of a code box that is larger than the screen.
+#[derive(Debug, Default)]
+pub struct Scroll {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+    /// Number of lines to scroll for each step of the mouse wheel.
+    wheel_speed: f32,
+    /// Space between the code box and the edge of the screen when it doesn't fit.
+    margin: f32,
+    /// Height at the top of the screen covered by other things, like a tab bar.
+    top: f32,
+    /// Mouse position in the previous frame while dragging.
+    drag_position: Option<Vec2>,
+    /// Speed of the scrolling after the mouse button has been released.
+    velocity: Vec2,
+}
+
+impl Scroll {
+    pub fn new(wheel_speed: f32, margin: f32) -> Self {
+        Self {
+            wheel_speed,
+            margin,
+            zoom: 1.,
+            ..Default::default()
+        }
+    }
+
+    /// Scroll vertically with the mouse wheel, or horizontally while shift
+    /// is held down. Horizontal trackpad gestures scroll horizontally.
+    /// Zooms around the mouse cursor while control is held down.
+    pub fn handle_mouse_wheel(&mut self, line_height: f32) {
+        let (wheel_x, wheel_y) = mouse_wheel();
+        let step = line_height * self.wheel_speed;
+        if wheel_y != 0.
+            && (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl))
+        {
+            self.zoom_at(mouse_position(), wheel_y.signum());
+            return;
+        }
+        if wheel_y != 0. {
+            if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                self.x -= wheel_y.signum() * step;
+            } else {
+                self.y -= wheel_y.signum() * step;
+            }
+        }
+        if wheel_x != 0. {
+            self.x -= wheel_x.signum() * step;
+        }
+    }
+
+    /// Pan the code by dragging it with the left mouse button. The code
+    /// keeps moving and slows down after the button is released.
+    pub fn handle_mouse_drag(&mut self) {
+        let frame_time = get_frame_time().max(f32::EPSILON);
+        let mouse = Vec2::from(mouse_position());
+        if is_mouse_button_down(MouseButton::Left) {
+            if let Some(last_position) = self.drag_position {
+                let delta = (mouse - last_position) / self.zoom;
+                self.x -= delta.x;
+                self.y -= delta.y;
+                self.velocity = delta / frame_time;
+            }
+            self.drag_position = Some(mouse);
+            return;
+        }
+        self.drag_position = None;
+        if self.velocity.length() < MIN_INERTIA_SPEED {
+            self.velocity = Vec2::ZERO;
+            return;
+        }
+        self.x -= self.velocity.x * frame_time;
+        self.y -= self.velocity.y * frame_time;
+        self.velocity *= INERTIA_FRICTION.powf(frame_time);
+    }
+
+    /// Keep the code box below this height at the top of the screen.
+    pub fn set_top(&mut self, top: f32) {
+        self.top = top;
+    }
+
+    /// Scroll vertically so that `y`, relative to the top of the box, is in
+    /// the middle of the screen.
+    pub fn center_on(&mut self, y: f32) {
+        self.y = y + self.margin - screen_height() / self.zoom / 2.;
+    }
+
+    /// Zoom one step in or out, keeping the code under the cursor in place.
+    fn zoom_at(&mut self, (mouse_x, mouse_y): (f32, f32), direction: f32) {
+        let zoom = (self.zoom * ZOOM_STEP.powf(direction)).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.x += mouse_x / self.zoom - mouse_x / zoom;
+        self.y += mouse_y / self.zoom - mouse_y / zoom;
+        self.zoom = zoom;
+    }
+
+    pub fn handle_keys(&mut self, line_height: f32) {
+        let page_height = screen_height() / self.zoom - self.margin * 2. - line_height;
+        if is_key_down(KeyCode::Down) {
+            self.y += line_height * LINES_PER_SECOND * get_frame_time();
+        }
+        if is_key_down(KeyCode::Up) {
+            self.y -= line_height * LINES_PER_SECOND * get_frame_time();
+        }
+        if is_key_pressed(KeyCode::PageDown) {
+            self.y += page_height;
+        }
+        if is_key_pressed(KeyCode::PageUp) {
+            self.y -= page_height;
+        }
+        if is_key_pressed(KeyCode::Home) {
+            self.y = 0.;
+        }
+        if is_key_pressed(KeyCode::End) {
+            self.y = f32::MAX;
+        }
+    }
+
+    /// Clamp the scroll position to the size of the box and return the
+    /// position on screen to draw the box at.
+    pub fn position(&mut self, width: f32, height: f32) -> (f32, f32) {
+        let screen = Rect::new(
+            0.,
+            self.top / self.zoom,
+            screen_width() / self.zoom,
+            (screen_height() - self.top) / self.zoom,
+        );
+        self.position_in(screen, width, height)
+    }
+
+    /// Camera that scales the drawing by the zoom, with the origin in the
+    /// top left corner of the screen.
+    pub fn camera(&self) -> Camera2D {
+        Camera2D {
+            target: vec2(
+                screen_width() / (2. * self.zoom),
+                screen_height() / (2. * self.zoom),
+            ),
+            zoom: vec2(
+                2. * self.zoom / screen_width(),
+                -2. * self.zoom / screen_height(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// Clamp the scroll position to the size of the box and return the
+    /// position to draw the box at inside the viewport.
+    pub fn position_in(&mut self, viewport: Rect, width: f32, height: f32) -> (f32, f32) {
+        let max_x = (width + self.margin * 2. - viewport.w).max(0.);
+        let max_y = (height + self.margin * 2. - viewport.h).max(0.);
+        self.x = self.x.clamp(0., max_x);
+        self.y = self.y.clamp(0., max_y);
+        let xpos = if max_x > 0. {
+            viewport.x + self.margin - self.x
+        } else {
+            viewport.x + viewport.w / 2. - width / 2.
+        };
+        let ypos = if max_y > 0. {
+            viewport.y + self.margin - self.y
+        } else {
+            viewport.y + viewport.h / 2. - height / 2.
+        };
+        (xpos, ypos)
+    }
+}
@@ -0,0 +1,205 @@
+use {
+    crate::{codebox::CodeBox, scroll::Scroll},
+    macroquad::prelude::*,
+};
+
+const FONT_SIZE: u16 = 28;
+const INPUT_HEIGHT: f32 = 44.;
+const INPUT_BACKGROUND_COLOR: Color = Color::new(0.1, 0.1, 0.12, 0.9);
+const MATCH_COLOR: Color = Color::new(1., 0.85, 0.2, 0.3);
+const CURRENT_MATCH_COLOR: Color = Color::new(1., 0.6, 0.1, 0.6);
+
+/// A match of the search query, as the index of the line in the code box,
+/// and the column and number of characters of the match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Match {
+    line: usize,
+    column: usize,
+    length: usize,
+}
+
+/// Search for text in the code box. Press `/` to type the query, then jump
+/// between the matches with `n` and `N`.
+#[derive(Debug, Default)]
+pub struct Search {
+    query: String,
+    typing: bool,
+    current: usize,
+    /// Matches in the code box, found once per frame.
+    matches: Vec<Match>,
+}
+
+impl Search {
+    /// Handle typing of the query. Returns true if the keyboard has been used
+    /// by the search input this frame, so that keys aren't used for anything else.
+    pub fn handle_keys(&mut self) -> bool {
+        if !self.typing {
+            if is_key_pressed(KeyCode::Slash) {
+                self.typing = true;
+                self.query.clear();
+                self.current = 0;
+                // Skip the slash that opened the search
+                while get_char_pressed().is_some() {}
+                return true;
+            }
+            return false;
+        }
+        while let Some(character) = get_char_pressed() {
+            if !character.is_control() {
+                self.query.push(character);
+                self.current = 0;
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.query.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            self.typing = false;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.typing = false;
+            self.query.clear();
+        }
+        true
+    }
+
+    /// Jump to the next match with `n`, or the previous with `N`.
+    pub fn handle_navigation(&mut self, codebox: &CodeBox, scroll: &mut Scroll) {
+        let count = self.matches.len();
+        if self.typing || count == 0 || !is_key_pressed(KeyCode::N) {
+            return;
+        }
+        self.current = match is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+            true => (self.current + count - 1) % count,
+            false => (self.current + 1) % count,
+        };
+        let found = self.matches[self.current];
+        scroll.center_on(codebox.line_top(0., found.line));
+    }
+
+    /// Find the case insensitive matches of the query on each line of the
+    /// code box, once per frame before they are navigated or drawn.
+    pub fn find_matches(&mut self, codebox: Option<&CodeBox>) {
+        let query: Vec<char> = self.query.chars().map(fold_case).collect();
+        let length = query.len();
+        self.matches = match (codebox, query.is_empty()) {
+            (Some(codebox), false) => codebox
+                .lines()
+                .iter()
+                .enumerate()
+                .flat_map(|(index, line)| {
+                    line_matches(&line.text(), &query)
+                        .into_iter()
+                        .map(move |column| Match {
+                            line: index,
+                            column,
+                            length,
+                        })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Draw highlights of the matches on top of the code box drawn at `xpos`, `ypos`.
+    pub fn draw_matches(&self, codebox: &CodeBox, xpos: f32, ypos: f32) {
+        for (index, found) in self.matches.iter().enumerate() {
+            let line = &codebox.lines()[found.line];
+            let x = codebox.column_x(xpos, line, found.column);
+            let width = codebox.column_x(xpos, line, found.column + found.length) - x;
+            let color = match index == self.current {
+                true => CURRENT_MATCH_COLOR,
+                false => MATCH_COLOR,
+            };
+            draw_rectangle(
+                x,
+                codebox.line_top(ypos, found.line),
+                width,
+                codebox.line_height(),
+                color,
+            );
+        }
+    }
+
+    /// Draw the search input at the bottom of the screen while typing.
+    pub fn draw_input(&self) {
+        if !self.typing {
+            return;
+        }
+        let top = screen_height() - INPUT_HEIGHT;
+        draw_rectangle(
+            0.,
+            top,
+            screen_width(),
+            INPUT_HEIGHT,
+            INPUT_BACKGROUND_COLOR,
+        );
+        let text = format!("/{}   {} matches", self.query, self.matches.len());
+        draw_text(
+            &text,
+            INPUT_HEIGHT / 2.,
+            top + INPUT_HEIGHT * 0.7,
+            FONT_SIZE as f32,
+            WHITE,
+        );
+    }
+}
+
+/// Columns where the query starts in the text, without overlapping. The
+/// characters are compared one by one, so that the columns stay those of the
+/// text even where lowercasing changes the number of characters.
+fn line_matches(text: &str, query: &[char]) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let text: Vec<char> = text.chars().map(fold_case).collect();
+    let mut columns = Vec::new();
+    let mut column = 0;
+    while column + query.len() <= text.len() {
+        if text[column..column + query.len()] == *query {
+            columns.push(column);
+            column += query.len();
+        } else {
+            column += 1;
+        }
+    }
+    columns
+}
+
+/// The lowercase character, or the character itself where its lowercase is
+/// more than one character, like `İ`.
+fn fold_case(c: char) -> char {
+    let mut lowercase = c.to_lowercase();
+    match (lowercase.next(), lowercase.next()) {
+        (Some(lowercase), None) => lowercase,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(text: &str, query: &str) -> Vec<usize> {
+        let query: Vec<char> = query.chars().map(fold_case).collect();
+        line_matches(text, &query)
+    }
+
+    #[test]
+    fn matches_case_insensitively() {
+        assert_eq!(matches("let Value = value;", "VALUE"), [4, 12]);
+    }
+
+    #[test]
+    fn keeps_the_columns_of_characters_with_longer_lowercase() {
+        assert_eq!(fold_case('İ'), 'İ');
+        assert_eq!(matches("İstanbul == istanbul", "istanbul"), [12]);
+        assert_eq!(matches("\"İ\" + name", "name"), [6]);
+    }
+
+    #[test]
+    fn skips_overlapping_matches() {
+        assert_eq!(matches("aaaa", "aa"), [0, 2]);
+        assert_eq!(matches("abc", ""), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,172 @@
+use {
+    crate::{codebox::CodeBox, contrast::extreme_for, scroll::Scroll},
+    macroquad::prelude::*,
+};
+
+const SELECTION_COLOR: Color = Color::new(0.3, 0.5, 1., 0.35);
+/// Width of the outline of the selection in high contrast.
+const SELECTION_OUTLINE_WIDTH: f32 = 2.;
+
+/// A position in the code box, as the index of the line and the column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+/// Text selected with the mouse. Dragging while holding shift selects
+/// characters, and while holding control selects whole lines.
+#[derive(Debug, Default)]
+pub struct Selection {
+    anchor: Option<Position>,
+    cursor: Position,
+    whole_lines: bool,
+    dragging: bool,
+}
+
+impl Selection {
+    /// Update the selection with the mouse, for a code box drawn at `xpos`,
+    /// `ypos`. Returns true while selecting, so the mouse isn't used to pan.
+    pub fn handle_mouse(
+        &mut self,
+        codebox: &CodeBox,
+        scroll: &Scroll,
+        xpos: f32,
+        ypos: f32,
+    ) -> bool {
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        let mouse = scroll
+            .camera()
+            .screen_to_world(Vec2::from(mouse_position()));
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if !(shift || control) {
+                self.anchor = None;
+                return false;
+            }
+            let position = hit_test(codebox, xpos, ypos, mouse);
+            self.anchor = Some(position);
+            self.cursor = position;
+            self.whole_lines = control;
+            self.dragging = true;
+            return true;
+        }
+        if !self.dragging {
+            return false;
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.dragging = false;
+            return false;
+        }
+        self.cursor = hit_test(codebox, xpos, ypos, mouse);
+        true
+    }
+
+    /// The start and end of the selection, in order.
+    fn range(&self, codebox: &CodeBox) -> Option<(Position, Position)> {
+        let anchor = self.anchor?;
+        let (start, end) = (anchor.min(self.cursor), anchor.max(self.cursor));
+        match self.whole_lines {
+            true => {
+                let end_column = codebox.lines().get(end.line)?.char_count();
+                Some((
+                    Position {
+                        line: start.line,
+                        column: 0,
+                    },
+                    Position {
+                        line: end.line,
+                        column: end_column,
+                    },
+                ))
+            }
+            false if start == end => None,
+            false => Some((start, end)),
+        }
+    }
+
+    /// The selected text, with soft wrapped lines joined again.
+    pub fn text(&self, codebox: &CodeBox) -> Option<String> {
+        let (start, end) = self.range(codebox)?;
+        let mut text = String::new();
+        for (index, line) in codebox
+            .lines()
+            .iter()
+            .enumerate()
+            .skip(start.line)
+            .take(end.line + 1 - start.line)
+        {
+            if index > start.line && !line.continuation {
+                text.push('\n');
+            }
+            let first = if index == start.line { start.column } else { 0 };
+            let last = if index == end.line {
+                end.column
+            } else {
+                usize::MAX
+            };
+            text.extend(
+                line.text()
+                    .chars()
+                    .skip(first)
+                    .take(last.saturating_sub(first)),
+            );
+        }
+        Some(text)
+    }
+
+    /// Draw the selection on top of the code box drawn at `xpos`, `ypos`.
+    pub fn draw(&self, codebox: &CodeBox, xpos: f32, ypos: f32) {
+        let Some((start, end)) = self.range(codebox) else {
+            return;
+        };
+        for (index, line) in codebox
+            .lines()
+            .iter()
+            .enumerate()
+            .skip(start.line)
+            .take(end.line + 1 - start.line)
+        {
+            let first = if index == start.line { start.column } else { 0 };
+            // Selections continuing on the next line include the line break
+            let last = match index == end.line {
+                true => end.column,
+                false => line.char_count() + 1,
+            };
+            let x = codebox.column_x(xpos, line, first);
+            let (y, width) = (
+                codebox.line_top(ypos, index),
+                codebox.column_x(xpos, line, last) - x,
+            );
+            // Tinting the code would lower the contrast of the text, so the
+            // selection is outlined instead
+            match codebox.high_contrast() {
+                true => draw_rectangle_lines(
+                    x,
+                    y,
+                    width,
+                    codebox.line_height(),
+                    SELECTION_OUTLINE_WIDTH,
+                    extreme_for(codebox.background_color()),
+                ),
+                false => draw_rectangle(x, y, width, codebox.line_height(), SELECTION_COLOR),
+            }
+        }
+    }
+}
+
+/// The line and column of the character boundary closest to the point.
+fn hit_test(codebox: &CodeBox, xpos: f32, ypos: f32, point: Vec2) -> Position {
+    let last_line = codebox.lines().len().saturating_sub(1);
+    let line = ((point.y - codebox.line_top(ypos, 0)) / codebox.line_height()).max(0.) as usize;
+    let line = line.min(last_line);
+    let column = codebox
+        .lines()
+        .get(line)
+        .map(|code_line| {
+            let column = (point.x - codebox.column_x(xpos, code_line, 0)) / codebox.char_width();
+            code_line.char_index_at(column)
+        })
+        .unwrap_or(0);
+    Position { line, column }
+}
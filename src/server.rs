@@ -0,0 +1,202 @@
+use {
+    macroquad::prelude::*,
+    nanoserde::DeJson,
+    std::{
+        error::Error,
+        io::Read,
+        sync::mpsc::{self, Receiver, Sender},
+        thread,
+    },
+    tiny_http::{Header, Method, Request, Response, Server},
+};
+
+/// Largest request body that is read, in bytes.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+/// Largest width and height of a rendered code box, in pixels, to keep the
+/// texture it is rendered to within what graphics cards support.
+pub const MAX_IMAGE_SIZE: f32 = 8192.;
+
+/// Code to render, posted as JSON.
+#[derive(Debug, DeJson)]
+pub struct RenderPayload {
+    pub code: String,
+    /// Language of the code, defaults to the language of the server options,
+    /// or a modeline in the code.
+    #[nserde(default)]
+    pub language: Option<String>,
+    /// Name of a bundled theme, defaults to the theme of the server options.
+    #[nserde(default)]
+    pub theme: Option<String>,
+}
+
+/// Code to display instead of the current file, posted as JSON.
+#[derive(Debug, DeJson)]
+pub struct CodePayload {
+    pub code: String,
+    /// Language of the code, defaults to the language option, or a modeline
+    /// in the code.
+    #[nserde(default)]
+    pub language: Option<String>,
+}
+
+/// A posted payload, which is answered once it has been handled on the main
+/// thread.
+pub struct PostRequest<T> {
+    pub payload: T,
+    request: Request,
+}
+
+impl<T> PostRequest<T> {
+    pub fn respond_png(self, png: Vec<u8>) {
+        let response = Response::from_data(png).with_header(content_type("image/png"));
+        if let Err(e) = self.request.respond(response) {
+            warn!("Couldn't send the rendered image: {}", e);
+        }
+    }
+
+    pub fn respond_ok(self) {
+        respond(self.request, 200, "ok");
+    }
+
+    pub fn respond_error(self, status: u16, message: &str) {
+        respond(self.request, status, message);
+    }
+}
+
+/// HTTP server that accepts JSON payloads posted to a single path. The
+/// requests are received on a background thread, and handled on the main
+/// thread which owns the graphics context and the displayed code.
+pub struct PostServer<T> {
+    receiver: Receiver<PostRequest<T>>,
+}
+
+impl<T: DeJson + Send + 'static> PostServer<T> {
+    pub fn listen(address: &str, path: &'static str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let server = Server::http(address)?;
+        info!("Listening for code posted to http://{}{}", address, path);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle_request(request, path, &sender);
+            }
+        });
+        Ok(Self { receiver })
+    }
+
+    /// Requests received since the last call.
+    pub fn requests(&self) -> Vec<PostRequest<T>> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+fn handle_request<T: DeJson>(mut request: Request, path: &str, sender: &Sender<PostRequest<T>>) {
+    let requested_path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if requested_path != path {
+        return respond(request, 404, &format!("Not found, POST code to {}", path));
+    }
+    if request.method() != &Method::Post {
+        return respond(request, 405, "Use POST to send code");
+    }
+    // Browsers post plain text across origins without asking first, so only
+    // JSON from the server's own origin, or from outside a browser, is taken
+    let json = header(&request, "Content-Type")
+        .and_then(|value| value.split(';').next().map(str::trim).map(str::to_string))
+        .is_some_and(|mime| mime.eq_ignore_ascii_case("application/json"));
+    if !json {
+        return respond(
+            request,
+            415,
+            "Send code with Content-Type: application/json",
+        );
+    }
+    if let Some(origin) = header(&request, "Origin") {
+        let host = header(&request, "Host").unwrap_or_default();
+        if !is_same_origin(&origin, &host) {
+            return respond(request, 403, "Requests from other origins aren't allowed");
+        }
+    }
+    let too_large = format!("Send at most {} bytes of JSON", MAX_BODY_SIZE);
+    if request
+        .body_length()
+        .is_some_and(|length| length as u64 > MAX_BODY_SIZE)
+    {
+        return respond(request, 413, &too_large);
+    }
+    // Bodies without a length are read one byte past the limit to tell
+    // whether they are too large
+    let mut body = String::new();
+    if let Err(e) = request
+        .as_reader()
+        .take(MAX_BODY_SIZE + 1)
+        .read_to_string(&mut body)
+    {
+        return respond(request, 400, &format!("Couldn't read the request: {}", e));
+    }
+    if body.len() as u64 > MAX_BODY_SIZE {
+        return respond(request, 413, &too_large);
+    }
+    match T::deserialize_json(&body) {
+        Ok(payload) => {
+            // The receiver is only dropped when the program exits
+            let _ = sender.send(PostRequest { payload, request });
+        }
+        Err(e) => respond(request, 400, &format!("Couldn't parse JSON: {}", e)),
+    }
+}
+
+/// Value of the first header with the name.
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.to_string())
+}
+
+/// True if the origin of a request is the host that the request was sent
+/// to, like `http://localhost:8080` for `localhost:8080`.
+pub(crate) fn is_same_origin(origin: &str, host: &str) -> bool {
+    let origin = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"));
+    !host.is_empty() && origin.is_some_and(|origin| origin.eq_ignore_ascii_case(host))
+}
+
+/// Send a plain text response.
+fn respond(request: Request, status: u16, message: &str) {
+    let response = Response::from_string(message)
+        .with_status_code(status)
+        .with_header(content_type("text/plain; charset=utf-8"));
+    if let Err(e) = request.respond(response) {
+        warn!("Couldn't send the response: {}", e);
+    }
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("Content types are valid headers")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_origin_of_the_host() {
+        assert!(is_same_origin("http://localhost:8080", "localhost:8080"));
+        assert!(is_same_origin("https://Example.com", "example.com"));
+    }
+
+    #[test]
+    fn rejects_other_origins() {
+        assert!(!is_same_origin("http://example.com", "localhost:8080"));
+        assert!(!is_same_origin("http://localhost:8081", "localhost:8080"));
+        assert!(!is_same_origin("localhost:8080", "localhost:8080"));
+        assert!(!is_same_origin("null", "localhost:8080"));
+        assert!(!is_same_origin("http://", ""));
+    }
+}
@@ -0,0 +1,150 @@
+/// How an Arabic letter connects to the letters around it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Joining {
+    /// Connects to the letters on both sides
+    Dual,
+    /// Only connects to the letter before it
+    Right,
+    /// Marks that the letters around them connect through
+    Transparent,
+    None,
+}
+
+/// The presentation form of an Arabic letter, depending on the letters it
+/// connects to.
+#[derive(Clone, Copy)]
+enum Form {
+    Isolated,
+    Final,
+    Initial,
+    Medial,
+}
+
+/// Replace the Arabic letters in the text, given in logical order, with
+/// their contextual forms from the Arabic Presentation Forms-B block, so
+/// that they are drawn connected without a text shaper. The number of
+/// characters is kept, so lam-alef pairs aren't joined into ligatures.
+pub fn shape_arabic(chars: &[char]) -> Vec<char> {
+    let joinings: Vec<Joining> = chars.iter().map(|c| joining(*c)).collect();
+    // The letters before and after each character, skipping marks
+    let neighbour = |index: usize, step: isize| {
+        let mut index = index as isize + step;
+        while let Some(joining) = usize::try_from(index).ok().and_then(|i| joinings.get(i)) {
+            if *joining != Joining::Transparent {
+                return *joining;
+            }
+            index += step;
+        }
+        Joining::None
+    };
+    chars
+        .iter()
+        .enumerate()
+        .map(|(index, c)| {
+            let joins = matches!(joinings[index], Joining::Dual | Joining::Right);
+            if !joins {
+                return *c;
+            }
+            let joins_previous = neighbour(index, -1) == Joining::Dual;
+            let joins_next = joinings[index] == Joining::Dual
+                && matches!(neighbour(index, 1), Joining::Dual | Joining::Right);
+            let form = match (joins_previous, joins_next) {
+                (false, false) => Form::Isolated,
+                (true, false) => Form::Final,
+                (false, true) => Form::Initial,
+                (true, true) => Form::Medial,
+            };
+            presentation_form(*c, form).unwrap_or(*c)
+        })
+        .collect()
+}
+
+fn joining(c: char) -> Joining {
+    match c {
+        '\u{0622}'..='\u{0625}'
+        | '\u{0627}'
+        | '\u{0629}'
+        | '\u{062F}'..='\u{0632}'
+        | '\u{0648}'
+        | '\u{0649}' => Joining::Right,
+        '\u{0626}'
+        | '\u{0628}'
+        | '\u{062A}'..='\u{062E}'
+        | '\u{0633}'..='\u{063A}'
+        | '\u{0640}'..='\u{0647}'
+        | '\u{064A}' => Joining::Dual,
+        '\u{064B}'..='\u{065F}' | '\u{0670}' => Joining::Transparent,
+        _ => Joining::None,
+    }
+}
+
+/// The character of the letter in the form, if the block has one.
+fn presentation_form(c: char, form: Form) -> Option<char> {
+    // Isolated form of each letter from hamza to yeh, or 0 for letters
+    // without presentation forms
+    const ISOLATED: [u32; 42] = [
+        0xFE80, 0xFE81, 0xFE83, 0xFE85, 0xFE87, 0xFE89, 0xFE8D, 0xFE8F, 0xFE93, 0xFE95, 0xFE99,
+        0xFE9D, 0xFEA1, 0xFEA5, 0xFEA9, 0xFEAB, 0xFEAD, 0xFEAF, 0xFEB1, 0xFEB5, 0xFEB9, 0xFEBD,
+        0xFEC1, 0xFEC5, 0xFEC9, 0xFECD, 0, 0, 0, 0, 0, 0, 0xFED1, 0xFED5, 0xFED9, 0xFEDD, 0xFEE1,
+        0xFEE5, 0xFEE9, 0xFEED, 0xFEEF, 0xFEF1,
+    ];
+    let isolated = *ISOLATED.get((c as u32).checked_sub(0x0621)? as usize)?;
+    if isolated == 0 {
+        return None;
+    }
+    let offset = match (form, joining(c)) {
+        (Form::Isolated, _) => 0,
+        (Form::Final, _) => 1,
+        (Form::Initial, Joining::Dual) => 2,
+        (Form::Medial, Joining::Dual) => 3,
+        // Letters that only join the letter before them have no initial or
+        // medial forms
+        (Form::Initial, _) => 0,
+        (Form::Medial, _) => 1,
+    };
+    char::from_u32(isolated + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(text: &str) -> String {
+        shape_arabic(&text.chars().collect::<Vec<char>>())
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn joins_letters_on_both_sides() {
+        // Beh, seen and meem
+        assert_eq!(
+            shape("\u{0628}\u{0633}\u{0645}"),
+            "\u{FE91}\u{FEB4}\u{FEE2}"
+        );
+    }
+
+    #[test]
+    fn letters_joining_only_the_letter_before_end_words() {
+        // Beh and alef, then dal that alef doesn't join
+        assert_eq!(
+            shape("\u{0628}\u{0627}\u{062F}"),
+            "\u{FE91}\u{FE8E}\u{FEA9}"
+        );
+    }
+
+    #[test]
+    fn joins_letters_across_marks() {
+        // Beh with fatha, then beh
+        assert_eq!(
+            shape("\u{0628}\u{064E}\u{0628}"),
+            "\u{FE91}\u{064E}\u{FE90}"
+        );
+    }
+
+    #[test]
+    fn keeps_other_text() {
+        assert_eq!(shape("let x = 1;"), "let x = 1;");
+        assert_eq!(shape("\u{0628} \u{0628}"), "\u{FE8F} \u{FE8F}");
+    }
+}
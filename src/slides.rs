@@ -0,0 +1,74 @@
+use crate::{codebox::CodeBox, Code, CodeSource, Result};
+
+/// A code box and the source its code was loaded from.
+pub struct Slide {
+    pub source: CodeSource,
+    /// The loaded code, kept to rebuild the code box when the theme changes.
+    pub code: Option<Code>,
+    pub codebox: Result<CodeBox>,
+}
+
+/// The loaded slides and which of them is currently displayed.
+pub struct Slides {
+    slides: Vec<Slide>,
+    current: usize,
+}
+
+impl Slides {
+    /// Create slides from a list, which must contain at least one slide.
+    pub fn new(slides: Vec<Slide>) -> Self {
+        assert!(!slides.is_empty(), "At least one slide is needed");
+        Self { slides, current: 0 }
+    }
+
+    pub fn current(&self) -> &Slide {
+        &self.slides[self.current]
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Show the slide at `index`, returns true if the current slide changed.
+    pub fn set_current(&mut self, index: usize) -> bool {
+        if index < self.slides.len() && index != self.current {
+            self.current = index;
+            return true;
+        }
+        false
+    }
+
+    pub fn current_mut(&mut self) -> &mut Slide {
+        &mut self.slides[self.current]
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Slide> {
+        self.slides.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Slide> {
+        self.slides.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Slide> {
+        self.slides.iter_mut()
+    }
+
+    /// Move to the next slide, returns true if the current slide changed.
+    pub fn next_slide(&mut self) -> bool {
+        if self.current + 1 < self.slides.len() {
+            self.current += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Move to the previous slide, returns true if the current slide changed.
+    pub fn previous_slide(&mut self) -> bool {
+        if self.current > 0 {
+            self.current -= 1;
+            return true;
+        }
+        false
+    }
+}
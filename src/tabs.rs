@@ -0,0 +1,110 @@
+use {
+    crate::code_theme::{hex_color, CodeTheme},
+    macroquad::prelude::*,
+};
+
+const FONT_SIZE: f32 = 18.;
+/// Space around the title of each tab.
+const TAB_PADDING: f32 = 14.;
+/// Height of the tab bar, in number of lines of the font.
+const TAB_BAR_LINES: f32 = 2.;
+/// Tabs with longer titles are cut off.
+const MAX_TITLE_CHARS: usize = 32;
+
+/// Strip at the top of the screen with a tab for each loaded file.
+pub struct TabBar {
+    titles: Vec<String>,
+    font_size: u16,
+    height: f32,
+    background_color: Color,
+    active_color: Color,
+    text_color: Color,
+}
+
+impl TabBar {
+    pub fn new(titles: Vec<String>, code_theme: &CodeTheme, scale: f32) -> Self {
+        let font_size = FONT_SIZE * scale;
+        Self {
+            titles: titles.into_iter().map(shorten).collect(),
+            font_size: font_size as u16,
+            height: font_size * TAB_BAR_LINES,
+            background_color: hex_color(&code_theme.header_background_color),
+            active_color: hex_color(&code_theme.code_background_color),
+            text_color: hex_color(&code_theme.header_text_color),
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Switch to the next tab with Ctrl+Tab, the previous with
+    /// Ctrl+Shift+Tab, or the tab that is clicked. Returns the index of the
+    /// tab to switch to.
+    pub fn handle_input(&self, current: usize) -> Option<usize> {
+        let count = self.titles.len();
+        let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        if control && is_key_pressed(KeyCode::Tab) {
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            return Some(match shift {
+                true => (current + count - 1) % count,
+                false => (current + 1) % count,
+            });
+        }
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let mouse = Vec2::from(mouse_position());
+            return self.tabs().iter().position(|tab| tab.contains(mouse));
+        }
+        None
+    }
+
+    pub fn draw(&self, current: usize) {
+        draw_rectangle(0., 0., screen_width(), self.height, self.background_color);
+        for (index, (tab, title)) in self.tabs().iter().zip(self.titles.iter()).enumerate() {
+            if index == current {
+                draw_rectangle(tab.x, tab.y, tab.w, tab.h, self.active_color);
+            }
+            let color = match index == current {
+                true => self.text_color,
+                false => Color {
+                    a: self.text_color.a * 0.6,
+                    ..self.text_color
+                },
+            };
+            let dimensions = measure_text(title, None, self.font_size, 1.);
+            draw_text(
+                title,
+                tab.x + TAB_PADDING,
+                tab.y + (tab.h + dimensions.offset_y) / 2.,
+                self.font_size as f32,
+                color,
+            );
+        }
+    }
+
+    /// Area of each tab, next to each other from the left edge.
+    fn tabs(&self) -> Vec<Rect> {
+        let mut x = 0.;
+        self.titles
+            .iter()
+            .map(|title| {
+                let width = measure_text(title, None, self.font_size, 1.).width + TAB_PADDING * 2.;
+                let tab = Rect::new(x, 0., width, self.height);
+                x += width;
+                tab
+            })
+            .collect()
+    }
+}
+
+/// Cut off long titles, the default font only has ASCII so three dots
+/// show that the title goes on.
+fn shorten(title: String) -> String {
+    match title.chars().count() > MAX_TITLE_CHARS {
+        true => {
+            let start: String = title.chars().take(MAX_TITLE_CHARS - 3).collect();
+            format!("{}...", start)
+        }
+        false => title,
+    }
+}
@@ -0,0 +1,74 @@
+use {crate::auto_advance::Interval, macroquad::prelude::*};
+
+const FONT_SIZE: f32 = 24.;
+/// Distance between the timer and the edges of the screen.
+const TIMER_MARGIN: f32 = 12.;
+/// Fraction of the talk left when the countdown turns to the warning color.
+const WARNING_FRACTION: f64 = 0.1;
+const TEXT_COLOR: Color = Color::new(1., 1., 1., 0.7);
+const WARNING_COLOR: Color = Color::new(1., 0.75, 0., 0.9);
+const OVERTIME_COLOR: Color = Color::new(1., 0.25, 0.2, 0.9);
+
+/// Clock in the top left corner that shows the time since the presentation
+/// started, or counts down the time left of the talk.
+pub struct Timer {
+    talk_length: Option<Interval>,
+    start_time: f64,
+    pub visible: bool,
+}
+
+impl Timer {
+    /// A timer counting up, or down from the talk length. It is only shown
+    /// from the start when there is a talk length to count down from.
+    pub fn new(talk_length: Option<Interval>) -> Self {
+        Self {
+            talk_length,
+            start_time: get_time(),
+            visible: talk_length.is_some(),
+        }
+    }
+
+    /// Start timing the talk from now.
+    pub fn restart(&mut self) {
+        self.start_time = get_time();
+    }
+
+    pub fn draw(&self, scale: f32) {
+        if !self.visible {
+            return;
+        }
+        let elapsed = get_time() - self.start_time;
+        let (seconds, color) = match self.talk_length {
+            Some(talk_length) => {
+                let remaining = talk_length.seconds - elapsed;
+                let color = if remaining < 0. {
+                    OVERTIME_COLOR
+                } else if remaining < talk_length.seconds * WARNING_FRACTION {
+                    WARNING_COLOR
+                } else {
+                    TEXT_COLOR
+                };
+                (remaining, color)
+            }
+            None => (elapsed, TEXT_COLOR),
+        };
+        let font_size = FONT_SIZE * scale;
+        let margin = TIMER_MARGIN * scale;
+        let text = format_time(seconds);
+        let dimensions = measure_text(&text, None, font_size as u16, 1.);
+        draw_text(
+            &text,
+            margin,
+            margin + dimensions.offset_y,
+            font_size,
+            color,
+        );
+    }
+}
+
+/// Minutes and seconds, with a minus sign when the talk has run over.
+fn format_time(seconds: f64) -> String {
+    let sign = if seconds < 0. { "-" } else { "" };
+    let seconds = seconds.abs() as u64;
+    format!("{}{:02}:{:02}", sign, seconds / 60, seconds % 60)
+}
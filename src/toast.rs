@@ -0,0 +1,59 @@
+use macroquad::prelude::*;
+
+const FONT_SIZE: f32 = 20.;
+/// Seconds that the toast is shown, the last of them fading out.
+const TOAST_SECONDS: f64 = 2.5;
+const FADE_SECONDS: f64 = 0.5;
+const TOAST_PADDING: f32 = 12.;
+/// Distance between the toast and the bottom of the screen.
+const TOAST_MARGIN: f32 = 32.;
+const BACKGROUND_COLOR: Color = Color::new(0.1, 0.1, 0.12, 0.85);
+
+/// A short message at the bottom of the screen that disappears by itself,
+/// to confirm that something happened.
+pub struct Toast {
+    message: String,
+    start_time: f64,
+}
+
+impl Toast {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            start_time: get_time(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        get_time() - self.start_time >= TOAST_SECONDS
+    }
+
+    pub fn draw(&self, scale: f32) {
+        let remaining = TOAST_SECONDS - (get_time() - self.start_time);
+        let alpha = (remaining / FADE_SECONDS).clamp(0., 1.) as f32;
+        let font_size = FONT_SIZE * scale;
+        let padding = TOAST_PADDING * scale;
+        let dimensions = measure_text(&self.message, None, font_size as u16, 1.);
+        let width = dimensions.width + padding * 2.;
+        let height = font_size + padding * 2.;
+        let x = (screen_width() - width) / 2.;
+        let y = screen_height() - height - TOAST_MARGIN * scale;
+        draw_rectangle(
+            x,
+            y,
+            width,
+            height,
+            Color {
+                a: BACKGROUND_COLOR.a * alpha,
+                ..BACKGROUND_COLOR
+            },
+        );
+        draw_text(
+            &self.message,
+            x + padding,
+            y + (height + dimensions.offset_y) / 2.,
+            font_size,
+            Color::new(1., 1., 1., alpha),
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use {clap::ValueEnum, macroquad::prelude::*};
+
+/// How much the zoom transition grows the previous file and shrinks the
+/// next one.
+const ZOOM_AMOUNT: f32 = 0.3;
+
+/// Animation when switching to another file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Transition {
+    /// Switch instantly
+    #[default]
+    None,
+    /// Fade out the previous file while the next fades in
+    Fade,
+    /// Slide the next file in from the side, pushing the previous one out
+    Slide,
+    /// Zoom out of the previous file and into the next
+    Zoom,
+}
+
+impl Transition {
+    /// The transition named in the theme, like `fade`.
+    pub fn parse(name: &str) -> Self {
+        <Self as ValueEnum>::from_str(name, true).unwrap_or_else(|_| {
+            if !name.is_empty() {
+                warn!("Unknown transition: {}", name);
+            }
+            Self::None
+        })
+    }
+}
+
+/// A transition in progress. The previous file is kept as a snapshot, and
+/// the next file is drawn into a render target each frame, so that both can
+/// be moved and faded as a whole.
+pub struct SlideTransition {
+    transition: Transition,
+    duration: f32,
+    /// 1 when moving to a later file, -1 when moving back.
+    direction: f32,
+    start_time: f64,
+    from: RenderTarget,
+    to: RenderTarget,
+}
+
+impl SlideTransition {
+    pub fn new(transition: Transition, duration: f32, direction: f32) -> Self {
+        let target = || {
+            let render_target = render_target(screen_width() as u32, screen_height() as u32);
+            render_target.texture.set_filter(FilterMode::Linear);
+            render_target
+        };
+        Self {
+            transition,
+            duration,
+            direction,
+            start_time: get_time(),
+            from: target(),
+            to: target(),
+        }
+    }
+
+    /// Camera to draw the snapshot of the previous file with, at the zoom it
+    /// was shown at.
+    pub fn from_camera(&self, zoom: f32) -> Camera2D {
+        camera(&self.from, zoom)
+    }
+
+    /// Camera to draw the next file with while the transition runs.
+    pub fn to_camera(&self, zoom: f32) -> Camera2D {
+        camera(&self.to, zoom)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress() >= 1.
+    }
+
+    /// Eased fraction of the transition that has passed.
+    fn progress(&self) -> f32 {
+        let t = ((get_time() - self.start_time) as f32 / self.duration.max(f32::EPSILON)).min(1.);
+        t * t * (3. - 2. * t)
+    }
+
+    /// Draw the previous and next files on screen.
+    pub fn draw(&self) {
+        let t = self.progress();
+        let (width, height) = (screen_width(), screen_height());
+        let (from, to) = match self.transition {
+            Transition::None => ((0., 1., 0.), (0., 1., 1.)),
+            Transition::Fade => ((0., 1., 1. - t), (0., 1., t)),
+            Transition::Slide => (
+                (-self.direction * t * width, 1., 1.),
+                (self.direction * (1. - t) * width, 1., 1.),
+            ),
+            Transition::Zoom => (
+                (0., 1. + ZOOM_AMOUNT * t, 1. - t),
+                (0., 1. - ZOOM_AMOUNT * (1. - t), t),
+            ),
+        };
+        for (target, (offset, scale, alpha)) in [(&self.from, from), (&self.to, to)] {
+            let size = vec2(width, height) * scale;
+            draw_texture_ex(
+                &target.texture,
+                offset + (width - size.x) / 2.,
+                (height - size.y) / 2.,
+                Color::new(1., 1., 1., alpha),
+                DrawTextureParams {
+                    dest_size: Some(size),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+/// Camera that draws into the render target like on screen. A positive y
+/// zoom puts the top of the screen in the first row of the texture, so it
+/// isn't drawn upside down.
+fn camera(render_target: &RenderTarget, zoom: f32) -> Camera2D {
+    let (width, height) = (screen_width(), screen_height());
+    Camera2D {
+        target: vec2(width / (2. * zoom), height / (2. * zoom)),
+        zoom: vec2(2. * zoom / width, 2. * zoom / height),
+        render_target: Some(render_target.clone()),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,162 @@
+use {
+    crate::codebox::{CodeLine, Span, SpanKind},
+    macroquad::prelude::*,
+    syntect::{
+        highlighting::{self, FontStyle, Highlighter, Theme},
+        parsing::Scope,
+    },
+    tree_sitter_highlight::{HighlightConfiguration, HighlightEvent},
+};
+
+/// Highlight names used by the tree-sitter queries, with the TextMate scope
+/// used to look up their color in the syntax highlighting theme.
+const HIGHLIGHT_SCOPES: &[(&str, &str)] = &[
+    ("attribute", "entity.other.attribute-name"),
+    ("comment", "comment"),
+    ("constant", "constant"),
+    ("constant.builtin", "constant.language"),
+    ("constructor", "entity.name.type"),
+    ("escape", "constant.character.escape"),
+    ("function", "entity.name.function"),
+    ("function.builtin", "support.function"),
+    ("function.macro", "support.function"),
+    ("function.method", "entity.name.function"),
+    ("keyword", "keyword"),
+    ("label", "entity.name.label"),
+    ("number", "constant.numeric"),
+    ("operator", "keyword.operator"),
+    ("property", "variable.other.member"),
+    ("punctuation", "punctuation"),
+    ("string", "string"),
+    ("type", "entity.name.type"),
+    ("type.builtin", "storage.type"),
+    ("variable", "variable"),
+    ("variable.builtin", "variable.language"),
+    ("variable.parameter", "variable.parameter"),
+];
+
+/// Highlight the code with a tree-sitter grammar, using the colors of the
+/// syntax highlighting theme. Returns None if there is no grammar for the
+/// language.
+pub fn highlight(language: &str, code: &str, theme: &Theme) -> Option<Vec<CodeLine>> {
+    let config = configuration(language)?;
+    let styles = highlight_styles(theme);
+    let default_color = syntect_color(
+        theme
+            .settings
+            .foreground
+            .unwrap_or(highlighting::Color::WHITE),
+    );
+
+    let mut highlighter = tree_sitter_highlight::Highlighter::new();
+    let events = highlighter
+        .highlight(&config, code.as_bytes(), None, |_| None)
+        .map_err(|e| warn!("Couldn't highlight code with tree-sitter: {}", e))
+        .ok()?;
+
+    let mut lines = vec![Vec::new()];
+    // Color, style and kind of the innermost highlight, a highlight inside a
+    // comment or string is still part of it
+    let default_style = (default_color, FontStyle::empty(), SpanKind::Code);
+    let mut stack = vec![default_style];
+    for event in events {
+        match event.ok()? {
+            HighlightEvent::HighlightStart(highlight) => {
+                let kind = match SpanKind::from_scope(HIGHLIGHT_SCOPES[highlight.0].1) {
+                    SpanKind::Code => stack.last().map(|(_, _, kind)| *kind).unwrap_or_default(),
+                    kind => kind,
+                };
+                let (color, style) = styles[highlight.0];
+                stack.push((color, style, kind));
+            }
+            HighlightEvent::HighlightEnd => {
+                stack.pop();
+            }
+            HighlightEvent::Source { start, end } => {
+                let (color, style, kind) = *stack.last().unwrap_or(&default_style);
+                for (index, text) in code[start..end].split('\n').enumerate() {
+                    if index > 0 {
+                        lines.push(Vec::new());
+                    }
+                    let text = text.trim_end_matches('\r').to_string();
+                    if !text.is_empty() {
+                        if let Some(spans) = lines.last_mut() {
+                            spans.push(Span {
+                                text,
+                                color,
+                                kind,
+                                style,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    // Like syntect, the newline at the end of the code doesn't start a new line
+    if code.ends_with('\n') {
+        lines.pop();
+    }
+
+    Some(
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, spans)| CodeLine {
+                number: index + 1,
+                continuation: false,
+                background: None,
+                spans,
+            })
+            .collect(),
+    )
+}
+
+/// Grammar and queries for the language, given as a name or file extension.
+fn configuration(language: &str) -> Option<HighlightConfiguration> {
+    let (grammar, highlights, injections, locals) = match language.to_lowercase().as_str() {
+        "rs" | "rust" => (
+            tree_sitter_rust::language(),
+            tree_sitter_rust::HIGHLIGHT_QUERY,
+            tree_sitter_rust::INJECTIONS_QUERY,
+            "",
+        ),
+        "py" | "python" => (
+            tree_sitter_python::language(),
+            tree_sitter_python::HIGHLIGHT_QUERY,
+            "",
+            "",
+        ),
+        "js" | "mjs" | "javascript" => (
+            tree_sitter_javascript::language(),
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+            tree_sitter_javascript::INJECTION_QUERY,
+            tree_sitter_javascript::LOCALS_QUERY,
+        ),
+        _ => return None,
+    };
+    let mut config = HighlightConfiguration::new(grammar, highlights, injections, locals)
+        .map_err(|e| warn!("Couldn't load tree-sitter queries: {:?}", e))
+        .ok()?;
+    let names: Vec<&str> = HIGHLIGHT_SCOPES.iter().map(|(name, _)| *name).collect();
+    config.configure(&names);
+    Some(config)
+}
+
+/// Color and font style of each highlight name, in the same order as
+/// `HIGHLIGHT_SCOPES`.
+fn highlight_styles(theme: &Theme) -> Vec<(Color, FontStyle)> {
+    let highlighter = Highlighter::new(theme);
+    HIGHLIGHT_SCOPES
+        .iter()
+        .map(|(_, scope)| {
+            let scope = Scope::new(scope).expect("Highlight scopes are valid");
+            let style = highlighter.style_for_stack(&[scope]);
+            (syntect_color(style.foreground), style.font_style)
+        })
+        .collect()
+}
+
+fn syntect_color(color: highlighting::Color) -> Color {
+    Color::from_rgba(color.r, color.g, color.b, color.a)
+}
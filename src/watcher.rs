@@ -0,0 +1,46 @@
+use {
+    macroquad::prelude::*,
+    std::{fs, path::PathBuf, time::SystemTime},
+};
+
+/// Seconds between checks of the modification time of the file.
+const POLL_INTERVAL: f64 = 0.5;
+
+/// Polls a file on disk to detect when it has been modified.
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: f64,
+}
+
+impl FileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = Self::modified(&path);
+        Self {
+            path,
+            modified,
+            last_check: get_time(),
+        }
+    }
+
+    /// Returns true if the file has been modified since the last time it changed.
+    pub fn has_changed(&mut self) -> bool {
+        if get_time() - self.last_check < POLL_INTERVAL {
+            return false;
+        }
+        self.last_check = get_time();
+        let modified = Self::modified(&self.path);
+        if modified != self.modified {
+            debug!("File changed: {}", self.path.display());
+            self.modified = modified;
+            return true;
+        }
+        false
+    }
+
+    fn modified(path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
@@ -0,0 +1,150 @@
+use {
+    crate::code_theme::{hex_color, CodeTheme},
+    macroquad::prelude::*,
+};
+
+/// Distance between the watermark and the edges of the screen.
+const WATERMARK_MARGIN: f32 = 16.;
+/// Space between the image and the text of the watermark.
+const WATERMARK_GAP: f32 = 8.;
+
+/// Corner of the screen to draw the watermark in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn parse(position: &str) -> Self {
+        match position {
+            "top-left" => Corner::TopLeft,
+            "top-right" => Corner::TopRight,
+            "bottom-left" => Corner::BottomLeft,
+            _ => Corner::BottomRight,
+        }
+    }
+}
+
+/// Text and a small image, like a logo or an attribution, drawn in a corner
+/// of the screen on top of the code.
+pub struct Watermark {
+    text: String,
+    image: Option<(String, Texture2D)>,
+    corner: Corner,
+    font_size: f32,
+    color: Color,
+    scale: f32,
+}
+
+impl Watermark {
+    /// Load the watermark set in the code theme, if it has any text or image.
+    pub async fn load(code_theme: &CodeTheme, scale: f32) -> Option<Self> {
+        let image = match code_theme.watermark_image.is_empty() {
+            true => None,
+            false => match load_texture(&code_theme.watermark_image).await {
+                Ok(texture) => Some((code_theme.watermark_image.clone(), texture)),
+                Err(e) => {
+                    warn!(
+                        "Couldn't load watermark image {}: {}",
+                        code_theme.watermark_image, e
+                    );
+                    None
+                }
+            },
+        };
+        if code_theme.watermark_text.is_empty() && image.is_none() {
+            return None;
+        }
+        Some(Self {
+            text: code_theme.watermark_text.clone(),
+            image,
+            corner: Corner::parse(&code_theme.watermark_position),
+            font_size: code_theme.watermark_font_size * scale,
+            color: hex_color(&code_theme.watermark_color),
+            scale,
+        })
+    }
+
+    /// Path of the watermark image, if there is one.
+    pub fn image_path(&self) -> Option<&str> {
+        self.image.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Where to draw the image, and the baseline of the text, on a canvas of
+    /// the given size.
+    pub fn layout(&self, width: f32, height: f32) -> (Option<Rect>, Vec2) {
+        let image_size = self
+            .image
+            .as_ref()
+            .map(|(_, texture)| texture.size() * self.scale)
+            .unwrap_or_default();
+        let text_size = measure_text(&self.text, None, self.font_size as u16, 1.0);
+        let gap = match self.image.is_some() && !self.text.is_empty() {
+            true => WATERMARK_GAP * self.scale,
+            false => 0.,
+        };
+        let content_width = image_size.x + gap + text_size.width;
+        let content_height = image_size.y.max(text_size.height);
+
+        let margin = WATERMARK_MARGIN * self.scale;
+        let x = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => margin,
+            Corner::TopRight | Corner::BottomRight => width - margin - content_width,
+        };
+        let y = match self.corner {
+            Corner::TopLeft | Corner::TopRight => margin,
+            Corner::BottomLeft | Corner::BottomRight => height - margin - content_height,
+        };
+
+        let image_rect = self.image.as_ref().map(|_| {
+            Rect::new(
+                x,
+                y + (content_height - image_size.y) / 2.,
+                image_size.x,
+                image_size.y,
+            )
+        });
+        let text_top = y + (content_height - text_size.height) / 2.;
+        let baseline = vec2(x + image_size.x + gap, text_top + text_size.offset_y);
+        (image_rect, baseline)
+    }
+
+    /// Draw the watermark in its corner of a canvas of the given size.
+    pub fn draw(&self, width: f32, height: f32) {
+        let (image_rect, baseline) = self.layout(width, height);
+        if let (Some((_, texture)), Some(rect)) = (&self.image, image_rect) {
+            draw_texture_ex(
+                texture,
+                rect.x,
+                rect.y,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(rect.size()),
+                    ..Default::default()
+                },
+            );
+        }
+        draw_text(
+            &self.text,
+            baseline.x,
+            baseline.y,
+            self.font_size,
+            self.color,
+        );
+    }
+}